@@ -43,6 +43,12 @@ pub enum AlertType {
     TrailerUndocked,
     RackSpace,
     LowTopRackSpace,
+    DoorOffline,
+    DoorOnline,
+    SensorThreshold,
+    StuckSensor,
+    WmsPlcDiscrepancy,
+    ConditionMatched,
 }
 
 /// Represents an alert with all its associated information
@@ -138,6 +144,33 @@ impl fmt::Display for Alert {
             },
             AlertType::DockReady => format!("✅ DOCK READY: Door {}", self.door_name),
             AlertType::TrailerUndocked => format!("🚚 TRAILER UNDOCKED: Door {}", self.door_name),
+            AlertType::DoorOffline => format!("📡 PLC OFFLINE: Door {} is not responding, sensor values are unknown", self.door_name),
+            AlertType::DoorOnline => format!("✅ PLC BACK ONLINE: Door {}", self.door_name),
+            AlertType::SensorThreshold => {
+                let info = |key: &str| self.additional_info.get(key).map_or("Unknown", |s| s);
+                format!(
+                    "📏 SENSOR OUT OF RANGE: Door {} - {} is {} ({} {})",
+                    self.door_name, info("sensor"), info("value"), info("operator"), info("threshold")
+                )
+            },
+            AlertType::StuckSensor => {
+                let info = |key: &str| self.additional_info.get(key).map_or("Unknown", |s| s);
+                format!(
+                    "🔧 MAINTENANCE: Door {} - sensor {} has not changed in {} cycles (stuck at {})",
+                    self.door_name, info("sensor"), info("idle cycles"), info("value")
+                )
+            },
+            AlertType::WmsPlcDiscrepancy => {
+                let info = |key: &str| self.additional_info.get(key).map_or("Unknown", |s| s);
+                format!(
+                    "🔀 WMS/PLC MISMATCH: Door {} - {} since {} ({})",
+                    self.door_name, info("pattern"), info("since"), info("details")
+                )
+            },
+            AlertType::ConditionMatched => {
+                let info = |key: &str| self.additional_info.get(key).map_or("Unknown", |s| s);
+                format!("📋 {}: Door {} - {}", info("rule"), self.door_name, info("message"))
+            },
             AlertType::RackSpace => {
                 let send_info = self.additional_info.get("info");
                 if send_info.is_none() {
@@ -282,6 +315,30 @@ impl AlertManager {
                 format!("trailer_undocked_{}", alert.door_name),
                 self.settings.trailer_undocked.repeat_interval,
             ),
+            AlertType::DoorOffline => (
+                format!("door_offline_{}", alert.door_name),
+                DEFAULT_REPEAT_INTERVAL,
+            ),
+            AlertType::DoorOnline => (
+                format!("door_online_{}", alert.door_name),
+                DEFAULT_REPEAT_INTERVAL,
+            ),
+            AlertType::SensorThreshold => (
+                format!("sensor_threshold_{}_{}", alert.door_name, alert.additional_info.get("sensor").unwrap_or(&"unknown".to_string())),
+                DEFAULT_REPEAT_INTERVAL,
+            ),
+            AlertType::StuckSensor => (
+                format!("stuck_sensor_{}_{}", alert.door_name, alert.additional_info.get("sensor").unwrap_or(&"unknown".to_string())),
+                DEFAULT_REPEAT_INTERVAL,
+            ),
+            AlertType::WmsPlcDiscrepancy => (
+                format!("wms_plc_discrepancy_{}_{}", alert.door_name, alert.additional_info.get("pattern").unwrap_or(&"unknown".to_string())),
+                DEFAULT_REPEAT_INTERVAL,
+            ),
+            AlertType::ConditionMatched => (
+                format!("condition_{}_{}", alert.additional_info.get("rule").unwrap_or(&"unknown".to_string()), alert.door_name),
+                DEFAULT_REPEAT_INTERVAL,
+            ),
             AlertType::LowTopRackSpace => (
                 format!("low_top_rack_space_{}", alert.additional_info.get("plant").unwrap_or(&"unknown".to_string())),
                 DEFAULT_REPEAT_INTERVAL,
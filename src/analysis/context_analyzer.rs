@@ -1,9 +1,19 @@
 use chrono::{Duration, NaiveDateTime};
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock, RwLock};
+use async_trait::async_trait;
+use futures::future::join_all;
+use tokio::sync::mpsc;
+use log::warn;
 use serde::{Deserialize, Serialize};
 
+use crate::config::Settings;
 use crate::models::{DoorState, DockDoorEvent, DockDoor, DbInsert};
 use crate::models::consolidated_dock_event::ConsolidatedDockEvent;
+use crate::rules::LoadedRule;
+use crate::services::db::DatabaseService;
+use crate::state_management::door_state_repository::DoorStateRepository;
+use crate::utils::clock::Clock;
+use crate::utils::shutdown::ShutdownCoordinator;
 
 /// The result of applying an analysis rule to a dock door event
 #[derive(Debug, Clone)]
@@ -102,6 +112,54 @@ pub enum AlertType {
         shipment_id: Option<String>,
         timestamp: NaiveDateTime,
     },
+    /// The PLC controlling a door has stopped answering
+    DoorOffline {
+        door_name: String,
+        shipment_id: Option<String>,
+        consecutive_failures: u32,
+        last_good_read: Option<NaiveDateTime>,
+        reason: Option<String>,
+    },
+    /// The PLC controlling a door is answering again after an outage
+    DoorOnline {
+        door_name: String,
+        shipment_id: Option<String>,
+        offline_duration: Option<Duration>,
+    },
+    /// A sensor value has crossed a configured threshold
+    SensorThreshold {
+        door_name: String,
+        shipment_id: Option<String>,
+        sensor: String,
+        value: f64,
+        operator: String,
+        threshold: f64,
+        message: Option<String>,
+    },
+    /// A sensor has not changed over several cycles of door activity and may be faulty
+    StuckSensor {
+        door_name: String,
+        shipment_id: Option<String>,
+        sensor: String,
+        value: Option<String>,
+        idle_cycles: u32,
+        last_change: NaiveDateTime,
+    },
+    /// What the WMS reports for a door has contradicted its sensors for longer than the grace period
+    WmsPlcDiscrepancy {
+        door_name: String,
+        shipment_id: Option<String>,
+        pattern: String,
+        details: String,
+        since: NaiveDateTime,
+    },
+    /// The conditions of a rule configured in `rules.json` started to hold for a door
+    ConditionMatched {
+        door_name: String,
+        shipment_id: Option<String>,
+        rule: String,
+        message: String,
+    },
 }
 
 /// Represents different types of log entries that can be generated by analysis rules
@@ -314,6 +372,84 @@ pub enum LogEntry {
         previous_state: Option<String>,
         previous_state_dttm: Option<NaiveDateTime>,
     },
+    /// Logs a sensor value crossing a configured threshold in either direction
+    SensorThreshold {
+        log_dttm: NaiveDateTime,
+        plant: String,
+        door_name: String,
+        shipment_id: Option<String>,
+        event_type: String,
+        success: bool,
+        notes: String,
+        severity: i32,
+        previous_state: Option<String>,
+        previous_state_dttm: Option<NaiveDateTime>,
+    },
+    /// Logs a door state change that the door state transition table does not allow
+    DoorStateAnomaly {
+        log_dttm: NaiveDateTime,
+        plant: String,
+        door_name: String,
+        shipment_id: Option<String>,
+        event_type: String,
+        success: bool,
+        notes: String,
+        severity: i32,
+        previous_state: Option<String>,
+        previous_state_dttm: Option<NaiveDateTime>,
+    },
+    /// Logs a contradiction between the WMS and the sensors of a door, or its resolution
+    WmsPlcDiscrepancy {
+        log_dttm: NaiveDateTime,
+        plant: String,
+        door_name: String,
+        shipment_id: Option<String>,
+        event_type: String,
+        success: bool,
+        notes: String,
+        severity: i32,
+        previous_state: Option<String>,
+        previous_state_dttm: Option<NaiveDateTime>,
+    },
+    /// Logs the conditions of a rule configured in `rules.json` starting to hold for a door
+    ConditionMatched {
+        log_dttm: NaiveDateTime,
+        plant: String,
+        door_name: String,
+        shipment_id: Option<String>,
+        event_type: String,
+        success: bool,
+        notes: String,
+        severity: i32,
+        previous_state: Option<String>,
+        previous_state_dttm: Option<NaiveDateTime>,
+    },
+    /// Logs a sensor being reported as stuck, or changing again afterwards
+    StuckSensor {
+        log_dttm: NaiveDateTime,
+        plant: String,
+        door_name: String,
+        shipment_id: Option<String>,
+        event_type: String,
+        success: bool,
+        notes: String,
+        severity: i32,
+        previous_state: Option<String>,
+        previous_state_dttm: Option<NaiveDateTime>,
+    },
+    /// Logs a door's PLC going offline or coming back online
+    PlcConnectivity {
+        log_dttm: NaiveDateTime,
+        plant: String,
+        door_name: String,
+        shipment_id: Option<String>,
+        event_type: String,
+        success: bool,
+        notes: String,
+        severity: i32,
+        previous_state: Option<String>,
+        previous_state_dttm: Option<NaiveDateTime>,
+    },
     WmsEvent {
         log_dttm: NaiveDateTime,
         plant: String,
@@ -330,44 +466,193 @@ pub enum LogEntry {
     },
 }
 
+/// The services shared by every analysis rule, so that rules can query the database, read the time,
+/// wait or look up the latest state of a door without creating their own
+#[derive(Clone)]
+pub struct RuleContext {
+    /// The database service for rules that read or write the databases
+    pub db_service: Arc<DatabaseService>,
+    /// The clock rules read the time from and wait on
+    pub clock: Arc<dyn Clock>,
+    /// The application settings
+    pub settings: Arc<Settings>,
+    /// The repository holding the latest state of every door, for rules that wait for a door to settle
+    pub door_repository: Arc<DoorStateRepository>,
+    /// Where rules send the results they produce after returning from `apply`, such as once a door has settled,
+    /// to be handled like the results of an event
+    pub deferred_results: mpsc::Sender<DeferredResults>,
+    /// The coordinator the tasks rules run in the background are registered with, so that shutdown waits for them
+    pub shutdown: Arc<ShutdownCoordinator>,
+    /// The JSON form of the door being analyzed, shared by the rules of one evaluation; the analyzer gives every
+    /// evaluation a fresh one
+    pub door_json: DoorJson,
+}
+
+/// The JSON form of a door, serialized the first time a rule asks for it
+#[derive(Clone, Default)]
+pub struct DoorJson(Arc<OnceLock<Result<serde_json::Value, String>>>);
+
+impl DoorJson {
+    /// Returns the JSON form of the door, serializing it if no rule of the evaluation has yet
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(&Value)` with the door's JSON form
+    /// * `Err(&str)` describing why the door could not be serialized
+    pub fn get(&self, dock_door: &DockDoor) -> Result<&serde_json::Value, &str> {
+        self.0.get_or_init(|| serde_json::to_value(dock_door).map_err(|e| e.to_string()))
+            .as_ref()
+            .map_err(String::as_str)
+    }
+}
+
+/// Results a rule produced in the background after it finished analyzing an event
+#[derive(Debug)]
+pub struct DeferredResults {
+    /// The plant of the door the results are about
+    pub plant_id: String,
+    /// The name of the door the results are about
+    pub dock_name: String,
+    /// The results to handle
+    pub results: Vec<AnalysisResult>,
+}
+
 /// Defines the interface for analysis rules that can be applied to dock door events
+///
+/// Rules run on the tokio runtime and must not block it: waiting and I/O go through the `RuleContext` and are awaited.
+/// As the events are analyzed one at a time, a rule that has to wait spawns the wait and sends its results through
+/// the context's `deferred_results`, and writes that need no answer are spawned rather than awaited.
+#[async_trait]
 pub trait AnalysisRule: Send + Sync {
     /// Applies the analysis rule to a dock door and an event, potentially generating `AnalysisResult`s
-    fn apply(&self, dock_door: &DockDoor, event: &DockDoorEvent) -> Vec<AnalysisResult>;
+    async fn apply(&self, dock_door: &DockDoor, event: &DockDoorEvent, context: &RuleContext) -> Vec<AnalysisResult>;
+
+    /// Applies the analysis rule to a dock door on the analyzer's periodic tick, for rules that watch for time
+    /// passing without an event; generates nothing by default
+    async fn tick(&self, _dock_door: &DockDoor, _context: &RuleContext) -> Vec<AnalysisResult> {
+        Vec::new()
+    }
+
+    /// Returns the name the rule is reported by, its type name by default
+    fn name(&self) -> &str {
+        let type_name = std::any::type_name::<Self>();
+        type_name.rsplit("::").next().unwrap_or(type_name)
+    }
+}
+
+/// An analysis rule with the time it is given to analyze an event
+#[derive(Clone)]
+struct ScheduledRule {
+    /// The rule to apply
+    rule: Arc<dyn AnalysisRule>,
+    /// The time after which the rule is abandoned for the event
+    timeout: std::time::Duration,
 }
 
 /// Analyzes dock door events in context using a set of rules
-#[derive(Default, Clone)]
+///
+/// The rules loaded from the rules file can be replaced while events are being analyzed; an event is analyzed with
+/// the rule set in place when its analysis started.
 pub struct ContextAnalyzer {
-    /// The collection of analysis rules to apply
-    rules: Vec<Arc<dyn AnalysisRule>>,
+    /// The analysis rules added in code, which are kept when the loaded rules are replaced
+    rules: Vec<ScheduledRule>,
+    /// The analysis rules loaded from the rules file, replaced as a whole when the file changes
+    loaded_rules: RwLock<Arc<Vec<ScheduledRule>>>,
+    /// The context the rules are applied with
+    context: RuleContext,
+    /// The time given to rules added without their own timeout
+    default_timeout: std::time::Duration,
 }
 
 impl ContextAnalyzer {
     /// Creates a new `ContextAnalyzer` with no rules initially
-    pub fn new() -> Self {
-        ContextAnalyzer { rules: Vec::new() }
+    ///
+    /// # Arguments
+    ///
+    /// * `context` - The `RuleContext` the rules are applied with
+    /// * `default_timeout` - The time given to rules added without their own timeout
+    pub fn new(context: RuleContext, default_timeout: std::time::Duration) -> Self {
+        ContextAnalyzer { rules: Vec::new(), loaded_rules: RwLock::new(Arc::new(Vec::new())), context, default_timeout }
     }
 
-    /// Adds an analysis rule to the analyzer
+    /// Adds an analysis rule to the analyzer, with the default timeout
     pub fn add_rule(&mut self, rule: Arc<dyn AnalysisRule>) {
-        self.rules.push(rule);
+        self.add_rule_with_timeout(rule, None);
+    }
+
+    /// Adds an analysis rule to the analyzer, with its own timeout or the default one
+    pub fn add_rule_with_timeout(&mut self, rule: Arc<dyn AnalysisRule>, timeout: Option<std::time::Duration>) {
+        let timeout = timeout.unwrap_or(self.default_timeout);
+        self.rules.push(ScheduledRule { rule, timeout });
+    }
+
+    /// Replaces the rules loaded from the rules file with a new set, in one step
+    ///
+    /// Analyses already running finish with the previous set. The loaded rules are applied before the rules added in code.
+    ///
+    /// # Arguments
+    ///
+    /// * `rules` - The rules loaded from the rules file, with their own timeouts if any
+    ///
+    /// # Returns
+    ///
+    /// The number of rules in the new set
+    pub fn replace_loaded_rules(&self, rules: Vec<LoadedRule>) -> usize {
+        let scheduled: Vec<ScheduledRule> = rules.into_iter()
+            .map(|loaded| ScheduledRule { rule: loaded.rule, timeout: loaded.timeout.unwrap_or(self.default_timeout) })
+            .collect();
+        let count = scheduled.len();
+        *self.loaded_rules.write().unwrap() = Arc::new(scheduled);
+        count
     }
 
     /// Analyzes a dock door event using the registered rules
+    ///
+    /// The rules run concurrently, each within its timeout; a rule that does not finish in time is abandoned
+    /// with a warning and contributes no results. The results of the loaded rules come first, followed by those
+    /// of the rules added in code, each in the order the rules were added.
     pub async fn analyze(&self, dock_door: &DockDoor, event: &DockDoorEvent) -> Vec<AnalysisResult> {
-        let results: Vec<AnalysisResult>  = self.rules
-            .iter()
-            .flat_map(|rule| {
-                let rule_results = rule.apply(dock_door, event);
-                rule_results
-            })
-            .collect();
-        results
+        self.run_rules(dock_door, Some(event)).await
+    }
+
+    /// Applies the registered rules to a dock door on the periodic tick
+    ///
+    /// The rules run like they do for an event, so rules whose conditions depend on time passing are evaluated even
+    /// when the door has no events.
+    pub async fn tick(&self, dock_door: &DockDoor) -> Vec<AnalysisResult> {
+        self.run_rules(dock_door, None).await
+    }
+
+    /// Runs the registered rules concurrently for an event, or for the periodic tick if there is none
+    async fn run_rules(&self, dock_door: &DockDoor, event: Option<&DockDoorEvent>) -> Vec<AnalysisResult> {
+        let loaded_rules = Arc::clone(&self.loaded_rules.read().unwrap());
+        let context = RuleContext { door_json: DoorJson::default(), ..self.context.clone() };
+        let context = &context;
+        let runs = loaded_rules.iter().chain(self.rules.iter()).map(|scheduled| async move {
+            let run = match event {
+                Some(event) => scheduled.rule.apply(dock_door, event, context),
+                None => scheduled.rule.tick(dock_door, context),
+            };
+            match tokio::time::timeout(scheduled.timeout, run).await {
+                Ok(results) => results,
+                Err(_) => {
+                    let trigger = event.map_or_else(|| "the periodic tick".to_string(), |event| format!("event {:?}", event));
+                    warn!("Rule {} did not finish within {:?} on door {}, skipping it for {}",
+                        scheduled.rule.name(), scheduled.timeout, dock_door.dock_name, trigger);
+                    Vec::new()
+                },
+            }
+        });
+        join_all(runs).await.into_iter().flatten().collect()
     }
 }
 
 /// Creates a default `ContextAnalyzer` with no rules
-pub fn create_default_analyzer() -> ContextAnalyzer {
-    ContextAnalyzer::new()
+///
+/// # Arguments
+///
+/// * `context` - The `RuleContext` the rules are applied with
+pub fn create_default_analyzer(context: RuleContext) -> ContextAnalyzer {
+    let default_timeout = std::time::Duration::from_secs(context.settings.analysis.rule_timeout_secs.max(1));
+    ContextAnalyzer::new(context, default_timeout)
 }
\ No newline at end of file
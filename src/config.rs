@@ -11,11 +11,16 @@
 use serde::{Deserialize, Serialize};
 use config::{Config, Environment, File};
 use std::{env, fmt};
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 use secrecy::{Secret, ExposeSecret};
 use log::{debug};
 use url::Url;
 use crate::errors::DockManagerError;
+use crate::models::{
+    DockLockState, DoorPosition, LoadingStatus, ModbusAddress, PlcAddress, SensorType, SensorValue, TrailerState, ValueMapping,
+    DEFAULT_MODBUS_PORT, DEFAULT_MODBUS_UNIT_ID,
+};
 
 /// Represents the complete set of configuration settings for the IQX Dock Manager.
 /// It's populated by reading from various configuration sources and provides convenient access to the settings throughout the application.
@@ -35,7 +40,31 @@ pub struct Settings {
     pub plants: Vec<PlantSettings>,
     pub alerts: AlertSettings,
     pub monitoring: MonitoringSettings,
-    pub batch_size: usize
+    pub batch_size: usize,
+    /// Settings for persisting the door states across restarts
+    #[serde(default)]
+    pub snapshot: SnapshotSettings,
+    /// Settings for the journal of dock door events
+    #[serde(default)]
+    pub journal: JournalSettings,
+    /// Settings for the subscribers of the dock door event bus
+    #[serde(default)]
+    pub event_bus: EventBusSettings,
+    /// Settings for the outbox holding the database inserts that could not be written
+    #[serde(default)]
+    pub outbox: OutboxSettings,
+    /// Settings for the graceful shutdown of the service
+    #[serde(default)]
+    pub shutdown: ShutdownSettings,
+    /// Settings for the cross-check of the WMS door status against the PLC sensors
+    #[serde(default)]
+    pub reconciler: ReconcilerSettings,
+    /// Settings for the plant-level registry following shipments across doors
+    #[serde(default)]
+    pub shipment_registry: ShipmentRegistrySettings,
+    /// Settings for applying the analysis rules to the door events
+    #[serde(default)]
+    pub analysis: AnalysisSettings,
 }
 
 /// Represents the configuration settings for a specific plant
@@ -49,6 +78,8 @@ pub struct PlantSettings {
     pub lgv_wms_database: LgvWmsDatabaseSettings,
     /// Configuration for dock doors and their associated PLC tags at this plant
     pub dock_doors: DockDoorSettings,
+    /// Overrides of the global PLC polling settings for this plant
+    pub plc: Option<PlcOverrides>,
 }
 
 /// # Database Settings
@@ -181,7 +212,150 @@ pub struct PlcSettings {
     /// The timeout (in milliseconds) for PLC communication operations
     pub timeout_ms: u64,
     /// The maximum number of retries allowed for failed PLC communication attempts
-    pub max_retries: u64
+    pub max_retries: u64,
+    /// The delay (in milliseconds) before the first retry of a failed read; it doubles with every further retry
+    #[serde(default = "default_retry_backoff_ms")]
+    pub retry_backoff_ms: u64,
+    /// The number of consecutive failed polling cycles after which a door's PLC is considered offline (defaults to 3)
+    #[serde(default = "default_offline_after_failures")]
+    pub offline_after_failures: u32,
+    /// Whether the bit tags of a door are fetched with one multi-element read per data file (defaults to true)
+    #[serde(default = "default_block_reads")]
+    pub block_reads: bool,
+    /// The source used to obtain sensor values (defaults to reading the PLCs through libplctag)
+    #[serde(default)]
+    pub source: SensorSourceKind,
+    /// Scenarios played by the simulated sensor source
+    #[serde(default)]
+    pub simulation: SimulationSettings,
+    /// Settings for the replay sensor source
+    pub replay: Option<ReplaySettings>,
+    /// When set, every polled sensor value is appended to this file so it can be replayed later
+    pub record_path: Option<PathBuf>,
+}
+
+impl PlcSettings {
+    /// Resolves the polling settings for a plant, applying the plant's overrides to the global values
+    ///
+    /// # Arguments
+    ///
+    /// * `plant`: The plant whose polling settings are resolved
+    ///
+    /// # Returns
+    ///
+    /// The `PlcTiming` in effect for the plant
+    pub fn timing_for(&self, plant: &PlantSettings) -> PlcTiming {
+        let overrides = plant.plc.clone().unwrap_or_default();
+        PlcTiming {
+            poll_interval_secs: overrides.poll_interval_secs.unwrap_or(self.poll_interval_secs),
+            timeout_ms: overrides.timeout_ms.unwrap_or(self.timeout_ms),
+            max_retries: overrides.max_retries.unwrap_or(self.max_retries),
+            retry_backoff_ms: overrides.retry_backoff_ms.unwrap_or(self.retry_backoff_ms),
+        }
+    }
+}
+
+/// Per-plant overrides of the global PLC polling settings; any value left unset falls back to `PlcSettings`
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct PlcOverrides {
+    /// The interval (in seconds) at which the plant's PLCs are polled
+    pub poll_interval_secs: Option<u64>,
+    /// The timeout (in milliseconds) for the plant's PLC communication operations
+    pub timeout_ms: Option<u64>,
+    /// The maximum number of retries for the plant's failed reads
+    pub max_retries: Option<u64>,
+    /// The delay (in milliseconds) before the first retry of a failed read
+    pub retry_backoff_ms: Option<u64>,
+}
+
+/// The polling settings in effect for a single plant, after applying its overrides
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlcTiming {
+    /// The interval (in seconds) at which the plant's PLCs are polled
+    pub poll_interval_secs: u64,
+    /// The timeout (in milliseconds) for PLC communication operations
+    pub timeout_ms: u64,
+    /// The maximum number of retries for a failed read
+    pub max_retries: u64,
+    /// The delay (in milliseconds) before the first retry of a failed read
+    pub retry_backoff_ms: u64,
+}
+
+/// Retries start after a quarter of a second unless configured otherwise
+fn default_retry_backoff_ms() -> u64 {
+    250
+}
+
+/// A door is reported offline after three failed polling cycles in a row unless configured otherwise
+fn default_offline_after_failures() -> u32 {
+    3
+}
+
+/// Block reads are enabled unless explicitly turned off
+fn default_block_reads() -> bool {
+    true
+}
+
+/// The kinds of sensor sources that can be selected in the PLC settings
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SensorSourceKind {
+    /// Read sensor values from the PLCs over EtherNet/IP
+    #[default]
+    Plctag,
+    /// Produce sensor values from the configured simulation scenarios
+    Simulated,
+    /// Play back sensor values previously recorded to a file
+    Replay,
+}
+
+/// Holds the scenarios played by the simulated sensor source
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct SimulationSettings {
+    /// Values reported for tags that no scenario step has set (tags not listed here report 0);
+    /// booleans, integers and floats are accepted
+    #[serde(default)]
+    pub default_values: HashMap<String, SensorValue>,
+    /// The scripted scenarios, at most one per door
+    #[serde(default)]
+    pub scenarios: Vec<SimulationScenario>,
+}
+
+/// A scripted sequence of sensor values for a single dock door
+#[derive(Debug, Deserialize, Clone)]
+pub struct SimulationScenario {
+    /// The plant the door belongs to
+    pub plant_id: String,
+    /// The name of the dock door the scenario drives
+    pub door_name: String,
+    /// Whether the scenario starts over after its last step
+    #[serde(default)]
+    pub repeat: bool,
+    /// The steps of the scenario, played in order
+    pub steps: Vec<SimulationStep>,
+}
+
+/// A single step of a simulation scenario
+#[derive(Debug, Deserialize, Clone)]
+pub struct SimulationStep {
+    /// The number of polling cycles the step lasts
+    pub cycles: u32,
+    /// Tag values set by this step; tags not listed keep the value from the previous step
+    #[serde(default)]
+    pub values: HashMap<String, SensorValue>,
+    /// Whether the door's PLC is unreachable during this step, in which case no values are reported
+    #[serde(default)]
+    pub offline: bool,
+}
+
+/// Holds the configuration settings for the replay sensor source
+#[derive(Debug, Deserialize, Clone)]
+pub struct ReplaySettings {
+    /// The file of recorded `PlcVal` records, one JSON object per line
+    pub path: PathBuf,
+    /// Whether playback starts over once the end of the recording is reached
+    #[serde(default)]
+    pub loop_playback: bool,
 }
 
 /// Holds the configuration settings for application logging
@@ -245,6 +419,133 @@ pub struct DockDoorSettings {
     pub dock_plc_tags: Vec<DockPlcTag>,
 }
 
+impl DockDoorSettings {
+    /// Lists the problems in a plant's dock door configuration
+    ///
+    /// Door names must be unique and every door needs an IP address. Each tag name must be a known
+    /// `SensorType`, written in upper case, and configured only once. Each tag is checked against every door
+    /// that reads it, in the format of that door: its `address` must be a PCCC data table address (file,
+    /// element and bit) for MicroLogix, SLC 500, PLC-5 and Logix PCCC doors, its Modbus address must parse for
+    /// Modbus TCP doors, and its symbolic address must not be empty for the other families.
+    ///
+    /// # Arguments
+    ///
+    /// * `plant_id`: The ID of the plant, used in the reported problems
+    ///
+    /// # Returns
+    ///
+    /// A description of every problem found, empty if the configuration is valid
+    pub fn problems(&self, plant_id: &str) -> Vec<String> {
+        let mut problems = Vec::new();
+        let mut door_names = HashSet::new();
+
+        for door in &self.dock_door_config {
+            if !door_names.insert(door.dock_name.as_str()) {
+                problems.push(format!("plant {}: door {} is configured more than once", plant_id, door.dock_name));
+            }
+            if door.dock_ip.trim().is_empty() {
+                problems.push(format!("plant {}: door {} has no IP address", plant_id, door.dock_name));
+            }
+            if door.modbus_port == Some(0) {
+                problems.push(format!("plant {}: door {} has an invalid Modbus port 0", plant_id, door.dock_name));
+            }
+        }
+
+        let doors_reading = |format: TagAddressFormat| -> Vec<&str> {
+            self.dock_door_config.iter()
+                .filter(|door| door.tag_address_format() == format)
+                .map(|door| door.dock_name.as_str())
+                .collect()
+        };
+        let data_table_doors = doors_reading(TagAddressFormat::DataTable);
+        let symbolic_doors = doors_reading(TagAddressFormat::Symbolic);
+        let modbus_doors = doors_reading(TagAddressFormat::Modbus);
+
+        let describe = |error: DockManagerError| match error {
+            DockManagerError::ConfigError(message) => message,
+            other => other.to_string(),
+        };
+        let at = |doors: &[&str]| match doors {
+            [door] => format!("door {}", door),
+            doors => format!("doors {}", doors.join(", ")),
+        };
+
+        let mut tag_names = HashSet::new();
+        for tag in &self.dock_plc_tags {
+            let name = &tag.tag_name;
+            if !tag_names.insert(name.as_str()) {
+                problems.push(format!("plant {}: tag {} is configured more than once", plant_id, name));
+            }
+            match name.parse::<SensorType>() {
+                Err(_) => problems.push(format!("plant {}: unknown sensor name '{}'", plant_id, name)),
+                Ok(_) if *name != name.to_uppercase() => {
+                    problems.push(format!("plant {}: sensor name '{}' must be written as '{}'", plant_id, name, name.to_uppercase()));
+                }
+                Ok(_) => {}
+            }
+
+            if tag.active_low && !matches!(tag.data_type, None | Some(PlcElementType::Bool)) {
+                problems.push(format!("plant {}: tag {} is an analog tag and cannot be active-low", plant_id, name));
+            }
+            if let Some(deadband) = tag.deadband.filter(|deadband| !deadband.is_finite() || *deadband < 0.0) {
+                problems.push(format!("plant {}: tag {} has an invalid deadband {}", plant_id, name, deadband));
+            }
+
+            for key in tag.value_map.iter().flat_map(|value_map| value_map.keys()) {
+                match key.parse::<i64>() {
+                    Err(_) => problems.push(format!("plant {}: tag {} maps '{}', which is not an integer value", plant_id, name, key)),
+                    Ok(value) if tag.data_type == Some(PlcElementType::Bool) && !matches!(value, 0 | 1) => {
+                        problems.push(format!("plant {}: tag {} is a bool tag and cannot read value {}", plant_id, name, key));
+                    }
+                    Ok(_) => {}
+                }
+            }
+
+            if tag.address.trim().is_empty() {
+                problems.push(format!("plant {}: tag {} has no address", plant_id, name));
+                continue;
+            }
+
+            if !data_table_doors.is_empty() {
+                match tag.address.parse::<PlcAddress>() {
+                    Ok(address) if address.bit.is_some() && !matches!(tag.data_type, None | Some(PlcElementType::Bool)) => {
+                        problems.push(format!(
+                            "plant {}: tag {} reads bit address {} as a multi-bit value at {}",
+                            plant_id, name, address, at(&data_table_doors)
+                        ));
+                    }
+                    Ok(_) => {}
+                    Err(e) => problems.push(format!("plant {}: tag {} at {}: {}", plant_id, name, at(&data_table_doors), describe(e))),
+                }
+            }
+
+            if !symbolic_doors.is_empty() && tag.symbolic_address.as_deref().is_some_and(|address| address.trim().is_empty()) {
+                problems.push(format!("plant {}: tag {} has an empty symbolic address, read at {}", plant_id, name, at(&symbolic_doors)));
+            }
+
+            if !modbus_doors.is_empty() {
+                let address = tag.modbus_address.as_deref().unwrap_or(&tag.address);
+                if let Err(e) = address.parse::<ModbusAddress>() {
+                    problems.push(format!("plant {}: tag {} at {}: {}", plant_id, name, at(&modbus_doors), describe(e)));
+                }
+            }
+        }
+
+        problems
+    }
+}
+
+/// The formats tag addresses are written in, depending on the door reading them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TagAddressFormat {
+    /// PCCC data table addresses, e.g. "B9:0/9"
+    DataTable,
+    /// Symbolic tag names
+    Symbolic,
+    /// Modbus addresses, e.g. "coil:12"
+    Modbus,
+}
+
 /// Represents the configuration for a single dock door
 #[derive(Debug, Deserialize, Clone)]
 pub struct DockDoorConfig {
@@ -252,6 +553,114 @@ pub struct DockDoorConfig {
     pub dock_name: String,
     /// The IP address of the PLC controlling the dock door
     pub dock_ip: String,
+    /// The family of the PLC controlling the dock door (defaults to MicroLogix)
+    pub plc_kind: Option<PlcFamily>,
+    /// The routing path to the PLC, e.g. "1,0" for a ControlLogix CPU in slot 0 (defaults depend on the PLC family)
+    pub plc_path: Option<String>,
+    /// The element type of the door's tags (defaults to one byte per element)
+    pub element_type: Option<PlcElementType>,
+    /// The protocol used to talk to the door's PLC (defaults to EtherNet/IP)
+    pub protocol: Option<PlcProtocol>,
+    /// The TCP port of a Modbus TCP controller (defaults to 502)
+    pub modbus_port: Option<u16>,
+    /// The Modbus unit identifier of the controller (defaults to 1)
+    pub modbus_unit_id: Option<u8>,
+}
+
+impl DockDoorConfig {
+    /// Returns the format the door's tag addresses are written in
+    fn tag_address_format(&self) -> TagAddressFormat {
+        match self.protocol.unwrap_or_default() {
+            PlcProtocol::ModbusTcp => TagAddressFormat::Modbus,
+            PlcProtocol::EtherNetIp if self.plc_kind.unwrap_or_default().uses_data_table_addresses() => TagAddressFormat::DataTable,
+            PlcProtocol::EtherNetIp => TagAddressFormat::Symbolic,
+        }
+    }
+
+    /// Identifies the controller the door is read from: its IP address, plus the port and unit identifier
+    /// for Modbus TCP doors
+    pub fn endpoint(&self) -> String {
+        match self.protocol.unwrap_or_default() {
+            PlcProtocol::EtherNetIp => self.dock_ip.clone(),
+            PlcProtocol::ModbusTcp => format!(
+                "{}:{}/{}",
+                self.dock_ip,
+                self.modbus_port.unwrap_or(DEFAULT_MODBUS_PORT),
+                self.modbus_unit_id.unwrap_or(DEFAULT_MODBUS_UNIT_ID)
+            ),
+        }
+    }
+}
+
+/// The protocols used to read dock door PLCs
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PlcProtocol {
+    /// EtherNet/IP (CIP or PCCC), read through libplctag
+    #[default]
+    #[serde(rename = "ethernet_ip")]
+    EtherNetIp,
+    /// Modbus TCP; tag addresses refer to coils, discrete inputs or registers
+    #[serde(rename = "modbus_tcp")]
+    ModbusTcp,
+}
+
+/// The PLC families that dock doors can be connected to
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum PlcFamily {
+    /// MicroLogix controllers, addressed with PCCC data table addresses (e.g. "B9:0/9")
+    #[default]
+    MicroLogix,
+    /// SLC 500 controllers, addressed with PCCC data table addresses
+    #[serde(alias = "slc")]
+    Slc500,
+    /// PLC-5 controllers, addressed with PCCC data table addresses
+    Plc5,
+    /// ControlLogix controllers accessed through PCCC data table addresses
+    LogixPccc,
+    /// ControlLogix controllers, addressed with symbolic tag names
+    ControlLogix,
+    /// CompactLogix controllers, addressed with symbolic tag names
+    CompactLogix,
+    /// Micro800 controllers, addressed with symbolic tag names
+    Micro800,
+    /// Omron NJ/NX controllers, addressed with symbolic tag names
+    Omron,
+}
+
+impl PlcFamily {
+    /// Returns `true` if the family is addressed with PCCC data table addresses (e.g. "B9:0/9")
+    /// rather than symbolic tag names
+    pub fn uses_data_table_addresses(&self) -> bool {
+        matches!(self, PlcFamily::MicroLogix | PlcFamily::Slc500 | PlcFamily::Plc5 | PlcFamily::LogixPccc)
+    }
+}
+
+/// The element types PLC tags can be read as
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PlcElementType {
+    /// A single bit, reported as `true` or `false`
+    Bool,
+    /// An 8-bit integer
+    Sint,
+    /// A 16-bit integer
+    Int,
+    /// A 32-bit integer
+    Dint,
+    /// A 32-bit floating point value
+    Real,
+}
+
+impl PlcElementType {
+    /// Returns the size of one element of this type, in bytes
+    pub fn size(&self) -> usize {
+        match self {
+            PlcElementType::Bool | PlcElementType::Sint => 1,
+            PlcElementType::Int => 2,
+            PlcElementType::Dint | PlcElementType::Real => 4,
+        }
+    }
 }
 
 /// Represents the configuration of a PLC tag associated with a dock door
@@ -261,6 +670,84 @@ pub struct DockPlcTag {
     pub tag_name: String,
     /// The address of the PLC tag in the PLC's memory (e.g., "B9:0/9")
     pub address: String,
+    /// The data type of the tag's value (defaults to the door's `element_type`, or a single byte reported as an integer)
+    ///
+    /// Analog sensors such as `TRAILER_ANGLE` are declared as `int`, `dint` or `real` so their measurement is kept.
+    #[serde(default)]
+    pub data_type: Option<PlcElementType>,
+    /// Debounce applied to changes of the tag's value (changes are accepted on the first differing read when unset)
+    #[serde(default)]
+    pub debounce: Option<DebounceSettings>,
+    /// The largest difference between two analog reads that still counts as the same value, so that noise on a
+    /// measurement is not reported as a change (analog values must match exactly when unset)
+    #[serde(default)]
+    pub deadband: Option<f64>,
+    /// The address used for doors read over Modbus TCP (defaults to `address`), e.g. "coil:12" or "40001"
+    #[serde(default)]
+    pub modbus_address: Option<String>,
+    /// The tag name used for doors whose PLC family is addressed with symbolic tag names (defaults to `address`),
+    /// e.g. "Dock_Trailer_Present"
+    #[serde(default)]
+    pub symbolic_address: Option<String>,
+    /// Whether the input is wired active-low, i.e. reads 0 while the sensor is active
+    #[serde(default)]
+    pub active_low: bool,
+    /// Maps raw values read from the tag to their meaning, e.g. `{ "0": active, "1": inactive }`;
+    /// takes precedence over `active_low` for the values it lists
+    #[serde(default)]
+    pub value_map: Option<HashMap<String, SensorMeaning>>,
+}
+
+impl DockPlcTag {
+    /// Returns the address the tag is read from at the given door: its `modbus_address` for Modbus TCP doors,
+    /// its `symbolic_address` for doors addressed with symbolic tag names, and its `address` otherwise
+    pub fn address_for(&self, door: &DockDoorConfig) -> &str {
+        match door.protocol.unwrap_or_default() {
+            PlcProtocol::ModbusTcp => self.modbus_address.as_deref().unwrap_or(&self.address),
+            PlcProtocol::EtherNetIp => self.ethernet_ip_address(door.plc_kind.unwrap_or_default()),
+        }
+    }
+
+    /// Returns the address the tag is read from over EtherNet/IP at a door of the given PLC family
+    pub fn ethernet_ip_address(&self, family: PlcFamily) -> &str {
+        if family.uses_data_table_addresses() {
+            &self.address
+        } else {
+            self.symbolic_address.as_deref().unwrap_or(&self.address)
+        }
+    }
+
+    /// Returns how raw values read from the tag are translated before the door state is derived
+    pub fn value_mapping(&self) -> ValueMapping {
+        ValueMapping {
+            active_low: self.active_low,
+            value_map: self.value_map.clone().unwrap_or_default(),
+        }
+    }
+}
+
+/// The meaning of a raw value read from a PLC tag
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SensorMeaning {
+    /// The sensor is active (e.g. the door is open or the restraint is engaged)
+    Active,
+    /// The sensor is inactive
+    Inactive,
+}
+
+/// Represents how long a changed sensor value must hold before the change is accepted
+///
+/// A change is accepted once the new value has been read in `min_samples` consecutive polling cycles and has held for
+/// at least `min_stable_ms`; a change that reverts before then is discarded as a glitch.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DebounceSettings {
+    /// The minimum time, in milliseconds, a new value must hold before it is accepted
+    #[serde(default)]
+    pub min_stable_ms: u64,
+    /// The number of consecutive reads that must report the new value before it is accepted
+    #[serde(default)]
+    pub min_samples: u32,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -296,6 +783,332 @@ pub struct MonitoringThresholds {
     pub repeat_interval: u64,  // in seconds
 }
 
+/// Holds the settings for snapshots of the door states, which let docking times, loading status and shipment
+/// history survive a restart
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct SnapshotSettings {
+    /// The file the snapshots are written to; snapshots are disabled when not set
+    #[serde(default)]
+    pub path: Option<PathBuf>,
+    /// How often a snapshot is written, in seconds (defaults to 60); one is also written on shutdown
+    #[serde(default = "default_snapshot_interval_secs")]
+    pub interval_secs: u64,
+    /// Snapshots older than this, in seconds, are not restored on startup (defaults to 3600)
+    #[serde(default = "default_snapshot_max_age_secs")]
+    pub max_age_secs: u64,
+}
+
+impl Default for SnapshotSettings {
+    fn default() -> Self {
+        Self {
+            path: None,
+            interval_secs: default_snapshot_interval_secs(),
+            max_age_secs: default_snapshot_max_age_secs(),
+        }
+    }
+}
+
+/// Holds the settings for the append-only journal every dock door event is written to
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct JournalSettings {
+    /// The file the journal is written to; the journal is disabled when not set
+    #[serde(default)]
+    pub path: Option<PathBuf>,
+    /// The size in bytes after which the journal file is rotated (defaults to 64 MiB)
+    #[serde(default = "default_journal_max_file_bytes")]
+    pub max_file_bytes: u64,
+    /// The number of rotated journal files kept next to the current one (defaults to 10)
+    #[serde(default = "default_journal_max_files")]
+    pub max_files: usize,
+    /// Whether the door states are rebuilt from the journal on startup, after any snapshot has been restored
+    #[serde(default)]
+    pub replay_on_startup: bool,
+}
+
+impl Default for JournalSettings {
+    fn default() -> Self {
+        Self {
+            path: None,
+            max_file_bytes: default_journal_max_file_bytes(),
+            max_files: default_journal_max_files(),
+            replay_on_startup: false,
+        }
+    }
+}
+
+/// Holds the settings for the subscribers of the dock door event bus
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct EventBusSettings {
+    /// Buffer settings by subscriber name, overriding the ones the subscriber asks for
+    #[serde(default)]
+    pub subscribers: HashMap<String, SubscriberSettings>,
+}
+
+/// The buffer of a single event bus subscriber
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub struct SubscriberSettings {
+    /// The number of events buffered for the subscriber
+    pub capacity: usize,
+    /// What happens to events published while the buffer is full
+    #[serde(default)]
+    pub overflow: OverflowPolicy,
+}
+
+/// What happens to an event published while a subscriber's buffer is full
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum OverflowPolicy {
+    /// The publisher waits until the subscriber has made room, so no event is lost
+    #[default]
+    Block,
+    /// The oldest buffered event is dropped to make room
+    DropOldest,
+    /// The published event is dropped
+    DropNewest,
+}
+
+/// Holds the settings for the outboxes that keep `DOCK_DOOR_EVENTS` and `TRAILER_VISITS` inserts while the database
+/// is unreachable
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct OutboxSettings {
+    /// The file the pending inserts are kept in; they are only kept in memory when not set
+    #[serde(default)]
+    pub path: Option<PathBuf>,
+    /// The delay in seconds before the first retry after a failed insert (defaults to 5)
+    #[serde(default = "default_outbox_retry_initial_secs")]
+    pub retry_initial_secs: u64,
+    /// The longest delay in seconds between retries (defaults to 300)
+    #[serde(default = "default_outbox_retry_max_secs")]
+    pub retry_max_secs: u64,
+    /// The number of times a record the database rejects is tried before it is dead-lettered (defaults to 3)
+    #[serde(default = "default_outbox_reject_attempts")]
+    pub reject_attempts: u32,
+    /// The largest number of records kept in the outbox; the oldest ones beyond it are dead-lettered
+    /// (defaults to 100000)
+    #[serde(default = "default_outbox_max_pending")]
+    pub max_pending: usize,
+    /// The JSON lines file dead-lettered records are appended to; they are only logged when not set
+    #[serde(default)]
+    pub dead_letter_path: Option<PathBuf>,
+    /// The file the completed trailer visits waiting to be stored are kept in; they are only kept in memory when
+    /// not set
+    #[serde(default)]
+    pub visit_path: Option<PathBuf>,
+    /// The JSON lines file dead-lettered trailer visits are appended to; they are only logged when not set
+    #[serde(default)]
+    pub visit_dead_letter_path: Option<PathBuf>,
+}
+
+impl Default for OutboxSettings {
+    fn default() -> Self {
+        Self {
+            path: None,
+            retry_initial_secs: default_outbox_retry_initial_secs(),
+            retry_max_secs: default_outbox_retry_max_secs(),
+            reject_attempts: default_outbox_reject_attempts(),
+            max_pending: default_outbox_max_pending(),
+            dead_letter_path: None,
+            visit_path: None,
+            visit_dead_letter_path: None,
+        }
+    }
+}
+
+/// Holds the settings for the graceful shutdown of the service
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ShutdownSettings {
+    /// The number of seconds the background tasks get to finish and flush their work after a shutdown
+    /// signal, before they are aborted (defaults to 30)
+    #[serde(default = "default_shutdown_deadline_secs")]
+    pub deadline_secs: u64,
+}
+
+impl Default for ShutdownSettings {
+    fn default() -> Self {
+        Self { deadline_secs: default_shutdown_deadline_secs() }
+    }
+}
+
+/// Holds the settings for the reconciler that cross-checks what the WMS reports for each door against its sensors
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ReconcilerSettings {
+    /// The number of seconds between two cross-checks (defaults to 60)
+    #[serde(default = "default_reconciler_interval_secs")]
+    pub interval_secs: u64,
+    /// The combinations of WMS and sensor state that contradict each other
+    #[serde(default = "default_contradiction_patterns")]
+    pub patterns: Vec<ContradictionPattern>,
+}
+
+impl Default for ReconcilerSettings {
+    fn default() -> Self {
+        Self {
+            interval_secs: default_reconciler_interval_secs(),
+            patterns: default_contradiction_patterns(),
+        }
+    }
+}
+
+/// Holds the settings for applying the analysis rules to the door events
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct AnalysisSettings {
+    /// The number of seconds a rule may take to analyze an event before it is abandoned, unless the rule
+    /// sets its own `timeout_secs` (defaults to 10)
+    #[serde(default = "default_rule_timeout_secs")]
+    pub rule_timeout_secs: u64,
+    /// The JSON file the analysis rules are loaded from (defaults to `src/config/rules.json`)
+    #[serde(default = "default_rules_path")]
+    pub rules_path: PathBuf,
+    /// The number of seconds between checks of the rules file for changes, which are applied without a restart;
+    /// 0 disables reloading (defaults to 5)
+    #[serde(default = "default_rules_reload_interval_secs")]
+    pub rules_reload_interval_secs: u64,
+    /// The number of seconds between periodic ticks, on which the rules are applied to every door without an event
+    /// so that conditions on durations are noticed once they are reached; 0 disables the ticks (defaults to 30)
+    #[serde(default = "default_rule_tick_secs")]
+    pub rule_tick_secs: u64,
+}
+
+impl Default for AnalysisSettings {
+    fn default() -> Self {
+        Self {
+            rule_timeout_secs: default_rule_timeout_secs(),
+            rules_path: default_rules_path(),
+            rules_reload_interval_secs: default_rules_reload_interval_secs(),
+            rule_tick_secs: default_rule_tick_secs(),
+        }
+    }
+}
+
+/// Holds the settings for the registry that follows every shipment across the doors it is assigned to
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ShipmentRegistrySettings {
+    /// The number of seconds a shipment is kept after it was last assigned to a door or received a WMS event
+    /// (defaults to 7 days)
+    #[serde(default = "default_shipment_retention_secs")]
+    pub retention_secs: u64,
+}
+
+impl Default for ShipmentRegistrySettings {
+    fn default() -> Self {
+        Self { retention_secs: default_shipment_retention_secs() }
+    }
+}
+
+/// A combination of WMS and sensor state that should not occur at a door
+///
+/// A door matches the pattern when every condition that is set holds; conditions that are not set are ignored.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct ContradictionPattern {
+    /// The name the discrepancy is reported under
+    pub name: String,
+    /// The loading statuses reported by the WMS; any status matches when empty
+    #[serde(default)]
+    pub loading_statuses: Vec<LoadingStatus>,
+    /// Whether the WMS has a shipment assigned to the door
+    #[serde(default)]
+    pub shipment_assigned: Option<bool>,
+    /// The trailer state derived from the sensors
+    #[serde(default)]
+    pub trailer_state: Option<TrailerState>,
+    /// The dock lock state derived from the sensors
+    #[serde(default)]
+    pub dock_lock_state: Option<DockLockState>,
+    /// The door position derived from the sensors
+    #[serde(default)]
+    pub door_position: Option<DoorPosition>,
+    /// The number of seconds the contradiction must last before it is reported (defaults to 300)
+    #[serde(default = "default_contradiction_grace_period_secs")]
+    pub grace_period_secs: u64,
+}
+
+fn default_reconciler_interval_secs() -> u64 {
+    60
+}
+
+fn default_contradiction_grace_period_secs() -> u64 {
+    300
+}
+
+fn default_contradiction_patterns() -> Vec<ContradictionPattern> {
+    vec![
+        ContradictionPattern {
+            name: "loading_without_trailer".to_string(),
+            loading_statuses: vec![LoadingStatus::Loading],
+            shipment_assigned: None,
+            trailer_state: Some(TrailerState::Undocked),
+            dock_lock_state: None,
+            door_position: None,
+            grace_period_secs: 120,
+        },
+        ContradictionPattern {
+            name: "idle_with_restrained_trailer".to_string(),
+            loading_statuses: vec![LoadingStatus::Idle],
+            shipment_assigned: None,
+            trailer_state: Some(TrailerState::Docked),
+            dock_lock_state: Some(DockLockState::Engaged),
+            door_position: None,
+            grace_period_secs: 3600,
+        },
+    ]
+}
+
+fn default_rule_timeout_secs() -> u64 {
+    10
+}
+
+fn default_rules_path() -> PathBuf {
+    PathBuf::from("src/config/rules.json")
+}
+
+fn default_rules_reload_interval_secs() -> u64 {
+    5
+}
+
+fn default_rule_tick_secs() -> u64 {
+    30
+}
+
+fn default_shipment_retention_secs() -> u64 {
+    7 * 24 * 3600
+}
+
+fn default_shutdown_deadline_secs() -> u64 {
+    30
+}
+
+fn default_outbox_retry_initial_secs() -> u64 {
+    5
+}
+
+fn default_outbox_retry_max_secs() -> u64 {
+    300
+}
+
+fn default_outbox_reject_attempts() -> u32 {
+    3
+}
+
+fn default_outbox_max_pending() -> usize {
+    100_000
+}
+
+fn default_journal_max_file_bytes() -> u64 {
+    64 * 1024 * 1024
+}
+
+fn default_journal_max_files() -> usize {
+    10
+}
+
+fn default_snapshot_interval_secs() -> u64 {
+    60
+}
+
+fn default_snapshot_max_age_secs() -> u64 {
+    3600
+}
+
 
 /// # Settings Initialization
 ///
@@ -313,10 +1126,13 @@ impl Settings {
     ///
     /// The `CONFIG_DIR` environment variable can be used to specify the directory where the YAML configuration files are located (defaults to "src/config").
     ///
+    /// The loaded settings are checked with `validate`, so a misconfigured dock door stops the service from starting.
+    ///
     /// # Returns
     ///
     /// * `Ok(Settings)`: If the settings were loaded and constructed successfully
-    /// * `Err(DockManagerError)`: If there was an error during the loading or construction process
+    /// * `Err(DockManagerError)`: If there was an error during the loading or construction process,
+    ///   or the dock door configuration is invalid
     pub fn new() -> Result<Self, DockManagerError> {
         let run_mode = env::var("RUN_MODE").unwrap_or_else(|_| "development".into());
         let config_dir = env::var("CONFIG_DIR").unwrap_or_else(|_| "src/config".into());
@@ -337,6 +1153,7 @@ impl Settings {
         }
 
         s.batch_size = 1;
+        s.validate()?;
 
         Ok(s)
     }
@@ -344,6 +1161,42 @@ impl Settings {
     pub fn get_plant(&self, plant_id: &str) -> Option<&PlantSettings> {
         self.plants.iter().find(|plant| plant.plant_id == plant_id)
     }
+
+    /// Checks the dock door configuration of every plant
+    ///
+    /// Every door and tag is checked with `DockDoorSettings::problems`, and no two doors, in any plant, may share
+    /// the same controller. All problems found are reported together rather than stopping at the first one.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())`: If no problems were found
+    /// * `Err(DockManagerError::ConfigError)`: Listing every problem found
+    pub fn validate(&self) -> Result<(), DockManagerError> {
+        let mut problems = Vec::new();
+        let mut endpoints: HashMap<String, (&str, &str)> = HashMap::new();
+
+        for plant in &self.plants {
+            problems.extend(plant.dock_doors.problems(&plant.plant_id));
+
+            for door in &plant.dock_doors.dock_door_config {
+                if let Some((other_plant, other_door)) = endpoints.insert(door.endpoint(), (&plant.plant_id, &door.dock_name)) {
+                    problems.push(format!(
+                        "plant {}: door {} uses the same controller ({}) as door {} in plant {}",
+                        plant.plant_id, door.dock_name, door.endpoint(), other_door, other_plant
+                    ));
+                }
+            }
+        }
+
+        if problems.is_empty() {
+            return Ok(());
+        }
+        Err(DockManagerError::ConfigError(format!(
+            "{} problem(s) in the dock door configuration:\n  - {}",
+            problems.len(),
+            problems.join("\n  - ")
+        )))
+    }
 }
 
 /// Helper struct for deserializing secret strings from configuration
@@ -383,4 +1236,56 @@ impl fmt::Display for LgvWmsDatabaseSettings {
             self.host, self.port, self.username, self.database_name, self.app_name, self.win_auth, self.trusted
         )
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn dock_doors(doors: serde_json::Value, tags: serde_json::Value) -> DockDoorSettings {
+        serde_json::from_value(json!({ "dock_door_config": doors, "dock_plc_tags": tags })).unwrap()
+    }
+
+    #[test]
+    fn each_door_reads_the_address_of_its_own_plc_family() {
+        let settings = dock_doors(
+            json!([
+                { "dock_name": "DOOR_1", "dock_ip": "10.0.0.1", "plc_kind": "micrologix" },
+                { "dock_name": "DOOR_2", "dock_ip": "10.0.0.2", "plc_kind": "controllogix" },
+            ]),
+            json!([{ "tag_name": "TRAILER_AT_DOOR", "address": "B9:0/1", "symbolic_address": "Trailer_Present" }]),
+        );
+
+        assert!(settings.problems("P1").is_empty());
+        let tag = &settings.dock_plc_tags[0];
+        assert_eq!(tag.address_for(&settings.dock_door_config[0]), "B9:0/1");
+        assert_eq!(tag.address_for(&settings.dock_door_config[1]), "Trailer_Present");
+    }
+
+    #[test]
+    fn symbolic_addresses_are_not_parsed_as_data_table_addresses() {
+        let settings = dock_doors(
+            json!([{ "dock_name": "DOOR_1", "dock_ip": "10.0.0.1", "plc_kind": "controllogix" }]),
+            json!([{ "tag_name": "TRAILER_AT_DOOR", "address": "Trailer_Present" }]),
+        );
+
+        assert!(settings.problems("P1").is_empty());
+    }
+
+    #[test]
+    fn address_problems_name_the_doors_reading_the_tag() {
+        let settings = dock_doors(
+            json!([
+                { "dock_name": "DOOR_1", "dock_ip": "10.0.0.1", "plc_kind": "micrologix" },
+                { "dock_name": "DOOR_2", "dock_ip": "10.0.0.2", "plc_kind": "controllogix" },
+                { "dock_name": "DOOR_3", "dock_ip": "10.0.0.3", "protocol": "modbus_tcp" },
+            ]),
+            json!([{ "tag_name": "TRAILER_AT_DOOR", "address": "Trailer_Present", "modbus_address": "nope:1" }]),
+        );
+
+        let problems = settings.problems("P1");
+        assert_eq!(problems.len(), 2, "{:?}", problems);
+        assert!(problems[0].starts_with("plant P1: tag TRAILER_AT_DOOR at door DOOR_1: "), "{}", problems[0]);
+        assert!(problems[1].starts_with("plant P1: tag TRAILER_AT_DOOR at door DOOR_3: "), "{}", problems[1]);
+    }
+}
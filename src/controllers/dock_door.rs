@@ -3,7 +3,7 @@ use tokio::sync::Mutex;
 use log::info;
 
 use crate::config::Settings;
-use crate::services::plc::PlcService;
+use crate::services::plc::SensorSource;
 use crate::state_management::DockDoorStateManager;
 use crate::errors::{DockManagerError, DockManagerResult};
 use crate::event_handling::EventHandler;
@@ -14,8 +14,8 @@ use crate::models::{DbInsert, WmsEvent};
 pub struct DockDoorController {
     /// The application settings
     pub settings: Arc<Settings>,
-    /// The service for interacting with PLCs
-    pub plc_service: Arc<PlcService>,
+    /// The source of sensor values (PLCs, a simulator or a recording)
+    pub sensor_source: Arc<dyn SensorSource>,
     /// The state manager for tracking dock door states
     pub state_manager: Arc<DockDoorStateManager>,
     /// The event handler for processing dock door events
@@ -33,13 +33,13 @@ impl DockDoorController {
     /// # Arguments
     ///
     /// * `settings`: The application settings
-    /// * `plc_service`: The `PlcService` for PLC communication
+    /// * `sensor_source`: The `SensorSource` sensor values are polled from
     /// * `state_manager`: The `DockDoorStateManager` for managing door states
     /// * `event_handler`: The `EventHandler` for processing events
     /// * `db_service`: The `DatabaseService` for database interactions
     pub fn new(
         settings: Settings,
-        plc_service: PlcService,
+        sensor_source: Arc<dyn SensorSource>,
         state_manager: Arc<DockDoorStateManager>,
         event_handler: Arc<EventHandler>,
         db_service: DatabaseService
@@ -47,53 +47,49 @@ impl DockDoorController {
         info!("Initializing Dock Door Controller");
         Self {
             settings: Arc::new(settings),
-            plc_service: Arc::new(plc_service),
+            sensor_source,
             state_manager,
             event_handler,
             db_service: Arc::new(Mutex::new(db_service)),
         }
     }
 
-    /// Executes a single polling cycle, updating sensor data and processing events
+    /// Executes a single polling cycle for a plant, updating sensor data and processing events
     ///
-    /// 1. Polls sensors using the `plc_service`
-    /// 2. Updates the state manager with the new sensor values, which may generate events
-    /// 3. Sends the generated events to the `event_handler` for processing, which may result in database insert events
-    /// 4. Inserts the database events into the database using the `db_service`
-    /// 5. Logs informational messages about the process
+    /// 1. Polls the plant's sensors using the `sensor_source`
+    /// 2. Updates each door's PLC health from the read statuses, which may generate `DoorOffline`/`DoorOnline` events,
+    ///    then updates the state manager with the new sensor values, which may generate events
+    /// 3. Publishes the generated events on the event bus, where the `event_handler` processes them and inserts the
+    ///    resulting database events
+    /// 4. Logs informational messages about the process
+    ///
+    /// # Arguments
+    ///
+    /// * `plant_id`: The ID of the plant to poll
     ///
     /// # Returns
     ///
     /// * `Ok(())` if the polling cycle completes successfully
-    /// * `Err(DockManagerError)` if any errors occur during polling, state updates, event handling, or database insertion
-    pub async fn run_polling_cycle(&self) -> DockManagerResult<()> {
+    /// * `Err(DockManagerError)` if the plant is not configured, or if any errors occur during polling, state updates or event dispatching
+    pub async fn run_polling_cycle(&self, plant_id: &str) -> DockManagerResult<()> {
+        let plant = self.settings.get_plant(plant_id)
+            .ok_or_else(|| DockManagerError::PlantNotFound(plant_id.to_string()))?;
+
         let start = std::time::Instant::now();
-        info!("Starting PLC value polling...");
-        let plc_values = self.plc_service.poll_sensors(&self.settings).await?;
+        info!("Starting PLC value polling for plant {} from {} source...", plant_id, self.sensor_source.name());
+        let readings = self.sensor_source.poll_plant(&self.settings, plant).await?;
         info!("PLC value polling completed in {:?}", start.elapsed());
 
         let update_start = std::time::Instant::now();
         info!("Starting sensor update...");
-        let events = self.state_manager.update_sensors(plc_values).await?;
+        let mut events = self.state_manager.update_plc_health(readings.doors, self.settings.plc.offline_after_failures).await?;
+        events.extend(self.state_manager.update_sensors(readings.values).await?);
         info!("Sensor update completed in {:?}", update_start.elapsed());
 
         let event_start = std::time::Instant::now();
-        info!("DOCK DOOR CONTROLLER: Processing {} events...", events.len());
-        let mut db_events = Vec::new();
-        for event in events {
-            let new_db_events = self.event_handler.process_event(event).await?;
-            db_events.extend(new_db_events);
-        }
-        info!("Event processing completed in {:?}", event_start.elapsed());
-
-        if !db_events.is_empty() {
-            let db_start = std::time::Instant::now();
-            info!("Inserting {} DB events", db_events.len());
-            self.db_service.lock().await.insert_dock_door_events(db_events).await?;
-            info!("DB insertion completed in {:?}", db_start.elapsed());
-        } else {
-            info!("No DB events to insert");
-        }
+        info!("DOCK DOOR CONTROLLER: Dispatching {} events...", events.len());
+        self.state_manager.dispatch_events(events).await?;
+        info!("Event dispatching completed in {:?}", event_start.elapsed());
 
         info!("Full polling cycle completed in {:?}", start.elapsed());
         Ok(())
@@ -104,14 +100,14 @@ impl DockDoorController {
     /// 1. Gets all doors from the state manager
     /// 2. Filters doors that have an assigned shipment
     /// 3. For each such door, fetches WMS events concurrently using the `db_service`
-    /// 4. Collects all fetched WMS events and processes them using the `state_manager`, which may generate database insert events
-    /// 5. Inserts the database events into the database
-    /// 6. Logs informational messages about the process
+    /// 4. Collects all fetched WMS events and processes them using the `state_manager`, which converts them to door events
+    /// 5. Publishes the door events on the event bus, where the `event_handler` processes them and inserts the
+    ///    resulting database events
     ///
     /// # Returns
     ///
     /// * `Ok(())` if the WMS event update completes successfully
-    /// * `Err(DockManagerError)` if any errors occur during fetching WMS events, processing them, or inserting into the database
+    /// * `Err(DockManagerError)` if any errors occur during fetching WMS events, processing them, or dispatching the door events
     pub async fn update_wms_events(&self) -> DockManagerResult<()> {
         let db_service = Arc::clone(&self.db_service);
         let door_repository = self.state_manager.get_door_repository();
@@ -145,21 +141,9 @@ impl DockDoorController {
         // Process WMS events and get DockDoorEvents
         let dock_door_events = self.state_manager.process_wms_events(all_wms_events.clone()).await?;
 
-
-        // Process the resulting DockDoorEvents through the event handler
-        let mut db_events = Vec::new();
-
-        for event in dock_door_events {
-            let new_db_events = self.event_handler.process_event(event).await?;
-            info!("WMS Events Converted to DBInsert: {:?}", new_db_events);
-            db_events.extend(new_db_events);
-        }
-
-        // Insert the resulting database events
-        if !db_events.is_empty() {
-            info!("Inserting {} WMS event DB events", db_events.len());
-            self.db_service.lock().await.insert_dock_door_events(db_events).await?;
-        }
+        // Publish the resulting DockDoorEvents for the event handler
+        info!("Dispatching {} WMS events", dock_door_events.len());
+        self.state_manager.dispatch_events(dock_door_events).await?;
 
         Ok(())
     }
@@ -169,14 +153,13 @@ impl DockDoorController {
     /// 1. Iterates through all configured plants
     /// 2. Fetches WMS data (door statuses) for the current plant using the `db_service`
     /// 3. Updates the `state_manager` with the fetched WMS data, which may generate events
-    /// 4. Sends the generated events to the `event_handler` for processing, which may result in database insert events
-    /// 5. Inserts the database events into the database
-    /// 6. Logs informational messages about the process
+    /// 4. Publishes the generated events on the event bus, where the `event_handler` processes them and inserts the
+    ///    resulting database events
     ///
     /// # Returns
     ///
     /// * `Ok(())` if the WMS door status update completes successfully
-    /// * `Err(DockManagerError)` if any errors occur during fetching WMS data, updating the state manager, event handling, or database insertion
+    /// * `Err(DockManagerError)` if any errors occur during fetching WMS data, updating the state manager, or dispatching the events
     pub async fn update_wms_door_status(&self) -> DockManagerResult<()> {
         let mut all_events = Vec::new();
 
@@ -187,17 +170,27 @@ impl DockDoorController {
             all_events.extend(events);
         }
 
-        let mut db_events = Vec::new();
-        for event in all_events {
-            let new_db_events = self.event_handler.process_event(event.clone()).await?;
-            db_events.extend(new_db_events);
-        }
+        info!("Dispatching {} WMS door status events", all_events.len());
+        self.state_manager.dispatch_events(all_events).await?;
 
-        if !db_events.is_empty() {
-            info!("Inserting {} WMS door status DB events", db_events.len());
-            self.db_service.lock().await.insert_dock_door_events(db_events).await?;
-        }
+        Ok(())
+    }
 
+    /// Cross-checks the WMS state of every door against its sensors
+    ///
+    /// The discrepancy events generated by the state manager's reconciler are published on the event bus,
+    /// where the `event_handler` sends their alerts and inserts their database events
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` if the cross-check completes successfully
+    /// * `Err(DockManagerError)` if the events cannot be dispatched
+    pub async fn reconcile_wms_and_plc(&self) -> DockManagerResult<()> {
+        let events = self.state_manager.reconcile().await;
+        if !events.is_empty() {
+            info!("Dispatching {} WMS/PLC discrepancy events", events.len());
+            self.state_manager.dispatch_events(events).await?;
+        }
         Ok(())
     }
 
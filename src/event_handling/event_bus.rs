@@ -0,0 +1,262 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, RwLock};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use log::{debug, info};
+use serde::Serialize;
+use tokio::sync::Notify;
+use crate::config::{EventBusSettings, OverflowPolicy, SubscriberSettings};
+use crate::models::DockDoorEvent;
+
+/// The delivery statistics of a single event bus subscriber.
+#[derive(Debug, Clone, Serialize)]
+pub struct SubscriberStats {
+    /// The name the subscriber registered with.
+    pub name: String,
+    /// The number of events buffered for the subscriber.
+    pub capacity: usize,
+    /// What happens to events published while the buffer is full.
+    pub overflow: OverflowPolicy,
+    /// The number of events currently waiting in the buffer.
+    pub queued: usize,
+    /// The number of events put in the buffer.
+    pub delivered: u64,
+    /// The number of events dropped because the buffer was full.
+    pub dropped: u64,
+}
+
+/// The bounded buffer of events published to a single subscriber.
+struct SubscriberQueue {
+    /// The name the subscriber registered with.
+    name: String,
+    /// The buffer settings of the subscriber.
+    settings: SubscriberSettings,
+    /// The buffered events, oldest first.
+    buffer: Mutex<VecDeque<DockDoorEvent>>,
+    /// Signalled when an event is buffered or the queue is closed.
+    available: Notify,
+    /// Signalled when an event is taken from the buffer or the queue is closed.
+    space: Notify,
+    /// The number of events put in the buffer.
+    delivered: AtomicU64,
+    /// The number of events dropped because the buffer was full.
+    dropped: AtomicU64,
+    /// Whether the subscriber or the bus has gone away.
+    closed: AtomicBool,
+}
+
+impl SubscriberQueue {
+    /// Puts an event in the buffer, applying the overflow policy if it is full.
+    async fn push(&self, event: DockDoorEvent) {
+        loop {
+            let space = self.space.notified();
+            tokio::pin!(space);
+            {
+                let mut buffer = self.buffer.lock().unwrap();
+                space.as_mut().enable();
+                if self.closed.load(Ordering::SeqCst) {
+                    return;
+                }
+                if buffer.len() < self.settings.capacity.max(1) {
+                    buffer.push_back(event);
+                    drop(buffer);
+                    self.delivered.fetch_add(1, Ordering::Relaxed);
+                    self.available.notify_one();
+                    return;
+                }
+                match self.settings.overflow {
+                    OverflowPolicy::DropNewest => {
+                        self.record_drop();
+                        return;
+                    },
+                    OverflowPolicy::DropOldest => {
+                        buffer.pop_front();
+                        buffer.push_back(event);
+                        drop(buffer);
+                        self.record_drop();
+                        self.delivered.fetch_add(1, Ordering::Relaxed);
+                        self.available.notify_one();
+                        return;
+                    },
+                    OverflowPolicy::Block => {},
+                }
+            }
+            space.await;
+        }
+    }
+
+    /// Counts an event dropped because the buffer was full.
+    fn record_drop(&self) {
+        let dropped = self.dropped.fetch_add(1, Ordering::Relaxed) + 1;
+        debug!("Event bus subscriber {} is lagging, {} events dropped so far", self.name, dropped);
+    }
+
+    /// Takes the oldest event from the buffer, waiting for one if it is empty.
+    async fn pop(&self) -> Option<DockDoorEvent> {
+        loop {
+            let available = self.available.notified();
+            tokio::pin!(available);
+            {
+                let mut buffer = self.buffer.lock().unwrap();
+                available.as_mut().enable();
+                if let Some(event) = buffer.pop_front() {
+                    drop(buffer);
+                    self.space.notify_one();
+                    return Some(event);
+                }
+                if self.closed.load(Ordering::SeqCst) {
+                    return None;
+                }
+            }
+            available.await;
+        }
+    }
+
+    /// Closes the queue, waking the subscriber and any blocked publishers.
+    fn close(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+        self.available.notify_waiters();
+        self.space.notify_waiters();
+    }
+
+    /// Returns the delivery statistics of the queue.
+    fn stats(&self) -> SubscriberStats {
+        SubscriberStats {
+            name: self.name.clone(),
+            capacity: self.settings.capacity,
+            overflow: self.settings.overflow,
+            queued: self.buffer.lock().unwrap().len(),
+            delivered: self.delivered.load(Ordering::Relaxed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// A named subscription to the event bus, receiving every event published after it was created.
+///
+/// Dropping the subscription unsubscribes it from the bus.
+pub struct EventSubscription {
+    /// The subscriber's buffer.
+    queue: Arc<SubscriberQueue>,
+}
+
+impl EventSubscription {
+    /// Receives the next event.
+    ///
+    /// # Returns
+    ///
+    /// The next event, or `None` once the bus has been closed and the buffer is empty.
+    pub async fn recv(&self) -> Option<DockDoorEvent> {
+        self.queue.pop().await
+    }
+
+    /// Returns the name the subscriber registered with.
+    pub fn name(&self) -> &str {
+        &self.queue.name
+    }
+
+    /// Returns the delivery statistics of the subscription.
+    pub fn stats(&self) -> SubscriberStats {
+        self.queue.stats()
+    }
+}
+
+impl Drop for EventSubscription {
+    fn drop(&mut self) {
+        self.queue.close();
+    }
+}
+
+/// Broadcasts dock door events to any number of named subscribers.
+///
+/// Every subscriber has its own bounded buffer and overflow policy, so a slow subscriber only affects
+/// the publishers if it asked for `Block`; otherwise its events are dropped and counted.
+pub struct EventBus {
+    /// Buffer settings overriding the ones subscribers ask for.
+    settings: EventBusSettings,
+    /// The buffers of the current subscribers.
+    subscribers: RwLock<Vec<Arc<SubscriberQueue>>>,
+    /// Whether the bus has been closed.
+    closed: AtomicBool,
+}
+
+impl EventBus {
+    /// Creates a new `EventBus` without subscribers.
+    ///
+    /// # Arguments
+    ///
+    /// * `settings` - The event bus settings.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `EventBus`.
+    pub fn new(settings: EventBusSettings) -> Self {
+        Self {
+            settings,
+            subscribers: RwLock::new(Vec::new()),
+            closed: AtomicBool::new(false),
+        }
+    }
+
+    /// Subscribes to the events published from now on.
+    ///
+    /// Settings configured for the subscriber's name in `event_bus.subscribers` take precedence over the given ones.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the subscriber, shown in its statistics.
+    /// * `settings` - The buffer settings the subscriber asks for.
+    ///
+    /// # Returns
+    ///
+    /// The new `EventSubscription`.
+    pub fn subscribe(&self, name: &str, settings: SubscriberSettings) -> EventSubscription {
+        let settings = self.settings.subscribers.get(name).copied().unwrap_or(settings);
+        let queue = Arc::new(SubscriberQueue {
+            name: name.to_string(),
+            settings,
+            buffer: Mutex::new(VecDeque::with_capacity(settings.capacity)),
+            available: Notify::new(),
+            space: Notify::new(),
+            delivered: AtomicU64::new(0),
+            dropped: AtomicU64::new(0),
+            closed: AtomicBool::new(self.is_closed()),
+        });
+        info!("Event bus subscriber {} registered with capacity {} ({:?} on overflow)", name, settings.capacity, settings.overflow);
+        self.subscribers.write().unwrap().push(Arc::clone(&queue));
+        EventSubscription { queue }
+    }
+
+    /// Publishes an event to every subscriber.
+    ///
+    /// # Arguments
+    ///
+    /// * `event` - The `DockDoorEvent` to publish.
+    pub async fn publish(&self, event: DockDoorEvent) {
+        let subscribers: Vec<Arc<SubscriberQueue>> = {
+            let mut subscribers = self.subscribers.write().unwrap();
+            subscribers.retain(|queue| !queue.closed.load(Ordering::SeqCst));
+            subscribers.clone()
+        };
+        for queue in subscribers {
+            queue.push(event.clone()).await;
+        }
+    }
+
+    /// Returns the delivery statistics of every subscriber.
+    pub fn stats(&self) -> Vec<SubscriberStats> {
+        self.subscribers.read().unwrap().iter().map(|queue| queue.stats()).collect()
+    }
+
+    /// Closes the bus; subscribers receive the events still buffered and then `None`.
+    pub fn close(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+        for queue in self.subscribers.read().unwrap().iter() {
+            queue.close();
+        }
+    }
+
+    /// Returns `true` if the bus has been closed.
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::SeqCst)
+    }
+}
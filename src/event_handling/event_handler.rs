@@ -1,21 +1,33 @@
 use std::sync::Arc;
-use tokio::sync::{mpsc, Mutex};
+use tokio::sync::{mpsc, oneshot};
 use chrono::{Local, Utc};
 use log::{info, error, debug};
-use crate::models::{DockDoorEvent, DbInsert, DockDoor};
-use crate::analysis::{AnalysisResult, context_analyzer, ContextAnalyzer};
+use crate::models::{DockDoorEvent, DbInsert, DockDoor, DoorState, DoorStateAnomalyEvent, TransitionCause, WmsPlcDiscrepancyEvent};
+use crate::analysis::{AnalysisResult, context_analyzer, ContextAnalyzer, DeferredResults};
 use crate::errors::{DockManagerResult, DockManagerError};
 use crate::alerting::alert_manager::{AlertManager, Alert, AlertType};
 use crate::monitoring::{MonitoringItem, MonitoringQueue};
 use crate::state_management::door_state_repository::DoorStateRepository;
+use crate::state_management::event_journal::EventJournal;
+use crate::event_handling::EventSubscription;
 use crate::services::db::DatabaseService;
+use crate::services::db_outbox::DbOutbox;
+use crate::utils::shutdown::ShutdownSignal;
 use crate::models::consolidated_dock_event::ConsolidatedDockEvent;
 
+/// A message to the task storing consolidated events.
+enum ConsolidatedMessage {
+    /// A consolidated event to store.
+    Event(ConsolidatedDockEvent),
+    /// A request to acknowledge once every event sent before it has been stored.
+    Flush(oneshot::Sender<()>),
+}
+
 /// The EventHandler is responsible for processing events in the dock door management system.
 #[derive(Clone)]
 pub struct EventHandler {
-    /// A queue for receiving `DockDoorEvent`s.
-    event_queue: Arc<Mutex<mpsc::Receiver<DockDoorEvent>>>,
+    /// The subscription to the event bus the `DockDoorEvent`s are received from.
+    event_queue: Arc<EventSubscription>,
     /// The state repository responsible for maintaining the state of dock doors.
     door_repository: Arc<DoorStateRepository>,
     /// The context analyzer used to analyze events and generate insights.
@@ -25,7 +37,11 @@ pub struct EventHandler {
     /// The queue for monitoring items.
     monitoring_queue: Arc<MonitoringQueue>,
     /// A channel sender for consolidated events.
-    consolidated_event_sender: mpsc::Sender<ConsolidatedDockEvent>,
+    consolidated_event_sender: mpsc::Sender<ConsolidatedMessage>,
+    /// The outbox the `DbInsert`s generated while processing events are written to the database through.
+    db_outbox: Arc<DbOutbox>,
+    /// The journal every processed event is recorded in, if enabled.
+    journal: Option<Arc<EventJournal>>,
 }
 
 impl EventHandler {
@@ -33,36 +49,41 @@ impl EventHandler {
     ///
     /// # Arguments
     ///
-    /// * `event_queue` - The receiver end of a channel to receive `DockDoorEvent`s.
+    /// * `event_queue` - The subscription to the event bus to receive `DockDoorEvent`s from.
     /// * `door_repository` - The `DoorStateRepository` to interact with for state updates.
     /// * `context_analyzer` - The `ContextAnalyzer` to use for event analysis.
     /// * `alert_manager` - The `AlertManager` to handle alerts.
     /// * `monitoring_queue` - The `MonitoringQueue` to add monitoring items.
-    /// * `db_service` - The `DatabaseService` for database operations.
+    /// * `db_outbox` - The `DbOutbox` to write the generated `DbInsert`s through, whose `DatabaseService`
+    ///   also stores the consolidated events.
+    /// * `journal` - The `EventJournal` to record every processed event in, if enabled.
     ///
     /// # Returns
     ///
     /// A new `EventHandler` instance.
     pub fn new(
-        event_queue: mpsc::Receiver<DockDoorEvent>,
+        event_queue: EventSubscription,
         door_repository: Arc<DoorStateRepository>,
         context_analyzer: Arc<ContextAnalyzer>,
         alert_manager: Arc<AlertManager>,
         monitoring_queue: Arc<MonitoringQueue>,
-        db_service: Arc<DatabaseService>,
+        db_outbox: Arc<DbOutbox>,
+        journal: Option<Arc<EventJournal>>,
     ) -> Self {
         let (consolidated_event_sender, consolidated_event_receiver) = mpsc::channel(1000);
 
         // Spawn a task to handle consolidated events
-        tokio::spawn(Self::process_consolidated_events(consolidated_event_receiver, Arc::clone(&db_service)));
+        tokio::spawn(Self::process_consolidated_events(consolidated_event_receiver, Arc::clone(db_outbox.db_service())));
 
         Self {
-            event_queue: Arc::new(Mutex::new(event_queue)),
+            event_queue: Arc::new(event_queue),
             door_repository,
             context_analyzer,
             alert_manager,
             monitoring_queue,
             consolidated_event_sender,
+            db_outbox,
+            journal,
         }
     }
 
@@ -76,29 +97,56 @@ impl EventHandler {
     /// * `receiver` - The receiver end of the channel for consolidated events.
     /// * `db_service` - The database service for inserting events.
     async fn process_consolidated_events(
-        mut receiver: mpsc::Receiver<ConsolidatedDockEvent>,
+        mut receiver: mpsc::Receiver<ConsolidatedMessage>,
         db_service: Arc<DatabaseService>,
     ) {
-        while let Some(event) = receiver.recv().await {
-            if let Err(e) = db_service.insert_consolidated_event(&event).await {
-                error!("Failed to insert consolidated event: {:?}", e);
+        while let Some(message) = receiver.recv().await {
+            match message {
+                ConsolidatedMessage::Event(event) => {
+                    if let Err(e) = db_service.insert_consolidated_event(&event).await {
+                        error!("Failed to insert consolidated event: {:?}", e);
+                    }
+                },
+                ConsolidatedMessage::Flush(ack) => {
+                    let _ = ack.send(());
+                },
             }
         }
     }
 
-    /// Runs the event handler, continuously processing events from the queue
-    /// until the queue is closed or an error occurs.
+    /// Waits until every consolidated event sent so far has been stored.
+    pub async fn flush_consolidated_events(&self) {
+        let (ack_sender, ack_receiver) = oneshot::channel();
+        if self.consolidated_event_sender.send(ConsolidatedMessage::Flush(ack_sender)).await.is_err()
+            || ack_receiver.await.is_err() {
+            error!("Consolidated event task stopped before it was flushed");
+        }
+    }
+
+    /// Runs the event handler, continuously processing events from the event bus
+    /// until the bus is closed, and writing the resulting `DbInsert`s to the database through the outbox.
+    ///
+    /// Once the bus is closed, the events still buffered are processed and the consolidated events are
+    /// flushed before returning.
     ///
     /// # Returns
     ///
     /// A `DockManagerResult` indicating success or failure of the run.
     pub async fn run(&self) -> DockManagerResult<()> {
         info!("EventHandler started");
-        while let Some(event) = self.event_queue.lock().await.recv().await {
-            if let Err(e) = self.process_event(event).await {
-                error!("Error processing event: {:?}", e);
+        while let Some(event) = self.event_queue.recv().await {
+            match self.process_event(event).await {
+                Ok(db_events) if !db_events.is_empty() => {
+                    info!("Inserting {} DB events", db_events.len());
+                    if let Err(e) = self.db_outbox.insert(db_events).await {
+                        error!("Error inserting DB events: {:?}", e);
+                    }
+                },
+                Ok(_) => {},
+                Err(e) => error!("Error processing event: {:?}", e),
             }
         }
+        self.flush_consolidated_events().await;
         info!("EventHandler stopped");
         Ok(())
     }
@@ -106,8 +154,9 @@ impl EventHandler {
     /// Processes a single dock door event.
     ///
     /// This method retrieves the associated dock door, analyzes the event using the `ContextAnalyzer`,
-    /// handles any resulting state transitions, alerts, or logs, updates the door's state,
-    /// and inserts any generated `DbInsert` events into the database.
+    /// applies the resulting state transitions and the event itself to the door in the repository, handles
+    /// any resulting alerts or logs, and inserts any generated `DbInsert` events into the database.
+    /// Events the processors already applied to the door are not applied again.
     ///
     /// # Arguments
     ///
@@ -120,21 +169,174 @@ impl EventHandler {
     pub async fn process_event(&self, event: DockDoorEvent) -> DockManagerResult<Vec<DbInsert>> {
         debug!("Processing event: {:?}", event);
 
+        let mut journal_sequence = None;
+        if let Some(journal) = &self.journal {
+            match journal.append(&event).await {
+                Ok(sequence) => journal_sequence = Some(sequence),
+                Err(e) => error!("Failed to record event in the journal: {:?}", e),
+            }
+        }
+
         let door_name = event.get_dock_name();
         let plant_id = event.get_plant_id();
 
-        let mut door = self.door_repository.get_door_state(plant_id, door_name).await
+        let door = self.door_repository.get_door_state(plant_id, door_name).await
             .ok_or_else(|| DockManagerError::DoorNotFound(door_name.to_string()))?;
 
-        let analysis_results = self.context_analyzer.analyze(&door, &event).await;
+        let mut analysis_results = self.context_analyzer.analyze(&door, &event).await;
+        if let DockDoorEvent::WmsPlcDiscrepancy(discrepancy) = &event {
+            analysis_results.extend(Self::discrepancy_results(discrepancy));
+        }
         info!("Analysis results: {:?}", analysis_results);
 
         let mut db_events = Vec::new();
+        if let DockDoorEvent::DoorStateAnomaly(anomaly) = &event {
+            db_events.push(Self::anomaly_db_insert(&door, anomaly));
+        }
+        let (door, handled) = self.door_repository.modify_door(plant_id, door_name, |live_door| {
+            Self::apply_transitions(live_door, door.door_state, &analysis_results, &mut db_events);
+            let handled = if event.is_applied_at_source() { Ok(None) } else { live_door.handle_event(&event) };
+            if let Ok(Some(DockDoorEvent::DoorStateAnomaly(anomaly))) = &handled {
+                db_events.push(Self::anomaly_db_insert(live_door, anomaly));
+            }
+            (live_door.clone(), handled)
+        }).await
+            .ok_or_else(|| DockManagerError::DoorNotFound(door_name.to_string()))?;
+        handled?;
+        if let (Some(journal), Some(sequence)) = (&self.journal, journal_sequence) {
+            journal.mark_applied(sequence);
+        }
+
+        self.handle_results(&door, analysis_results, &mut db_events).await;
+
+        if !db_events.is_empty() {
+            self.insert_db_events(&db_events).await?;
+        }
+
+        Ok(db_events)
+    }
+
+    /// Applies the analysis rules to every door on the periodic tick and handles their results like those of an
+    /// event, writing the resulting `DbInsert`s to the database through the outbox.
+    pub async fn tick_rules(&self) {
+        for door in self.door_repository.get_all_doors().await {
+            let analysis_results = self.context_analyzer.tick(&door).await;
+            if !analysis_results.is_empty() {
+                info!("Tick analysis results for door {}: {:?}", door.dock_name, analysis_results);
+                self.handle_door_results(door, analysis_results).await;
+            }
+        }
+    }
+
+    /// Handles the results the rules send after analyzing an event, such as once a door has settled, writing the
+    /// resulting `DbInsert`s to the database through the outbox.
+    ///
+    /// Runs until every sender is gone, or until the drain signal is triggered, after which the results already
+    /// sent are handled.
+    ///
+    /// # Arguments
+    ///
+    /// * `receiver` - The receiving end of the `RuleContext`'s `deferred_results`.
+    /// * `drain` - The `ShutdownCoordinator`'s drain signal.
+    pub async fn run_deferred_results(&self, mut receiver: mpsc::Receiver<DeferredResults>, mut drain: ShutdownSignal) {
+        loop {
+            let deferred = tokio::select! {
+                deferred = receiver.recv() => match deferred {
+                    Some(deferred) => deferred,
+                    None => return,
+                },
+                _ = drain.wait() => break,
+            };
+            self.handle_deferred_results(deferred).await;
+        }
+
+        receiver.close();
+        while let Some(deferred) = receiver.recv().await {
+            self.handle_deferred_results(deferred).await;
+        }
+        info!("Handled the remaining deferred analysis results");
+    }
+
+    /// Handles the results a rule sent for a door after analyzing an event.
+    async fn handle_deferred_results(&self, deferred: DeferredResults) {
+        info!("Deferred analysis results for door {}: {:?}", deferred.dock_name, deferred.results);
+        match self.door_repository.get_door_state(&deferred.plant_id, &deferred.dock_name).await {
+            Some(door) => self.handle_door_results(door, deferred.results).await,
+            None => error!("Dropping deferred results for unknown door {}", deferred.dock_name),
+        }
+    }
+
+    /// Handles analysis results produced outside of an event and writes their `DbInsert`s through the outbox.
+    ///
+    /// # Arguments
+    ///
+    /// * `door` - The state of the `DockDoor` the results were produced from.
+    /// * `analysis_results` - The `AnalysisResult`s to handle.
+    async fn handle_door_results(&self, door: DockDoor, analysis_results: Vec<AnalysisResult>) {
+        let mut db_events = Vec::new();
+        let updated = self.door_repository.modify_door(&door.plant_id, &door.dock_name, |live_door| {
+            Self::apply_transitions(live_door, door.door_state, &analysis_results, &mut db_events);
+            live_door.clone()
+        }).await;
+        let Some(door) = updated else {
+            error!("Dropping analysis results for unknown door {}", door.dock_name);
+            return;
+        };
+        self.handle_results(&door, analysis_results, &mut db_events).await;
+        if db_events.is_empty() {
+            return;
+        }
+        if let Err(e) = self.insert_db_events(&db_events).await {
+            error!("Error recording DB events: {:?}", e);
+        }
+        if let Err(e) = self.db_outbox.insert(db_events).await {
+            error!("Error inserting DB events: {:?}", e);
+        }
+    }
+
+    /// Applies the state transitions among the results of analyzing a door to the door.
+    ///
+    /// The transitions are guarded: each one is only made while the door is still in the state the rules analyzed,
+    /// or the one the previous transition moved it to, so a rule never undoes a change made after it ran.
+    ///
+    /// # Arguments
+    ///
+    /// * `door` - The `DockDoor` to move, as held by the repository.
+    /// * `analyzed_state` - The state the door was in when it was analyzed.
+    /// * `analysis_results` - The `AnalysisResult`s of the analysis.
+    /// * `db_events` - The `DbInsert`s generated so far, which those of illegal transitions are added to.
+    fn apply_transitions(door: &mut DockDoor, analyzed_state: DoorState, analysis_results: &[AnalysisResult], db_events: &mut Vec<DbInsert>) {
+        let mut expected_state = analyzed_state;
+        for result in analysis_results {
+            let AnalysisResult::StateTransition(new_state) = result else {
+                continue;
+            };
+            if door.door_state != expected_state {
+                info!(
+                    "Door {} moved to {:?} since it was analyzed in {:?}, skipping the transition to {:?}",
+                    door.dock_name, door.door_state, expected_state, new_state
+                );
+                continue;
+            }
+            if let Some(DockDoorEvent::DoorStateAnomaly(anomaly)) = door.transition_door_state(*new_state, TransitionCause::Rule, Local::now().naive_local()) {
+                db_events.push(Self::anomaly_db_insert(door, &anomaly));
+            }
+            expected_state = *new_state;
+        }
+    }
+
+    /// Handles the results of analyzing a door other than state transitions, which `apply_transitions` made:
+    /// sends alerts, and collects the `DbInsert`s of the logs and inserts.
+    ///
+    /// # Arguments
+    ///
+    /// * `door` - The `DockDoor` the results are for.
+    /// * `analysis_results` - The `AnalysisResult`s to handle.
+    /// * `db_events` - The `DbInsert`s generated so far, which the new ones are added to.
+    async fn handle_results(&self, door: &DockDoor, analysis_results: Vec<AnalysisResult>, db_events: &mut Vec<DbInsert>) {
         for result in analysis_results {
             match result {
-                AnalysisResult::StateTransition(new_state) => {
-                    door.door_state = new_state;
-                },
+                AnalysisResult::StateTransition(_) => {},
                 AnalysisResult::Log(log_entry) => {
                     info!("EVENT HANDLER: Processing event log entry for log: {:?}", log_entry);
                     let db_insert = DbInsert::from_log_entry(&log_entry);
@@ -142,31 +344,89 @@ impl EventHandler {
                 },
                 AnalysisResult::Alert(alert_type) => {
                     info!("EVENT HANDLER: Processing event alert for alert: {:?}", alert_type);
-                    let alert = self.create_alert(alert_type.clone(), &door);
+                    let alert = self.create_alert(alert_type.clone(), door);
                     match self.alert_manager.handle_alert(alert.clone()).await {
                         Ok(_) => info!("Alert handled successfully: {:?}", alert),
                         Err(e) => error!("Failed to handle alert: {:?}. Error: {:?}", alert, e),
                     }
-                    self.add_to_monitoring_queue(alert_type, &door).await;
+                    self.add_to_monitoring_queue(alert_type, door).await;
                 },
                 AnalysisResult::DbInsert(db_insert) => {
                     db_events.push(db_insert);
                 },
                 AnalysisResult::ConsolidatedEvent(consolidated_event) => {
-                    if let Err(e) = self.consolidated_event_sender.send(consolidated_event).await {
+                    if let Err(e) = self.consolidated_event_sender.send(ConsolidatedMessage::Event(consolidated_event)).await {
                         error!("Failed to send consolidated event: {:?}", e);
                     }
                 }
             }
         }
+    }
 
-        door.handle_event(&event)?;
+    /// Creates the database record of an illegal door state transition.
+    ///
+    /// # Arguments
+    ///
+    /// * `door` - The `DockDoor` whose state changed.
+    /// * `anomaly` - The `DoorStateAnomalyEvent` describing the transition and its cause.
+    ///
+    /// # Returns
+    ///
+    /// The `DbInsert` recording the anomaly.
+    fn anomaly_db_insert(door: &DockDoor, anomaly: &DoorStateAnomalyEvent) -> DbInsert {
+        DbInsert::from_log_entry(&context_analyzer::LogEntry::DoorStateAnomaly {
+            log_dttm: anomaly.timestamp,
+            plant: anomaly.plant_id.clone(),
+            door_name: anomaly.dock_name.clone(),
+            shipment_id: door.assigned_shipment.current_shipment.clone(),
+            event_type: "DOOR_STATE_ANOMALY".to_string(),
+            success: false,
+            notes: format!("Illegal transition {:?} -> {:?} caused by {}", anomaly.old_state, anomaly.new_state, anomaly.cause),
+            severity: 1,
+            previous_state: Some(format!("{:?}", anomaly.old_state)),
+            previous_state_dttm: None,
+        })
+    }
 
-        if !db_events.is_empty() {
-            self.insert_db_events(&db_events).await?;
+    /// Creates the alert and log entry of a contradiction between the WMS and the sensors of a door,
+    /// or only the log entry once it is resolved.
+    ///
+    /// # Arguments
+    ///
+    /// * `discrepancy` - The `WmsPlcDiscrepancyEvent` raised by the reconciler.
+    ///
+    /// # Returns
+    ///
+    /// The `AnalysisResult`s to handle for the discrepancy.
+    fn discrepancy_results(discrepancy: &WmsPlcDiscrepancyEvent) -> Vec<AnalysisResult> {
+        let mut results = Vec::new();
+        if !discrepancy.resolved {
+            results.push(AnalysisResult::Alert(context_analyzer::AlertType::WmsPlcDiscrepancy {
+                door_name: discrepancy.dock_name.clone(),
+                shipment_id: discrepancy.shipment_id.clone(),
+                pattern: discrepancy.pattern.clone(),
+                details: discrepancy.details.clone(),
+                since: discrepancy.since,
+            }));
         }
-
-        Ok(db_events)
+        let (event_type, notes) = if discrepancy.resolved {
+            ("WMS_PLC_DISCREPANCY_RESOLVED", format!("{} resolved: {}", discrepancy.pattern, discrepancy.details))
+        } else {
+            ("WMS_PLC_DISCREPANCY", format!("{}: {}", discrepancy.pattern, discrepancy.details))
+        };
+        results.push(AnalysisResult::Log(context_analyzer::LogEntry::WmsPlcDiscrepancy {
+            log_dttm: discrepancy.timestamp,
+            plant: discrepancy.plant_id.clone(),
+            door_name: discrepancy.dock_name.clone(),
+            shipment_id: discrepancy.shipment_id.clone(),
+            event_type: event_type.to_string(),
+            success: discrepancy.resolved,
+            notes,
+            severity: if discrepancy.resolved { 0 } else { 2 },
+            previous_state: Some(discrepancy.pattern.clone()),
+            previous_state_dttm: Some(discrepancy.since),
+        }));
+        results
     }
 
     /// Inserts a batch of database events.
@@ -249,6 +509,72 @@ impl EventHandler {
                     .add_info("Trailer has been held hostage for ".to_string(), duration.to_string())
                     .build()
             },
+            context_analyzer::AlertType::DoorOffline { door_name, shipment_id, consecutive_failures, last_good_read, reason } => {
+                let mut builder = Alert::new(AlertType::DoorOffline, door_name)
+                    .add_info("failed polling cycles".to_string(), consecutive_failures.to_string())
+                    .add_info("reason".to_string(), reason.unwrap_or_default());
+                if let Some(shipment_id) = shipment_id {
+                    builder = builder.shipment_id(shipment_id);
+                }
+                if let Some(last_good_read) = last_good_read {
+                    builder = builder.add_info("last good read timestamp".to_string(), last_good_read.to_string());
+                }
+                builder.build()
+            },
+            context_analyzer::AlertType::DoorOnline { door_name, shipment_id, offline_duration } => {
+                let mut builder = Alert::new(AlertType::DoorOnline, door_name);
+                if let Some(shipment_id) = shipment_id {
+                    builder = builder.shipment_id(shipment_id);
+                }
+                if let Some(offline_duration) = offline_duration {
+                    builder = builder.duration(offline_duration);
+                }
+                builder.build()
+            },
+            context_analyzer::AlertType::SensorThreshold { door_name, shipment_id, sensor, value, operator, threshold, message } => {
+                let mut builder = Alert::new(AlertType::SensorThreshold, door_name)
+                    .add_info("sensor".to_string(), sensor)
+                    .add_info("value".to_string(), value.to_string())
+                    .add_info("operator".to_string(), operator)
+                    .add_info("threshold".to_string(), threshold.to_string());
+                if let Some(shipment_id) = shipment_id {
+                    builder = builder.shipment_id(shipment_id);
+                }
+                if let Some(message) = message {
+                    builder = builder.add_info("message".to_string(), message);
+                }
+                builder.build()
+            },
+            context_analyzer::AlertType::StuckSensor { door_name, shipment_id, sensor, value, idle_cycles, last_change } => {
+                let mut builder = Alert::new(AlertType::StuckSensor, door_name)
+                    .add_info("sensor".to_string(), sensor)
+                    .add_info("value".to_string(), value.unwrap_or_else(|| "unknown".to_string()))
+                    .add_info("idle cycles".to_string(), idle_cycles.to_string())
+                    .add_info("last change".to_string(), last_change.to_string());
+                if let Some(shipment_id) = shipment_id {
+                    builder = builder.shipment_id(shipment_id);
+                }
+                builder.build()
+            },
+            context_analyzer::AlertType::WmsPlcDiscrepancy { door_name, shipment_id, pattern, details, since } => {
+                let mut builder = Alert::new(AlertType::WmsPlcDiscrepancy, door_name)
+                    .add_info("pattern".to_string(), pattern)
+                    .add_info("details".to_string(), details)
+                    .add_info("since".to_string(), since.to_string());
+                if let Some(shipment_id) = shipment_id {
+                    builder = builder.shipment_id(shipment_id);
+                }
+                builder.build()
+            },
+            context_analyzer::AlertType::ConditionMatched { door_name, shipment_id, rule, message } => {
+                let mut builder = Alert::new(AlertType::ConditionMatched, door_name)
+                    .add_info("rule".to_string(), rule)
+                    .add_info("message".to_string(), message);
+                if let Some(shipment_id) = shipment_id {
+                    builder = builder.shipment_id(shipment_id);
+                }
+                builder.build()
+            },
             _ => Alert::new(AlertType::ManualModeAlert, door.dock_name.clone()).build(),
         }
     }
@@ -311,4 +637,52 @@ impl EventHandler {
             _ => {}
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::test_support;
+
+    fn transitions(states: &[DoorState]) -> Vec<AnalysisResult> {
+        states.iter().map(|state| AnalysisResult::StateTransition(*state)).collect()
+    }
+
+    #[test]
+    fn rule_transitions_are_applied_in_order() {
+        let mut door = test_support::door(&test_support::plant());
+        let mut db_events = Vec::new();
+
+        let results = transitions(&[DoorState::Assigned, DoorState::TrailerDocked]);
+        EventHandler::apply_transitions(&mut door, DoorState::Unassigned, &results, &mut db_events);
+
+        assert_eq!(door.door_state, DoorState::TrailerDocked);
+        assert_eq!(door.previous_door_state, DoorState::Assigned);
+        assert!(db_events.is_empty());
+    }
+
+    #[test]
+    fn rule_transitions_are_skipped_once_the_door_has_moved_on() {
+        let mut door = test_support::door(&test_support::plant());
+        door.door_state = DoorState::TrailerDocked;
+        let mut db_events = Vec::new();
+
+        let results = transitions(&[DoorState::Assigned]);
+        EventHandler::apply_transitions(&mut door, DoorState::Unassigned, &results, &mut db_events);
+
+        assert_eq!(door.door_state, DoorState::TrailerDocked);
+        assert!(db_events.is_empty());
+    }
+
+    #[test]
+    fn illegal_rule_transitions_are_recorded() {
+        let mut door = test_support::door(&test_support::plant());
+        let mut db_events = Vec::new();
+
+        let results = transitions(&[DoorState::Loading]);
+        EventHandler::apply_transitions(&mut door, DoorState::Unassigned, &results, &mut db_events);
+
+        assert_eq!(door.door_state, DoorState::Loading);
+        assert_eq!(db_events.len(), 1);
+        assert_eq!(db_events[0].EVENT_TYPE, "DOOR_STATE_ANOMALY");
+    }
+}
@@ -1,2 +1,4 @@
 pub mod event_handler;
-pub use event_handler::*;
\ No newline at end of file
+pub mod event_bus;
+pub use event_handler::*;
+pub use event_bus::{EventBus, EventSubscription, SubscriberStats};
\ No newline at end of file
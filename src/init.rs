@@ -1,33 +1,41 @@
-use std::path::PathBuf;
 use std::sync::Arc;
 use anyhow::Result;
+use tokio::sync::mpsc;
 use crate::alerting::alert_manager::{AlertConfig, AlertManager};
-use crate::analysis::create_default_analyzer;
-use crate::config::Settings;
+use crate::analysis::{create_default_analyzer, DoorJson, RuleContext};
+use crate::config::{SensorSourceKind, Settings};
 use crate::controllers::dock_door::DockDoorController;
 use crate::event_handling::EventHandler;
+use crate::models::TrailerVisit;
 use crate::monitoring::{MonitoringQueue, MonitoringWorker};
-use crate::rules::{DynamicRuleManager, WmsShipmentStatus};
+use crate::rules::{DynamicRuleManager, RuleReloader, WmsShipmentStatus};
 use crate::services::db::DatabaseService;
-use crate::services::PlcService;
+use crate::services::db_outbox::DbOutbox;
+use crate::services::{PlcService, RecordingSensorSource, ReplaySensorSource, SensorSource, SimulatedSensorSource};
 use crate::state_management::DockDoorStateManager;
+use crate::state_management::event_journal::EventJournal;
+use crate::utils::clock::SystemClock;
+use crate::utils::shutdown::ShutdownCoordinator;
 
 
 pub struct AppContext {
     pub settings: Arc<Settings>,
-    pub plc_service: PlcService,
+    pub sensor_source: Arc<dyn SensorSource>,
     pub alert_manager: Arc<AlertManager>,
     pub db_service: DatabaseService,
+    pub db_outbox: Arc<DbOutbox>,
+    pub visit_outbox: Arc<DbOutbox<TrailerVisit>>,
     pub state_manager: Arc<DockDoorStateManager>,
     pub event_handler: Arc<EventHandler>,
+    pub rule_reloader: Arc<RuleReloader>,
     pub dock_door_controller: Arc<DockDoorController>,
     pub monitoring_worker: MonitoringWorker,
 }
 
-pub async fn initialize() -> Result<AppContext> {
+pub async fn initialize(shutdown: Arc<ShutdownCoordinator>) -> Result<AppContext> {
     let settings = Settings::new()?;
 
-    let plc_service = PlcService::new();
+    let sensor_source = create_sensor_source(&settings)?;
     let alert_config = AlertConfig {
         suspended_door: settings.alerts.suspended_door.clone(),
         trailer_pattern: settings.alerts.trailer_pattern.clone(),
@@ -52,30 +60,55 @@ pub async fn initialize() -> Result<AppContext> {
         .await
         .map_err(|e| anyhow::anyhow!("Failed to create DatabaseService: {}", e))?;
 
-    let rule_manager = DynamicRuleManager::new(PathBuf::from("src/config/rules.json"));
+    let db_outbox: Arc<DbOutbox> = Arc::new(DbOutbox::open(Arc::new(db_service.clone()), &settings.outbox).await?);
+    let visit_outbox: Arc<DbOutbox<TrailerVisit>> = Arc::new(DbOutbox::open(Arc::clone(db_outbox.db_service()), &settings.outbox).await?);
+
+    let journal = match &settings.journal.path {
+        Some(path) => Some(Arc::new(EventJournal::open(path, &settings.journal).await?)),
+        None => None,
+    };
+    let (state_manager, event_receiver) = DockDoorStateManager::new(&settings, Arc::clone(&db_outbox), Arc::clone(&visit_outbox), journal.clone()).await;
+
+    let shared_settings = Arc::new(settings.clone());
+    let rule_manager = DynamicRuleManager::new(settings.analysis.rules_path.clone(), Arc::clone(&shared_settings));
     let rules = rule_manager.load_rules().expect("Failed to load rules");
 
-    let mut context_analyzer = create_default_analyzer();
-    for rule in rules {
-        context_analyzer.add_rule(rule);
-    }
+    let (deferred_sender, deferred_receiver) = mpsc::channel(1000);
+    let rule_context = RuleContext {
+        db_service: Arc::clone(db_outbox.db_service()),
+        clock: Arc::new(SystemClock),
+        settings: shared_settings,
+        door_repository: state_manager.get_door_repository(),
+        deferred_results: deferred_sender,
+        shutdown: Arc::clone(&shutdown),
+        door_json: DoorJson::default(),
+    };
+    let mut context_analyzer = create_default_analyzer(rule_context);
+    context_analyzer.replace_loaded_rules(rules);
     context_analyzer.add_rule(Arc::new(WmsShipmentStatus));
+    let context_analyzer = Arc::new(context_analyzer);
+    let rule_reloader = Arc::new(RuleReloader::new(rule_manager, Arc::clone(&context_analyzer)).await);
 
     let monitoring_queue = Arc::new(MonitoringQueue::new());
-
-    let (state_manager, event_receiver) = DockDoorStateManager::new(&settings, Arc::new(db_service.clone())).await;
     let event_handler = EventHandler::new(
         event_receiver,
         state_manager.get_door_repository(),
-        Arc::new(context_analyzer),
+        context_analyzer,
         Arc::clone(&alert_manager),
         Arc::clone(&monitoring_queue),
-        Arc::new(db_service.clone()),
+        Arc::clone(&db_outbox),
+        journal,
     );
 
+    let deferred_handler = event_handler.clone();
+    let drain = shutdown.drain_signal();
+    shutdown.spawn_drain("Deferred analysis results", async move {
+        deferred_handler.run_deferred_results(deferred_receiver, drain).await
+    });
+
     let dock_door_controller = Arc::new(DockDoorController::new(
         settings.clone(),
-        plc_service.clone(),
+        Arc::clone(&sensor_source),
         Arc::new(state_manager.clone()),
         Arc::new(event_handler.clone()),
         db_service.clone(),
@@ -92,12 +125,35 @@ pub async fn initialize() -> Result<AppContext> {
 
     Ok(AppContext {
         settings: Arc::new(settings),
-        plc_service,
+        sensor_source,
         alert_manager,
         db_service,
+        db_outbox,
+        visit_outbox,
         state_manager: Arc::new(state_manager),
         event_handler: Arc::new(event_handler),
+        rule_reloader,
         dock_door_controller,
         monitoring_worker,
     })
-}
\ No newline at end of file
+}
+
+/// Creates the sensor source selected by `plc.source` in the settings
+///
+/// When `plc.record_path` is set, the selected source is wrapped so that every polled value is recorded
+fn create_sensor_source(settings: &Settings) -> Result<Arc<dyn SensorSource>> {
+    let source: Arc<dyn SensorSource> = match settings.plc.source {
+        SensorSourceKind::Plctag => Arc::new(PlcService::new()),
+        SensorSourceKind::Simulated => Arc::new(SimulatedSensorSource::new(settings.plc.simulation.clone())),
+        SensorSourceKind::Replay => {
+            let replay = settings.plc.replay.as_ref()
+                .ok_or_else(|| anyhow::anyhow!("plc.source is 'replay' but no plc.replay settings are configured"))?;
+            Arc::new(ReplaySensorSource::from_file(&replay.path, replay.loop_playback)?)
+        }
+    };
+
+    Ok(match &settings.plc.record_path {
+        Some(path) => Arc::new(RecordingSensorSource::new(source, path.clone())),
+        None => source,
+    })
+}
@@ -2,12 +2,15 @@ use std::error::Error;
 use std::sync::Arc;
 use std::time::Duration;
 use anyhow::Result;
-use log::{error, info};
+use log::{error, info, warn};
 use tokio::signal::ctrl_c;
-use tokio::time::interval;
+use tokio::task::JoinHandle;
+use tokio::time::{interval, Instant};
 use iqx_dockmonitor::alerting::alert_manager::{Alert, AlertType};
 use iqx_dockmonitor::init;
 use iqx_dockmonitor::init::AppContext;
+use iqx_dockmonitor::models::{LoadingStatus, TrailerState};
+use iqx_dockmonitor::utils::shutdown::ShutdownCoordinator;
 
 #[tokio::main]
 async fn main() {
@@ -24,27 +27,32 @@ fn init_logger() -> Result<(), Box<dyn Error>> {
 }
 
 async fn run() -> Result<()> {
-    let context = Arc::new(init::initialize().await?);
-
-    // Spawn PLC polling task
-    let plc_context = Arc::clone(&context);
-    tokio::spawn(async move {
-        let mut interval = interval(Duration::from_secs(20));
-        loop {
-            interval.tick().await;
-            info!("Starting new PLC polling cycle...");
-            if let Err(e) = plc_context.dock_door_controller.run_polling_cycle().await {
-                error!("Error during PLC polling cycle: {}", e);
+    let shutdown = Arc::new(ShutdownCoordinator::new());
+    let context = Arc::new(init::initialize(Arc::clone(&shutdown)).await?);
+
+    // Spawn a PLC polling task per plant, using the plant's configured poll interval
+    for plant in &context.settings.plants {
+        let plc_context = Arc::clone(&context);
+        let plant_id = plant.plant_id.clone();
+        let poll_interval = context.settings.plc.timing_for(plant).poll_interval_secs.max(1);
+        let mut signal = shutdown.signal();
+        shutdown.spawn(&format!("PLC polling for plant {}", plant.plant_id), async move {
+            let mut interval = interval(Duration::from_secs(poll_interval));
+            while signal.tick(&mut interval).await {
+                info!("Starting new PLC polling cycle for plant {}...", plant_id);
+                if let Err(e) = plc_context.dock_door_controller.run_polling_cycle(&plant_id).await {
+                    error!("Error during PLC polling cycle for plant {}: {}", plant_id, e);
+                }
             }
-        }
-    });
+        });
+    }
 
     // Spawn WMS event polling task
     let wms_event_context = Arc::clone(&context);
-    tokio::spawn(async move {
+    let mut signal = shutdown.signal();
+    shutdown.spawn("WMS event polling", async move {
         let mut interval = interval(Duration::from_secs(60));
-        loop {
-            interval.tick().await;
+        while signal.tick(&mut interval).await {
             info!("Starting WMS event polling cycle...");
             if let Err(e) = wms_event_context.dock_door_controller.update_wms_events().await {
                 error!("Error during WMS event update cycle: {}", e);
@@ -54,10 +62,10 @@ async fn run() -> Result<()> {
 
     // Spawn WMS door status polling task
     let wms_door_context = Arc::clone(&context);
-    tokio::spawn(async move {
+    let mut signal = shutdown.signal();
+    shutdown.spawn("WMS door status polling", async move {
         let mut interval = interval(Duration::from_secs(25));
-        loop {
-            interval.tick().await;
+        while signal.tick(&mut interval).await {
             info!("Starting WMS door status polling cycle...");
             if let Err(e) = wms_door_context.dock_door_controller.update_wms_door_status().await {
                 error!("Error during WMS door status update cycle: {}", e);
@@ -65,11 +73,24 @@ async fn run() -> Result<()> {
         }
     });
 
+    // Spawn WMS/PLC reconciliation task
+    let reconciler_context = Arc::clone(&context);
+    let reconciler_interval = context.settings.reconciler.interval_secs.max(1);
+    let mut signal = shutdown.signal();
+    shutdown.spawn("WMS/PLC reconciliation", async move {
+        let mut interval = interval(Duration::from_secs(reconciler_interval));
+        while signal.tick(&mut interval).await {
+            if let Err(e) = reconciler_context.dock_door_controller.reconcile_wms_and_plc().await {
+                error!("Error during WMS/PLC reconciliation: {}", e);
+            }
+        }
+    });
+
     let trailer_pattern_context = Arc::clone(&context);
-    tokio::spawn(async move {
+    let mut signal = shutdown.signal();
+    shutdown.spawn("Trailer pattern check", async move {
         let mut interval = interval(Duration::from_secs(300)); // 5 minutes
-        loop {
-            interval.tick().await;
+        while signal.tick(&mut interval).await {
             info!("Starting trailer pattern check cycle...");
             if let Err(e) = check_trailer_pattern_issues(Arc::clone(&trailer_pattern_context)).await {
                 error!("Error during trailer pattern check cycle: {}", e);
@@ -79,10 +100,10 @@ async fn run() -> Result<()> {
 
     // Hourly rack space utilization check
     let rack_space_context = Arc::clone(&context);
-    tokio::spawn(async move {
+    let mut signal = shutdown.signal();
+    shutdown.spawn("Rack space check", async move {
         let mut interval = interval(Duration::from_secs(3600));
-        loop {
-            interval.tick().await;
+        while signal.tick(&mut interval).await {
             info!("Starting rack space utilization check...");
             for plant in &rack_space_context.settings.plants {
                 let plant_id = &plant.plant_id;
@@ -133,9 +154,9 @@ async fn run() -> Result<()> {
         }
     });
 
-    // Spawn EventHandler task
+    // Spawn EventHandler task; it stops once the event bus is closed during shutdown
     let event_handler_context = Arc::clone(&context);
-    tokio::spawn(async move {
+    let event_handler_task = tokio::spawn(async move {
         if let Err(e) = event_handler_context.event_handler.run().await {
             error!("EventHandler error: {:?}", e);
         }
@@ -143,17 +164,152 @@ async fn run() -> Result<()> {
 
     // Spawn MonitoringWorker task
     let monitoring_context = Arc::clone(&context);
-    tokio::spawn(async move {
-        monitoring_context.monitoring_worker.run().await;
+    let signal = shutdown.signal();
+    shutdown.spawn("Monitoring worker", async move {
+        monitoring_context.monitoring_worker.run(signal).await;
+    });
+
+    // Spawn database outbox retry task
+    let outbox_context = Arc::clone(&context);
+    let signal = shutdown.signal();
+    shutdown.spawn("Database outbox retry", async move {
+        outbox_context.db_outbox.run(signal).await;
     });
 
+    // Spawn trailer visit outbox retry task, storing the completed visits off the event dispatch path
+    let visit_outbox_context = Arc::clone(&context);
+    let signal = shutdown.signal();
+    shutdown.spawn("Trailer visit outbox retry", async move {
+        visit_outbox_context.visit_outbox.run(signal).await;
+    });
+
+    // Spawn event bus, database outbox and door occupancy statistics task
+    let event_bus_context = Arc::clone(&context);
+    let mut signal = shutdown.signal();
+    shutdown.spawn("Event bus statistics", async move {
+        let mut interval = interval(Duration::from_secs(300));
+        while signal.tick(&mut interval).await {
+            for stats in event_bus_context.state_manager.event_bus().stats() {
+                if stats.dropped > 0 {
+                    warn!("Event bus subscriber {} has dropped {} events ({} delivered, {}/{} queued)",
+                        stats.name, stats.dropped, stats.delivered, stats.queued, stats.capacity);
+                } else {
+                    info!("Event bus subscriber {}: {} delivered, {}/{} queued",
+                        stats.name, stats.delivered, stats.queued, stats.capacity);
+                }
+            }
+            for occupancy in event_bus_context.state_manager.get_occupancy().await {
+                info!("Plant {}: {} doors, {} loading, {} idle, {} with a trailer, {} faulted, {} in manual mode, {} offline",
+                    occupancy.plant_id, occupancy.total_doors,
+                    occupancy.loading_status_count(LoadingStatus::Loading),
+                    occupancy.loading_status_count(LoadingStatus::Idle),
+                    occupancy.trailer_state_count(TrailerState::Docked),
+                    occupancy.faulted, occupancy.manual_mode, occupancy.offline);
+            }
+            let outbox_depth = event_bus_context.db_outbox.depth();
+            if outbox_depth > 0 {
+                warn!("Database outbox holds {} records waiting for the database", outbox_depth);
+            }
+            let visit_outbox_depth = event_bus_context.visit_outbox.depth();
+            if visit_outbox_depth > 0 {
+                warn!("Trailer visit outbox holds {} visits waiting for the database", visit_outbox_depth);
+            }
+        }
+    });
+
+    // Spawn rules file watch task, applying changes to rules.json without a restart
+    let rules_reload_interval = context.settings.analysis.rules_reload_interval_secs;
+    if rules_reload_interval > 0 {
+        let rule_reloader = Arc::clone(&context.rule_reloader);
+        let mut signal = shutdown.signal();
+        shutdown.spawn("Rules file watch", async move {
+            let mut interval = interval(Duration::from_secs(rules_reload_interval));
+            while signal.tick(&mut interval).await {
+                rule_reloader.reload_if_changed().await;
+            }
+        });
+    }
+
+    // Spawn rule tick task, applying the rules to every door so durations are noticed without new events
+    let rule_tick_interval = context.settings.analysis.rule_tick_secs;
+    if rule_tick_interval > 0 {
+        let event_handler = Arc::clone(&context.event_handler);
+        let mut signal = shutdown.signal();
+        shutdown.spawn("Rule tick", async move {
+            let mut interval = interval(Duration::from_secs(rule_tick_interval));
+            while signal.tick(&mut interval).await {
+                event_handler.tick_rules().await;
+            }
+        });
+    }
+
+    // Spawn door state snapshot task
+    if let Some(snapshot_path) = context.settings.snapshot.path.clone() {
+        let snapshot_context = Arc::clone(&context);
+        let snapshot_interval = context.settings.snapshot.interval_secs.max(1);
+        let mut signal = shutdown.signal();
+        shutdown.spawn("Door state snapshot", async move {
+            let mut interval = interval(Duration::from_secs(snapshot_interval));
+            while signal.tick(&mut interval).await {
+                if let Err(e) = snapshot_context.state_manager.save_snapshot(&snapshot_path).await {
+                    error!("Error saving door state snapshot: {}", e);
+                }
+            }
+        });
+    }
+
     // Wait for shutdown signal
     ctrl_c().await?;
     info!("Received shutdown signal. Shutting down gracefully...");
+    shutdown_gracefully(&context, &shutdown, event_handler_task).await;
 
     Ok(())
 }
 
+/// Shuts the service down within the configured deadline
+///
+/// The background tasks finish their current cycle and stop, after which the event bus is closed so that
+/// the `EventHandler` processes the events still buffered. The draining tasks then handle the deferred analysis
+/// results and the database updates the rules queued, and the pending database events, consolidated events and
+/// outbox are flushed. Whatever the deadline left undone, the sensor source is then closed and the door states
+/// are saved.
+async fn shutdown_gracefully(context: &AppContext, shutdown: &ShutdownCoordinator, event_handler_task: JoinHandle<()>) {
+    let deadline = Instant::now() + Duration::from_secs(context.settings.shutdown.deadline_secs);
+
+    shutdown.shutdown(deadline).await;
+    if tokio::time::timeout_at(deadline, context.state_manager.shutdown()).await.is_err() {
+        warn!("Pending database events were not flushed before the shutdown deadline");
+    }
+    match tokio::time::timeout_at(deadline, event_handler_task).await {
+        Ok(Ok(())) => {},
+        Ok(Err(e)) => error!("EventHandler task failed during shutdown: {}", e),
+        Err(_) => warn!("EventHandler did not finish processing the buffered events before the shutdown deadline"),
+    }
+    shutdown.drain(deadline).await;
+    if context.db_outbox.depth() > 0 {
+        match tokio::time::timeout_at(deadline, context.db_outbox.retry()).await {
+            Ok(Ok(written)) => info!("Wrote {} outbox records to the database", written),
+            Ok(Err(e)) => warn!("Database outbox still holds {} records at shutdown: {}", context.db_outbox.depth(), e),
+            Err(_) => warn!("Database outbox still holds {} records at the shutdown deadline", context.db_outbox.depth()),
+        }
+    }
+    if context.visit_outbox.depth() > 0 {
+        match tokio::time::timeout_at(deadline, context.visit_outbox.retry()).await {
+            Ok(Ok(written)) => info!("Wrote {} trailer visits to the database", written),
+            Ok(Err(e)) => warn!("Trailer visit outbox still holds {} visits at shutdown: {}", context.visit_outbox.depth(), e),
+            Err(_) => warn!("Trailer visit outbox still holds {} visits at the shutdown deadline", context.visit_outbox.depth()),
+        }
+    }
+
+    context.sensor_source.shutdown().await;
+    if let Some(snapshot_path) = &context.settings.snapshot.path {
+        if let Err(e) = context.state_manager.save_snapshot(snapshot_path).await {
+            error!("Error saving door state snapshot on shutdown: {}", e);
+        }
+    }
+    info!("Shutdown complete");
+}
+
 async fn check_trailer_pattern_issues(context: Arc<AppContext>) -> Result<(), Box<dyn Error>> {
     info!("Starting trailer pattern check...");
     match context.db_service.fetch_trailer_pattern_data().await {
@@ -68,6 +68,12 @@ impl DbInsert {
             LogEntry::TrailerHostage { log_dttm, plant, door_name, shipment_id, event_type, success, notes, severity, previous_state, previous_state_dttm } |
             LogEntry::ShipmentStartedLoadNotReady { log_dttm, plant, door_name, shipment_id, event_type, success, notes, severity, previous_state, previous_state_dttm } |
             LogEntry::TrailerUndocked { log_dttm, plant, door_name, shipment_id, event_type, success, notes, severity, previous_state, previous_state_dttm } |
+            LogEntry::PlcConnectivity { log_dttm, plant, door_name, shipment_id, event_type, success, notes, severity, previous_state, previous_state_dttm } |
+            LogEntry::SensorThreshold { log_dttm, plant, door_name, shipment_id, event_type, success, notes, severity, previous_state, previous_state_dttm } |
+            LogEntry::StuckSensor { log_dttm, plant, door_name, shipment_id, event_type, success, notes, severity, previous_state, previous_state_dttm } |
+            LogEntry::ConditionMatched { log_dttm, plant, door_name, shipment_id, event_type, success, notes, severity, previous_state, previous_state_dttm } |
+            LogEntry::WmsPlcDiscrepancy { log_dttm, plant, door_name, shipment_id, event_type, success, notes, severity, previous_state, previous_state_dttm } |
+            LogEntry::DoorStateAnomaly { log_dttm, plant, door_name, shipment_id, event_type, success, notes, severity, previous_state, previous_state_dttm } |
             LogEntry::TrailerPatternIssue { log_dttm, plant, door_name, shipment_id, event_type, success, notes, severity, previous_state, previous_state_dttm } => {
                 DbInsert {
                     LOG_DTTM: *log_dttm,
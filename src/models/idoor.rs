@@ -9,14 +9,14 @@ use chrono::NaiveDateTime;
 use serde::{Serialize, Deserialize};
 use std::collections::HashMap;
 use std::str::FromStr;
-use log::info;
+use log::{debug, info, warn};
 use crate::config::PlantSettings;
-use crate::models::isensor::DockSensor;
-use crate::models::istates::{DoorState, TrailerState, ManualMode, DockLockState, DoorPosition, LevelerPosition, FaultState};
+use crate::models::isensor::{DockSensor, SensorValue};
+use crate::models::istates::{DoorState, TransitionCause, TrailerState, ManualMode, DockLockState, DoorPosition, LevelerPosition, FaultState};
 use crate::models::istatus::LoadingStatus;
-use crate::models::ievents::{DockAssignedEvent, DockDoorEvent, DockUnassignedEvent, DoorStateChangedEvent, LoadingCompletedEvent, LoadingStartedEvent, LoadingStatusChangedEvent, SensorStateChangedEvent, TrailerDepartedEvent, TrailerDockedEvent};
+use crate::models::ievents::{DockAssignedEvent, DockDoorEvent, DockUnassignedEvent, DoorStateAnomalyEvent, DoorStateChangedEvent, LoadingCompletedEvent, LoadingStartedEvent, LoadingStatusChangedEvent, SensorStateChangedEvent, TrailerDepartedEvent, TrailerDockedEvent};
 use crate::errors::{DockManagerError, DockManagerResult};
-use crate::models::{AssignedShipment, RestraintState, ShipmentAssignedEvent, ShipmentUnassignedEvent, TrailerPositionState, TrailerStateChangedEvent, WmsDoorStatus};
+use crate::models::{AssignedShipment, DoorOfflineEvent, DoorOnlineEvent, DoorReadStatus, RestraintState, ShipmentAssignedEvent, ShipmentUnassignedEvent, TrailerPositionState, TrailerStateChangedEvent, TrailerVisit, WmsDoorStatus};
 
 
 /// Represents the result of evaluating a sensor update
@@ -25,9 +25,9 @@ pub struct SensorEvaluation {
     /// Indicates whether the sensor value has changed
     pub changed: bool,
     /// The old sensor value before the update
-    pub old_value: Option<u8>,
+    pub old_value: Option<SensorValue>,
     /// The new sensor value after the update
-    pub new_value: Option<u8>,
+    pub new_value: Option<SensorValue>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -54,6 +54,36 @@ pub struct ConsolidatedDataState {
     pub lgv_first_drop: Option<NaiveDateTime>,
 }
 
+/// Tracks the connectivity of the PLC controlling a dock door
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PlcHealthState {
+    /// Whether the door's PLC is currently answering
+    pub online: bool,
+    /// The number of polling cycles in a row in which the PLC did not answer
+    pub consecutive_failures: u32,
+    /// When the door's sensors were last read successfully
+    pub last_good_read: Option<NaiveDateTime>,
+    /// How long the last successful read of the door's sensors took, in milliseconds
+    pub last_latency_ms: Option<u64>,
+    /// When the PLC was reported offline, if it currently is
+    pub offline_since: Option<NaiveDateTime>,
+    /// The last error reported while reading the door's sensors
+    pub last_error: Option<String>,
+}
+
+impl Default for PlcHealthState {
+    fn default() -> Self {
+        Self {
+            online: true,
+            consecutive_failures: 0,
+            last_good_read: None,
+            last_latency_ms: None,
+            offline_since: None,
+            last_error: None,
+        }
+    }
+}
+
 /// Represents the state and data associated with a single dock door.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DockDoor {
@@ -106,7 +136,15 @@ pub struct DockDoor {
     /// The current position state of the trailer (Proper or Improper)
     pub trailer_position_state: TrailerPositionState,
     pub consolidated: ConsolidatedDataState,
-
+    /// The connectivity of the PLC controlling the door
+    pub plc_health: PlcHealthState,
+    /// Whether some sensor values were lost, after restoring from a snapshot or losing the PLC, and the door's
+    /// state has not yet been checked against the PLC again
+    #[serde(default)]
+    pub awaiting_reconciliation: bool,
+    /// The visit of the trailer currently at the door, if any
+    #[serde(default)]
+    pub current_visit: Option<TrailerVisit>,
 }
 
 impl DockDoor {
@@ -169,7 +207,13 @@ impl DockDoor {
             restraint_state: RestraintState::Unlocked,
             trailer_position_state: TrailerPositionState::Improper,
             consolidated,
+            plc_health: PlcHealthState::default(),
+            awaiting_reconciliation: false,
+            current_visit: None,
         };
+        let door_config = plant_settings.dock_doors.dock_door_config.iter()
+            .find(|config| config.dock_name == dock_name);
+        let door_element_type = door_config.and_then(|config| config.element_type);
         for tag in &plant_settings.dock_doors.dock_plc_tags {
             door.sensors.insert(
                 tag.tag_name.clone(),
@@ -177,8 +221,11 @@ impl DockDoor {
                     &dock_name,
                     &dock_ip,
                     &tag.tag_name,
-                    &tag.address,
-                )
+                    door_config.map_or(tag.address.as_str(), |config| tag.address_for(config)),
+                    tag.data_type.or(door_element_type),
+                    tag.debounce,
+                    tag.value_mapping(),
+                ).with_deadband(tag.deadband)
             );
         }
 
@@ -191,6 +238,10 @@ impl DockDoor {
     /// It returns a `SensorEvaluation` indicating whether the value actually changed
     /// and providing the old and new values for reference
     ///
+    /// The raw value is first translated with the sensor's `ValueMapping`, so the evaluation reports the
+    /// translated values. Changes are subject to the sensor's debounce: a change that has not yet held long enough is reported
+    /// as unchanged, and one that reverts in the meantime is counted as a suppressed glitch
+    ///
     /// If the sensor is not found or the new value is `None`, an error is returned
     ///
     /// # Arguments
@@ -202,17 +253,24 @@ impl DockDoor {
     ///
     /// * `Ok(SensorEvaluation)` if the sensor was found and updated successfully
     /// * `Err(DockManagerError)` if the sensor was not found or the new value is `None`
-    pub fn update_sensor(&mut self, sensor_name: &str, new_value: Option<u8>) -> Result<SensorEvaluation, DockManagerError> {
+    pub fn update_sensor(&mut self, sensor_name: &str, new_value: Option<SensorValue>) -> Result<SensorEvaluation, DockManagerError> {
         if let Some(sensor) = self.sensors.get_mut(sensor_name) {
             let old_value = sensor.get_sensor_data().current_value;
 
             match new_value {
-                Some(value) => {
-                    if old_value != Some(value) {
+                Some(raw) => {
+                    let value = sensor.translate(raw);
+                    let glitches = sensor.get_sensor_data().suppressed_glitches;
+                    let accepted = sensor.debounce(value, chrono::Local::now().naive_local());
+                    if sensor.get_sensor_data().suppressed_glitches > glitches {
+                        debug!("Suppressed glitch on sensor {} for door {}", sensor_name, self.dock_name);
+                    }
+
+                    if accepted {
                         sensor.update_value(Some(value));
-                        Ok(SensorEvaluation { changed: true, old_value, new_value })
+                        Ok(SensorEvaluation { changed: true, old_value, new_value: Some(value) })
                     } else {
-                        Ok(SensorEvaluation { changed: false, old_value, new_value })
+                        Ok(SensorEvaluation { changed: false, old_value, new_value: old_value })
                     }
                 },
                 None => {
@@ -224,6 +282,168 @@ impl DockDoor {
         }
     }
 
+    /// Records the outcome of a polling cycle in the door's PLC health state
+    ///
+    /// A cycle in which the PLC answered resets the failure count; otherwise the count grows, and once it reaches
+    /// `offline_after_failures` the door is reported offline. Sensors that could not be read while the PLC answered
+    /// are marked as unknown right away, and all of the door's sensors are marked as unknown once it goes offline,
+    /// so their last values are no longer trusted. The door is then awaiting reconciliation: the next value read
+    /// for an unknown sensor is compared against its last known value and the state derived from it.
+    ///
+    /// # Arguments
+    ///
+    /// * `status`: The read status of the door for the polling cycle
+    /// * `offline_after_failures`: The number of failed cycles in a row after which the door is reported offline
+    ///
+    /// # Returns
+    ///
+    /// * `Some(DockDoorEvent::DoorOffline)` or `Some(DockDoorEvent::DoorOnline)` if the door's connectivity changed
+    /// * `None` otherwise
+    pub fn update_plc_health(&mut self, status: &DoorReadStatus, offline_after_failures: u32) -> Option<DockDoorEvent> {
+        let now = chrono::Local::now().naive_local();
+        if status.last_error.is_some() {
+            self.plc_health.last_error = status.last_error.clone();
+        }
+
+        if status.is_reachable() {
+            self.mark_sensors_unknown(&status.failed_sensors);
+            self.plc_health.consecutive_failures = 0;
+            self.plc_health.last_good_read = Some(now);
+            self.plc_health.last_latency_ms = status.latency.map(|latency| latency.as_millis() as u64);
+            if self.plc_health.online {
+                return None;
+            }
+
+            self.plc_health.online = true;
+            let offline_since = self.plc_health.offline_since.take();
+            info!("PLC for door {} in plant {} is back online", self.dock_name, self.plant_id);
+            return Some(DockDoorEvent::DoorOnline(DoorOnlineEvent {
+                plant_id: self.plant_id.clone(),
+                dock_name: self.dock_name.clone(),
+                offline_since,
+                timestamp: now,
+            }));
+        }
+
+        self.plc_health.consecutive_failures = self.plc_health.consecutive_failures.saturating_add(1);
+        if !self.plc_health.online || self.plc_health.consecutive_failures < offline_after_failures.max(1) {
+            return None;
+        }
+
+        self.plc_health.online = false;
+        self.plc_health.offline_since = Some(now);
+        let sensor_names: Vec<String> = self.sensors.keys().cloned().collect();
+        self.mark_sensors_unknown(&sensor_names);
+        warn!(
+            "PLC for door {} in plant {} is offline after {} failed polling cycles: {}",
+            self.dock_name, self.plant_id, self.plc_health.consecutive_failures,
+            self.plc_health.last_error.as_deref().unwrap_or("no error reported")
+        );
+        Some(DockDoorEvent::DoorOffline(DoorOfflineEvent {
+            plant_id: self.plant_id.clone(),
+            dock_name: self.dock_name.clone(),
+            consecutive_failures: self.plc_health.consecutive_failures,
+            last_good_read: self.plc_health.last_good_read,
+            reason: self.plc_health.last_error.clone(),
+            timestamp: now,
+        }))
+    }
+
+    /// Returns the total number of glitches suppressed by the debounce of the door's sensors
+    pub fn suppressed_glitches(&self) -> u64 {
+        self.sensors.values().map(|sensor| sensor.get_sensor_data().suppressed_glitches).sum()
+    }
+
+    /// Restores the door's state from a snapshot of the same door
+    ///
+    /// The door state, loading status, docking times and shipment history are taken from the snapshot,
+    /// while the PLC address, sensors and PLC health stay as configured, with every sensor value unknown.
+    /// The door is then awaiting reconciliation: the first read of each sensor is compared against the
+    /// restored state and produces the events for anything that changed while the service was down
+    ///
+    /// # Arguments
+    ///
+    /// * `saved`: The door as it was saved in the snapshot
+    pub fn restore_from(&mut self, saved: DockDoor) {
+        let dock_ip = std::mem::take(&mut self.dock_ip);
+        let sensors = std::mem::take(&mut self.sensors);
+        *self = DockDoor {
+            dock_ip,
+            sensors,
+            plc_health: PlcHealthState::default(),
+            awaiting_reconciliation: true,
+            ..saved
+        };
+    }
+
+    /// Returns `true` once every sensor of the door has a known value
+    pub fn all_sensors_known(&self) -> bool {
+        self.sensors.values().all(|sensor| sensor.get_sensor_data().current_value.is_some())
+    }
+
+    /// Marks sensors as unknown, keeping their last known value as the previous value
+    ///
+    /// The door awaits reconciliation once any of its sensors loses its value, so that the next reads report
+    /// what changed while the values were unknown
+    ///
+    /// # Arguments
+    ///
+    /// * `sensor_names`: The names of the sensors whose values can no longer be trusted
+    pub fn mark_sensors_unknown(&mut self, sensor_names: &[String]) {
+        for sensor_name in sensor_names {
+            if let Some(sensor) = self.sensors.get_mut(sensor_name) {
+                if sensor.get_sensor_data().current_value.is_some() {
+                    sensor.update_value(None);
+                    self.awaiting_reconciliation = true;
+                }
+            }
+        }
+    }
+
+    /// Moves the door to a new state, checking the change against the door state transition table
+    ///
+    /// Every change of `door_state` goes through this method. A change the table allows produces a
+    /// `DoorStateChanged` event. Any other change is still applied, so the door keeps following the PLC and
+    /// the WMS, but produces a `DoorStateAnomaly` event carrying its cause instead
+    ///
+    /// # Arguments
+    ///
+    /// * `new_state`: The state to move the door to
+    /// * `cause`: What caused the change
+    /// * `timestamp`: When the change happened
+    ///
+    /// # Returns
+    ///
+    /// * `Some(DockDoorEvent)` describing the change, or `None` if the door already was in `new_state`
+    pub fn transition_door_state(&mut self, new_state: DoorState, cause: TransitionCause, timestamp: NaiveDateTime) -> Option<DockDoorEvent> {
+        let old_state = self.door_state;
+        if old_state == new_state {
+            return None;
+        }
+        self.previous_door_state = old_state;
+        self.door_state = new_state;
+
+        if old_state.can_transition_to(new_state) {
+            Some(DockDoorEvent::DoorStateChanged(DoorStateChangedEvent {
+                plant_id: self.plant_id.clone(),
+                dock_name: self.dock_name.clone(),
+                old_state,
+                new_state,
+                timestamp,
+            }))
+        } else {
+            warn!("Illegal door state transition for {}: {:?} -> {:?} caused by {}", self.dock_name, old_state, new_state, cause);
+            Some(DockDoorEvent::DoorStateAnomaly(DoorStateAnomalyEvent {
+                plant_id: self.plant_id.clone(),
+                dock_name: self.dock_name.clone(),
+                old_state,
+                new_state,
+                cause,
+                timestamp,
+            }))
+        }
+    }
+
     /// Handles an incoming `DockDoorEvent`, updating the door's state accordingly
     ///
     /// This method dispatches the event to the appropriate handler function based on its type
@@ -233,23 +453,31 @@ impl DockDoor {
     /// * `event`: The `DockDoorEvent` to be handled
     ///
     /// # Returns
-    /// * `Ok(())` if the event was handled successfully
+    /// * `Ok(Some(DockDoorEvent))` with the `DoorStateAnomaly` event if handling the event made an illegal state transition
+    /// * `Ok(None)` if the event was handled successfully otherwise
     /// * `Err(DockManagerError)` if an error occurred during event handling
-    pub fn handle_event(&mut self, event: &DockDoorEvent) -> Result<(), DockManagerError> {
-        match event {
-            DockDoorEvent::DockAssigned(e) => self.handle_dock_assigned(e),
-            DockDoorEvent::DockUnassigned(e) => self.handle_dock_unassigned(e),
-            DockDoorEvent::TrailerDocked(e) => self.handle_trailer_docked(e),
-            DockDoorEvent::TrailerDeparted(e) => self.handle_trailer_departed(e),
-            DockDoorEvent::LoadingStarted(e) => self.handle_loading_started(e),
-            DockDoorEvent::LoadingCompleted(e) => self.handle_loading_completed(e),
-            DockDoorEvent::SensorStateChanged(e) => self.handle_sensor_state_changed(e),
-            DockDoorEvent::DoorStateChanged(e) => self.handle_door_state_changed(e),
-            DockDoorEvent::LoadingStatusChanged(e) => self.handle_loading_status_changed(e),
-            DockDoorEvent::TrailerStateChanged(e) => self.handle_trailer_state_changed(e),
+    pub fn handle_event(&mut self, event: &DockDoorEvent) -> Result<Option<DockDoorEvent>, DockManagerError> {
+        let transition = match event {
+            DockDoorEvent::DockAssigned(e) => self.handle_dock_assigned(e)?,
+            DockDoorEvent::DockUnassigned(e) => self.handle_dock_unassigned(e)?,
+            DockDoorEvent::TrailerDocked(e) => self.handle_trailer_docked(e)?,
+            DockDoorEvent::TrailerDeparted(e) => self.handle_trailer_departed(e)?,
+            DockDoorEvent::LoadingStarted(e) => self.handle_loading_started(e)?,
+            DockDoorEvent::LoadingCompleted(e) => self.handle_loading_completed(e)?,
+            DockDoorEvent::SensorStateChanged(e) => self.handle_sensor_state_changed(e).map(|_| None)?,
+            DockDoorEvent::DoorStateChanged(e) => self.handle_door_state_changed(e)?,
+            DockDoorEvent::LoadingStatusChanged(e) => self.handle_loading_status_changed(e).map(|_| None)?,
+            DockDoorEvent::TrailerStateChanged(e) => self.handle_trailer_state_changed(e).map(|_| None)?,
+            DockDoorEvent::DoorStateAnomaly(e) => self.handle_door_state_anomaly(e).map(|_| None)?,
             // Add handlers for other events as needed
-            _ => Ok(()),
-        }
+            _ => None,
+        };
+        Ok(transition.filter(|event| matches!(event, DockDoorEvent::DoorStateAnomaly(_))))
+    }
+
+    /// Moves the door to a new state on behalf of a door event
+    fn transition_for_event(&mut self, new_state: DoorState, event_type: &str, timestamp: NaiveDateTime) -> Option<DockDoorEvent> {
+        self.transition_door_state(new_state, TransitionCause::Event { event_type: event_type.to_string() }, timestamp)
     }
 
     /// Handles a `DockAssignedEvent`, updating the door's state and shipment information
@@ -264,19 +492,19 @@ impl DockDoor {
     ///
     /// # Returns
     ///
-    /// * `Ok(())` if the event was handled successfully
-    fn handle_dock_assigned(&mut self, event: &DockAssignedEvent) -> Result<(), DockManagerError> {
+    /// * `Ok(Some(DockDoorEvent))` describing the state transition, if the door's state changed
+    fn handle_dock_assigned(&mut self, event: &DockAssignedEvent) -> Result<Option<DockDoorEvent>, DockManagerError> {
         let state_shipment = self.assigned_shipment.current_shipment.clone();
         let event_shipment = Some(event.shipment_id.clone());
         if state_shipment != event_shipment {
-            self.door_state = DoorState::Assigned;
+            let transition = self.transition_for_event(DoorState::Assigned, "DockAssigned", event.timestamp);
             self.assigned_shipment.previous_shipment = self.assigned_shipment.current_shipment.clone();
             self.assigned_shipment.current_shipment = Some(event.shipment_id.clone());
             self.assigned_shipment.assignment_dttm = Some(event.timestamp);
             self.last_updated = event.timestamp;
-            return Ok(());
+            return Ok(transition);
         }
-        Ok(())
+        Ok(None)
     }
 
     /// Handles a `DockUnassignedEvent`, updating the door's state and shipment information
@@ -290,14 +518,14 @@ impl DockDoor {
     ///
     /// # Returns
     ///
-    /// * `Ok(())` if the event was handled successfully
-    fn handle_dock_unassigned(&mut self, event: &DockUnassignedEvent) -> Result<(), DockManagerError> {
-        self.door_state = DoorState::Unassigned;
+    /// * `Ok(Some(DockDoorEvent))` describing the state transition, if the door's state changed
+    fn handle_dock_unassigned(&mut self, event: &DockUnassignedEvent) -> Result<Option<DockDoorEvent>, DockManagerError> {
+        let transition = self.transition_for_event(DoorState::Unassigned, "DockUnassigned", event.timestamp);
         self.assigned_shipment.previous_shipment = self.assigned_shipment.current_shipment.clone();
         self.assigned_shipment.current_shipment = None;
         self.assigned_shipment.assignment_dttm = Some(event.timestamp);
         self.last_updated = event.timestamp;
-        Ok(())
+        Ok(transition)
     }
 
     /// Handles a `TrailerDockedEvent`, updating the door and trailer states
@@ -311,13 +539,13 @@ impl DockDoor {
     ///
     /// # Returns
     ///
-    /// * `Ok(())` if the event was handled successfully
-    fn handle_trailer_docked(&mut self, event: &TrailerDockedEvent) -> Result<(), DockManagerError> {
+    /// * `Ok(Some(DockDoorEvent))` describing the state transition, if the door's state changed
+    fn handle_trailer_docked(&mut self, event: &TrailerDockedEvent) -> Result<Option<DockDoorEvent>, DockManagerError> {
         info!("TrailerDockedEvent: {:?}", event);
         self.trailer_state = TrailerState::Docked;
-        self.door_state = DoorState::TrailerDocked;
+        let transition = self.transition_for_event(DoorState::TrailerDocked, "TrailerDocked", event.timestamp);
         self.last_updated = event.timestamp;
-        Ok(())
+        Ok(transition)
     }
 
     /// Handles a `TrailerDepartedEvent`, updating the door and trailer states
@@ -331,14 +559,14 @@ impl DockDoor {
     ///
     /// # Returns
     ///
-    /// * `Ok(())` if the event was handled successfully
-    fn handle_trailer_departed(&mut self, event: &TrailerDepartedEvent) -> Result<(), DockManagerError> {
+    /// * `Ok(Some(DockDoorEvent))` describing the state transition, if the door's state changed
+    fn handle_trailer_departed(&mut self, event: &TrailerDepartedEvent) -> Result<Option<DockDoorEvent>, DockManagerError> {
         info!("TrailerDepartedEvent: {:?}", event);
 
         self.trailer_state = TrailerState::Undocked;
-        self.door_state = DoorState::WaitingForExit;
+        let transition = self.transition_for_event(DoorState::WaitingForExit, "TrailerDeparted", event.timestamp);
         self.last_updated = event.timestamp;
-        Ok(())
+        Ok(transition)
     }
 
     /// Handles a `LoadingStartedEvent`, updating the loading status and door state
@@ -352,12 +580,12 @@ impl DockDoor {
     ///
     /// # Returns
     ///
-    /// * `Ok(())` if the event was handled successfully
-    fn handle_loading_started(&mut self, event: &LoadingStartedEvent) -> Result<(), DockManagerError> {
+    /// * `Ok(Some(DockDoorEvent))` describing the state transition, if the door's state changed
+    fn handle_loading_started(&mut self, event: &LoadingStartedEvent) -> Result<Option<DockDoorEvent>, DockManagerError> {
         self.loading_status.loading_status = LoadingStatus::Loading;
-        self.door_state = DoorState::Loading;
+        let transition = self.transition_for_event(DoorState::Loading, "LoadingStarted", event.timestamp);
         self.last_updated = event.timestamp;
-        Ok(())
+        Ok(transition)
     }
 
     /// Handles a `LoadingCompletedEvent`, updating the loading status and door state
@@ -371,12 +599,12 @@ impl DockDoor {
     ///
     /// # Returns
     ///
-    /// * `Ok(())` if the event was handled successfully
-    fn handle_loading_completed(&mut self, event: &LoadingCompletedEvent) -> Result<(), DockManagerError> {
+    /// * `Ok(Some(DockDoorEvent))` describing the state transition, if the door's state changed
+    fn handle_loading_completed(&mut self, event: &LoadingCompletedEvent) -> Result<Option<DockDoorEvent>, DockManagerError> {
         self.loading_status.loading_status = LoadingStatus::Completed;
-        self.door_state = DoorState::LoadingCompleted;
+        let transition = self.transition_for_event(DoorState::LoadingCompleted, "LoadingCompleted", event.timestamp);
         self.last_updated = event.timestamp;
-        Ok(())
+        Ok(transition)
     }
 
     /// Handles a `SensorStateChangedEvent`, updating the corresponding sensor's value
@@ -410,10 +638,31 @@ impl DockDoor {
     ///
     /// # Returns
     ///
-    /// * `Ok(())` if the event was handled successfully
-    fn handle_door_state_changed(&mut self, event: &DoorStateChangedEvent) -> Result<(), DockManagerError> {
-        self.door_state = event.clone().new_state;
+    /// * `Ok(Some(DockDoorEvent))` describing the state transition, if the door's state changed
+    fn handle_door_state_changed(&mut self, event: &DoorStateChangedEvent) -> Result<Option<DockDoorEvent>, DockManagerError> {
+        let transition = self.transition_for_event(event.new_state, "DoorStateChanged", event.timestamp);
         self.last_updated = event.timestamp;
+        Ok(transition)
+    }
+
+    /// Handles a `DoorStateAnomalyEvent`, putting the door in the state the illegal transition moved it to
+    ///
+    /// The door already made the transition when the anomaly was raised, so this only matters when the event is
+    /// replayed from the journal; no further event is raised for it
+    ///
+    /// # Arguments
+    ///
+    /// * `event`: The `DoorStateAnomalyEvent` to be handled
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` if the event was handled successfully
+    fn handle_door_state_anomaly(&mut self, event: &DoorStateAnomalyEvent) -> Result<(), DockManagerError> {
+        if self.door_state != event.new_state {
+            self.previous_door_state = self.door_state;
+            self.door_state = event.new_state;
+            self.last_updated = event.timestamp;
+        }
         Ok(())
     }
 
@@ -449,6 +698,46 @@ impl DockDoor {
         Ok(())
     }
 
+    /// Follows the visit of the trailer at the door through a door event
+    ///
+    /// A trailer docking starts a new visit, closing the previous one if its departure was missed. While a
+    /// visit is open, the milestones of the events are recorded in it, and the trailer leaving completes it.
+    ///
+    /// # Arguments
+    ///
+    /// * `event`: The `DockDoorEvent` of the door
+    ///
+    /// # Returns
+    ///
+    /// * `Some(TrailerVisit)` with the visit the event completed, if any
+    /// * `None` otherwise
+    pub fn track_visit(&mut self, event: &DockDoorEvent) -> Option<TrailerVisit> {
+        match event {
+            DockDoorEvent::TrailerStateChanged(e) if e.new_state == TrailerState::Docked => {
+                let previous = self.current_visit.take();
+                if let Some(visit) = &previous {
+                    warn!("Door {} docked a trailer before visit {} departed, closing it", self.dock_name, visit.visit_id);
+                }
+                let visit = TrailerVisit::start(&self.plant_id, &self.dock_name, self.assigned_shipment.current_shipment.clone(), e.timestamp);
+                info!("Door {} started trailer visit {}", self.dock_name, visit.visit_id);
+                self.current_visit = Some(visit);
+                previous
+            },
+            DockDoorEvent::TrailerStateChanged(e) if e.new_state == TrailerState::Undocked => {
+                let mut visit = self.current_visit.take()?;
+                visit.departed_at = Some(e.timestamp);
+                info!("Door {} completed trailer visit {}", self.dock_name, visit.visit_id);
+                Some(visit)
+            },
+            _ => {
+                if let Some(visit) = self.current_visit.as_mut() {
+                    visit.record(event);
+                }
+                None
+            },
+        }
+    }
+
     /// Sets the manual mode of the door
     ///
     /// Updates the `manual_mode` field and the `last_updated` timestamp
@@ -461,9 +750,13 @@ impl DockDoor {
         self.last_updated = chrono::Local::now().naive_local();
     }
 
-    /// Sets the docking time to the current time
-    pub fn set_docking_time(&mut self) {
-        self.consolidated.docking_time = Some(chrono::Local::now().naive_local());
+    /// Sets the docking time
+    ///
+    /// # Arguments
+    ///
+    /// * `docking_time`: The time the trailer docked
+    pub fn set_docking_time(&mut self, docking_time: NaiveDateTime) {
+        self.consolidated.docking_time = Some(docking_time);
     }
 
     /// Clears the docking time
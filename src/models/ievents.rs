@@ -1,8 +1,8 @@
 use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
-use crate::models::istates::DoorState;
+use crate::models::istates::{DoorState, TransitionCause};
 use crate::models::istatus::LoadingStatus;
-use crate::models::{DbInsert, TrailerState, WmsEvent};
+use crate::models::{DbInsert, SensorValue, TrailerState, WmsEvent};
 
 /// Represents the different types of events that can occur at a dock door
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -37,6 +37,10 @@ pub enum DockDoorEvent {
     AppointmentUpdated(AppointmentUpdatedEvent),
     TripProcessed(TripProcessedEvent),
     UnknownWmsEvent(UnknownWmsEventEvent),
+    DoorOffline(DoorOfflineEvent),
+    DoorOnline(DoorOnlineEvent),
+    DoorStateAnomaly(DoorStateAnomalyEvent),
+    WmsPlcDiscrepancy(WmsPlcDiscrepancyEvent),
 }
 
 impl DockDoorEvent {
@@ -72,6 +76,10 @@ impl DockDoorEvent {
             DockDoorEvent::AppointmentUpdated(e) => &e.base_event.dock_name,
             DockDoorEvent::TripProcessed(e) => &e.base_event.dock_name,
             DockDoorEvent::UnknownWmsEvent(e) => &e.base_event.dock_name,
+            DockDoorEvent::DoorOffline(e) => &e.dock_name,
+            DockDoorEvent::DoorOnline(e) => &e.dock_name,
+            DockDoorEvent::DoorStateAnomaly(e) => &e.dock_name,
+            DockDoorEvent::WmsPlcDiscrepancy(e) => &e.dock_name,
         }
     }
 
@@ -107,6 +115,10 @@ impl DockDoorEvent {
             DockDoorEvent::AppointmentUpdated(e) => &e.base_event.plant_id,
             DockDoorEvent::TripProcessed(e) => &e.base_event.plant_id,
             DockDoorEvent::UnknownWmsEvent(e) => &e.base_event.plant_id,
+            DockDoorEvent::DoorOffline(e) => &e.plant_id,
+            DockDoorEvent::DoorOnline(e) => &e.plant_id,
+            DockDoorEvent::DoorStateAnomaly(e) => &e.plant_id,
+            DockDoorEvent::WmsPlcDiscrepancy(e) => &e.plant_id,
         }
     }
 
@@ -190,8 +202,28 @@ impl DockDoorEvent {
             DockDoorEvent::AppointmentUpdated(e) => Some(e.base_event.shipment_id.clone()),
             DockDoorEvent::TripProcessed(e) => Some(e.base_event.shipment_id.clone()),
             DockDoorEvent::UnknownWmsEvent(e) => Some(e.base_event.shipment_id.clone()),
+            DockDoorEvent::DoorOffline(_) => None,
+            DockDoorEvent::DoorOnline(_) => None,
+            DockDoorEvent::DoorStateAnomaly(_) => None,
+            DockDoorEvent::WmsPlcDiscrepancy(e) => e.shipment_id.clone(),
         }
     }
+
+    /// Returns `true` if the processor that raised the event already applied it to the door
+    ///
+    /// Such events describe a change of the door's sensors or state made in the repository before the event was
+    /// published, so handling them again could undo a later change; they are only handled when the door is rebuilt
+    /// from the journal.
+    pub fn is_applied_at_source(&self) -> bool {
+        matches!(
+            self,
+            DockDoorEvent::SensorStateChanged(_)
+                | DockDoorEvent::DoorStateChanged(_)
+                | DockDoorEvent::DoorStateAnomaly(_)
+                | DockDoorEvent::LoadingStatusChanged(_)
+                | DockDoorEvent::TrailerStateChanged(_)
+        )
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -281,11 +313,43 @@ pub struct SensorStateChangedEvent {
     pub plant_id: String,
     pub dock_name: String,
     pub sensor_name: String,
-    pub old_value: Option<u8>,
-    pub new_value: Option<u8>,
+    pub old_value: Option<SensorValue>,
+    pub new_value: Option<SensorValue>,
     pub timestamp: NaiveDateTime,
 }
 
+impl SensorStateChangedEvent {
+    /// Returns `true` if the sensor's new value is on
+    pub fn is_on(&self) -> bool {
+        self.new_value.is_some_and(|value| value.is_on())
+    }
+
+    /// Returns `true` if the sensor's new value is known and off
+    pub fn is_off(&self) -> bool {
+        self.new_value.is_some_and(|value| !value.is_on())
+    }
+
+    /// Returns `true` if the sensor's old value was on
+    pub fn was_on(&self) -> bool {
+        self.old_value.is_some_and(|value| value.is_on())
+    }
+
+    /// Returns `true` if the sensor's old value was known and off
+    pub fn was_off(&self) -> bool {
+        self.old_value.is_some_and(|value| !value.is_on())
+    }
+
+    /// Returns `true` if the sensor switched from off to on
+    pub fn turned_on(&self) -> bool {
+        self.was_off() && self.is_on()
+    }
+
+    /// Returns `true` if the sensor switched from on to off
+    pub fn turned_off(&self) -> bool {
+        self.was_on() && self.is_off()
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DoorStateChangedEvent {
     pub plant_id: String,
@@ -295,6 +359,36 @@ pub struct DoorStateChangedEvent {
     pub timestamp: NaiveDateTime,
 }
 
+/// Raised instead of a `DoorStateChanged` event when a door's state changed in a way its transition table does not allow
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DoorStateAnomalyEvent {
+    pub plant_id: String,
+    pub dock_name: String,
+    pub old_state: DoorState,
+    pub new_state: DoorState,
+    /// What caused the change
+    pub cause: TransitionCause,
+    pub timestamp: NaiveDateTime,
+}
+
+/// Raised when what the WMS reports for a door has contradicted its sensors for longer than the grace period
+/// of a contradiction pattern, and again with `resolved` set once the contradiction is gone
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WmsPlcDiscrepancyEvent {
+    pub plant_id: String,
+    pub dock_name: String,
+    pub shipment_id: Option<String>,
+    /// The name of the contradiction pattern the door matched
+    pub pattern: String,
+    /// The WMS and sensor state that contradicted each other
+    pub details: String,
+    /// When the contradiction was first seen
+    pub since: NaiveDateTime,
+    /// Whether the contradiction is gone
+    pub resolved: bool,
+    pub timestamp: NaiveDateTime,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LoadingStatusChangedEvent {
     pub plant_id: String,
@@ -313,6 +407,26 @@ pub struct TrailerStateChangedEvent {
     pub timestamp: NaiveDateTime,
 }
 
+/// Raised when a door's PLC has failed to answer for `offline_after_failures` polling cycles in a row
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DoorOfflineEvent {
+    pub plant_id: String,
+    pub dock_name: String,
+    pub consecutive_failures: u32,
+    pub last_good_read: Option<NaiveDateTime>,
+    pub reason: Option<String>,
+    pub timestamp: NaiveDateTime,
+}
+
+/// Raised when a door's PLC answers again after having been reported offline
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DoorOnlineEvent {
+    pub plant_id: String,
+    pub dock_name: String,
+    pub offline_since: Option<NaiveDateTime>,
+    pub timestamp: NaiveDateTime,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ShipmentAssignedEvent {
     pub plant_id: String,
@@ -2,10 +2,119 @@
 
 //! This module defines the `DockSensor` enum and the `SensorData` struct, which together represent the various sensors 
 //! associated with a dock door and the data collected from those sensors. The `SensorType` enum provides a type-safe 
-//! way to identify different sensor types.
+//! way to identify different sensor types, and the `SensorValue` enum holds a typed sensor reading.
 
+use std::fmt;
 use chrono::{Local, NaiveDateTime};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use crate::config::{DebounceSettings, PlcElementType, SensorMeaning};
+
+/// A typed value read from a sensor
+///
+/// Discrete sensors report `Bool` values (or `Int` values of 0 and 1 when no data type is configured for the tag),
+/// while analog sensors such as `TRAILER_ANGLE` or `TRAILER_DISTANCE` report `Int` or `Float` measurements.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum SensorValue {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+}
+
+impl SensorValue {
+    /// Returns `true` if the value is set, i.e. `true` or non-zero
+    pub fn is_on(&self) -> bool {
+        match self {
+            SensorValue::Bool(value) => *value,
+            SensorValue::Int(value) => *value != 0,
+            SensorValue::Float(value) => *value != 0.0,
+        }
+    }
+
+    /// Returns the value as a number, with `true` and `false` mapped to 1 and 0
+    pub fn as_f64(&self) -> f64 {
+        match self {
+            SensorValue::Bool(value) => f64::from(u8::from(*value)),
+            SensorValue::Int(value) => *value as f64,
+            SensorValue::Float(value) => *value,
+        }
+    }
+}
+
+impl From<bool> for SensorValue {
+    fn from(value: bool) -> Self {
+        SensorValue::Bool(value)
+    }
+}
+
+impl From<u8> for SensorValue {
+    fn from(value: u8) -> Self {
+        SensorValue::Int(i64::from(value))
+    }
+}
+
+impl From<i64> for SensorValue {
+    fn from(value: i64) -> Self {
+        SensorValue::Int(value)
+    }
+}
+
+impl From<f64> for SensorValue {
+    fn from(value: f64) -> Self {
+        SensorValue::Float(value)
+    }
+}
+
+impl fmt::Display for SensorValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SensorValue::Bool(value) => write!(f, "{}", value),
+            SensorValue::Int(value) => write!(f, "{}", value),
+            SensorValue::Float(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+/// Translates raw values read from a PLC tag into the values the door state is derived from
+///
+/// Once translated, an on value (`true` or non-zero) always means the sensor is active, whatever the wiring.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ValueMapping {
+    /// Whether the input reads 0 while the sensor is active
+    pub active_low: bool,
+    /// The meaning of specific raw values, keyed by the integer they read as (e.g. "0" or "2");
+    /// `Bool` values are keyed as "0" and "1"
+    pub value_map: HashMap<String, SensorMeaning>,
+}
+
+impl ValueMapping {
+    /// Translates a raw value
+    ///
+    /// Values listed in `value_map` become `Bool` values according to their meaning; otherwise an active-low
+    /// value is inverted into a `Bool`, and any other value is returned unchanged.
+    ///
+    /// # Arguments
+    ///
+    /// * `raw`: The value read from the PLC
+    pub fn apply(&self, raw: SensorValue) -> SensorValue {
+        if let Some(meaning) = self.value_map.get(&Self::map_key(raw)) {
+            return SensorValue::Bool(*meaning == SensorMeaning::Active);
+        }
+        if self.active_low {
+            return SensorValue::Bool(!raw.is_on());
+        }
+        raw
+    }
+
+    /// Returns the `value_map` key of a raw value, reading `Bool` values as 0 and 1 like the PLC does
+    fn map_key(raw: SensorValue) -> String {
+        match raw {
+            SensorValue::Bool(value) => u8::from(value).to_string(),
+            other => other.to_string(),
+        }
+    }
+}
 
 /// Represents the different types of sensors that can be associated with a dock door.
 /// Each sensor type holds its specific `SensorData`.
@@ -41,14 +150,62 @@ pub struct SensorData {
     pub sensor: String,
     /// The PLC address where the sensor's value is stored
     pub address: String,
+    /// The data type configured for the sensor's tag, if any
+    pub data_type: Option<PlcElementType>,
     /// The current value read from the sensor
-    pub current_value: Option<u8>,
+    pub current_value: Option<SensorValue>,
     /// The previous value read from the sensor
-    pub previous_value: Option<u8>,
+    pub previous_value: Option<SensorValue>,
     /// The timestamp of the last sensor update
     pub last_updated: NaiveDateTime,
     /// Information about the last reported change in the sensor's value
-    pub last_reported_change: Option<(Option<u8>, Option<u8>)>,
+    pub last_reported_change: Option<(Option<SensorValue>, Option<SensorValue>)>,
+    /// The debounce configured for the sensor's tag, if any
+    pub debounce: Option<DebounceSettings>,
+    /// The largest difference between two analog reads that still counts as the same value, if configured
+    #[serde(default)]
+    pub deadband: Option<f64>,
+    /// How raw values read from the sensor's tag are translated
+    pub value_mapping: ValueMapping,
+    /// The last raw value read from the sensor, before translation
+    pub raw_value: Option<SensorValue>,
+    /// A changed value that has not yet held long enough to be accepted
+    pub pending_change: Option<PendingChange>,
+    /// The number of changes discarded because they reverted before being accepted
+    pub suppressed_glitches: u64,
+}
+
+/// A changed sensor value waiting out the sensor's debounce
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PendingChange {
+    /// The new value read from the sensor
+    pub value: SensorValue,
+    /// The time the new value was first read
+    pub first_seen: NaiveDateTime,
+    /// The number of consecutive reads that reported the new value
+    pub samples: u32,
+}
+
+impl SensorData {
+    /// Returns `true` if the sensor reports a measurement rather than an on/off state
+    pub fn is_analog(&self) -> bool {
+        matches!(self.data_type, Some(PlcElementType::Sint | PlcElementType::Int | PlcElementType::Dint | PlcElementType::Real))
+    }
+
+    /// Returns `true` if two values read from the sensor count as the same value
+    ///
+    /// `Bool` values must be equal, while numbers may differ by up to the sensor's deadband.
+    ///
+    /// # Arguments
+    ///
+    /// * `a`: The first value
+    /// * `b`: The second value
+    pub fn same_value(&self, a: SensorValue, b: SensorValue) -> bool {
+        match (a, b, self.deadband) {
+            (SensorValue::Bool(_), _, _) | (_, SensorValue::Bool(_), _) | (_, _, None) => a == b,
+            (_, _, Some(deadband)) => (a.as_f64() - b.as_f64()).abs() <= deadband,
+        }
+    }
 }
 
 /// Provides a type-safe representation of the different sensor types
@@ -113,6 +270,9 @@ impl DockSensor {
     /// * `door_ip`: The IP address of the PLC controlling the door
     /// * `sensor_type`: A string representing the type of sensor
     /// * `address`: The PLC address where the sensor's value is stored
+    /// * `data_type`: The data type configured for the sensor's tag, if any
+    /// * `debounce`: The debounce configured for the sensor's tag, if any
+    /// * `value_mapping`: How raw values read from the sensor's tag are translated
     ///
     /// # Returns
     ///
@@ -121,16 +281,31 @@ impl DockSensor {
     /// # Panics
     ///
     /// This function will panic if an unknown `sensor_type` is provided
-    pub fn new(door_name: &str, door_ip: &str, sensor_type: &str, address: &str) -> Self {
+    pub fn new(
+        door_name: &str,
+        door_ip: &str,
+        sensor_type: &str,
+        address: &str,
+        data_type: Option<PlcElementType>,
+        debounce: Option<DebounceSettings>,
+        value_mapping: ValueMapping,
+    ) -> Self {
         let sensor_data = SensorData {
             door_name: door_name.to_string(),
             door_ip: door_ip.to_string(),
             sensor: sensor_type.to_string(),
             address: address.to_string(),
+            data_type,
             current_value: None,
             previous_value: None,
             last_updated: Local::now().naive_local(),
             last_reported_change: None,
+            debounce,
+            deadband: None,
+            value_mapping,
+            raw_value: None,
+            pending_change: None,
+            suppressed_glitches: 0,
         };
         match sensor_type {
             "AUTO_DISENGAGING" => DockSensor::AutoDisengaging(sensor_data),
@@ -154,18 +329,95 @@ impl DockSensor {
         }
     }
 
+    /// Sets the deadband within which analog reads count as the same value
+    ///
+    /// # Arguments
+    ///
+    /// * `deadband`: The deadband configured for the sensor's tag, if any
+    pub fn with_deadband(mut self, deadband: Option<f64>) -> Self {
+        self.get_sensor_data_mut().deadband = deadband;
+        self
+    }
+
     /// Updates the sensor's value and metadata
     ///
     /// # Arguments
     ///
     /// * `new_value`: The new value read from the sensor
-    pub fn update_value(&mut self, new_value: Option<u8>) {
+    pub fn update_value(&mut self, new_value: Option<SensorValue>) {
         let sensor_data = self.get_sensor_data_mut();
         sensor_data.previous_value = sensor_data.current_value;
         sensor_data.current_value = new_value;
+        sensor_data.pending_change = None;
         sensor_data.last_updated = Local::now().naive_local();
     }
 
+    /// Translates a raw value read from the sensor with its `ValueMapping`, remembering the raw value
+    ///
+    /// # Arguments
+    ///
+    /// * `raw`: The value read from the PLC
+    ///
+    /// # Returns
+    ///
+    /// The translated value
+    pub fn translate(&mut self, raw: SensorValue) -> SensorValue {
+        let sensor_data = self.get_sensor_data_mut();
+        sensor_data.raw_value = Some(raw);
+        sensor_data.value_mapping.apply(raw)
+    }
+
+    /// Passes a value read from the sensor through the sensor's debounce
+    ///
+    /// A read that matches the current value cancels any pending change, counting it as a suppressed glitch.
+    /// A read that differs starts or continues a pending change, which is accepted once it satisfies the
+    /// configured debounce. Sensors without a debounce accept every read that differs, and sensors without a
+    /// known current value accept every read. Analog reads within the sensor's deadband of the current value
+    /// count as matching it, and reads within the deadband of the pending value continue the pending change,
+    /// so a jittering measurement settles once it holds near its new value.
+    ///
+    /// # Arguments
+    ///
+    /// * `value`: The value read from the sensor
+    /// * `now`: The time of the read
+    ///
+    /// # Returns
+    ///
+    /// `true` if the value should be applied to the sensor, `false` if it is held back or unchanged
+    pub fn debounce(&mut self, value: SensorValue, now: NaiveDateTime) -> bool {
+        let sensor_data = self.get_sensor_data_mut();
+        let Some(current_value) = sensor_data.current_value else {
+            return true;
+        };
+        let Some(debounce) = sensor_data.debounce else {
+            return !sensor_data.same_value(current_value, value);
+        };
+
+        if sensor_data.same_value(current_value, value) {
+            if sensor_data.pending_change.take().is_some() {
+                sensor_data.suppressed_glitches += 1;
+            }
+            return false;
+        }
+
+        let pending = match sensor_data.pending_change {
+            Some(pending) if sensor_data.same_value(pending.value, value) => PendingChange { samples: pending.samples.saturating_add(1), ..pending },
+            previous => {
+                if previous.is_some() {
+                    sensor_data.suppressed_glitches += 1;
+                }
+                PendingChange { value, first_seen: now, samples: 1 }
+            }
+        };
+        let held_ms = now.signed_duration_since(pending.first_seen).num_milliseconds().max(0) as u64;
+        if pending.samples >= debounce.min_samples && held_ms >= debounce.min_stable_ms {
+            return true;
+        }
+
+        sensor_data.pending_change = Some(pending);
+        false
+    }
+
     /// Provides immutable access to the sensor's data
     ///
     /// # Returns
@@ -217,3 +469,122 @@ impl DockSensor {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn analog_sensor(deadband: Option<f64>) -> DockSensor {
+        DockSensor::new("DOOR_1", "10.0.0.1", "TRAILER_ANGLE", "F8:0", Some(PlcElementType::Real), None, ValueMapping::default())
+            .with_deadband(deadband)
+    }
+
+    fn debounced_sensor(data_type: PlcElementType, deadband: Option<f64>) -> DockSensor {
+        let debounce = DebounceSettings { min_stable_ms: 2000, min_samples: 3 };
+        DockSensor::new("DOOR_1", "10.0.0.1", "TRAILER_ANGLE", "F8:0", Some(data_type), Some(debounce), ValueMapping::default())
+            .with_deadband(deadband)
+    }
+
+    /// Passes a read through the debounce and applies it if accepted, like `DockDoor::update_sensor` does
+    fn read(sensor: &mut DockSensor, value: f64) -> bool {
+        read_at(sensor, SensorValue::Float(value), Local::now().naive_local())
+    }
+
+    fn read_at(sensor: &mut DockSensor, value: SensorValue, now: NaiveDateTime) -> bool {
+        let accepted = sensor.debounce(value, now);
+        if accepted {
+            sensor.update_value(Some(value));
+        }
+        accepted
+    }
+
+    /// Returns the time `seconds` after the first read of a test
+    fn at(seconds: i64) -> NaiveDateTime {
+        chrono::NaiveDate::from_ymd_opt(2024, 1, 1).unwrap().and_hms_opt(8, 0, 0).unwrap() + chrono::Duration::seconds(seconds)
+    }
+
+    #[test]
+    fn analog_noise_within_the_deadband_is_not_a_change() {
+        let mut sensor = analog_sensor(Some(0.5));
+
+        assert!(read(&mut sensor, 10.0));
+        assert!(!read(&mut sensor, 10.3));
+        assert!(!read(&mut sensor, 9.6));
+        assert!(read(&mut sensor, 10.6));
+        assert_eq!(sensor.get_sensor_data().current_value, Some(SensorValue::Float(10.6)));
+    }
+
+    #[test]
+    fn analog_values_must_match_exactly_without_a_deadband() {
+        let mut sensor = analog_sensor(None);
+
+        assert!(read(&mut sensor, 10.0));
+        assert!(!read(&mut sensor, 10.0));
+        assert!(read(&mut sensor, 10.001));
+    }
+
+    #[test]
+    fn deadband_does_not_apply_to_bool_values() {
+        let sensor = analog_sensor(Some(1.0));
+        let data = sensor.get_sensor_data();
+
+        assert!(!data.same_value(SensorValue::Bool(true), SensorValue::Bool(false)));
+        assert!(!data.same_value(SensorValue::Bool(true), SensorValue::Int(1)));
+        assert!(data.same_value(SensorValue::Int(3), SensorValue::Float(3.5)));
+    }
+
+    #[test]
+    fn bool_flicker_is_suppressed() {
+        let mut sensor = debounced_sensor(PlcElementType::Bool, None);
+        assert!(read_at(&mut sensor, SensorValue::Bool(false), at(0)));
+
+        assert!(!read_at(&mut sensor, SensorValue::Bool(true), at(1)));
+        assert!(!read_at(&mut sensor, SensorValue::Bool(false), at(2)));
+        assert!(!read_at(&mut sensor, SensorValue::Bool(true), at(3)));
+        assert!(!read_at(&mut sensor, SensorValue::Bool(false), at(4)));
+
+        let data = sensor.get_sensor_data();
+        assert_eq!(data.current_value, Some(SensorValue::Bool(false)));
+        assert_eq!(data.suppressed_glitches, 2);
+        assert_eq!(data.pending_change, None);
+    }
+
+    #[test]
+    fn stable_change_is_accepted_after_the_configured_reads() {
+        let mut sensor = debounced_sensor(PlcElementType::Bool, None);
+        assert!(read_at(&mut sensor, SensorValue::Bool(false), at(0)));
+
+        assert!(!read_at(&mut sensor, SensorValue::Bool(true), at(1)));
+        assert!(!read_at(&mut sensor, SensorValue::Bool(true), at(2)));
+        assert!(read_at(&mut sensor, SensorValue::Bool(true), at(3)));
+
+        let data = sensor.get_sensor_data();
+        assert_eq!(data.current_value, Some(SensorValue::Bool(true)));
+        assert_eq!(data.suppressed_glitches, 0);
+    }
+
+    #[test]
+    fn analog_jitter_within_the_deadband_settles() {
+        let mut sensor = debounced_sensor(PlcElementType::Real, Some(0.5));
+        assert!(read_at(&mut sensor, SensorValue::Float(10.0), at(0)));
+
+        assert!(!read_at(&mut sensor, SensorValue::Float(12.0), at(1)));
+        assert!(!read_at(&mut sensor, SensorValue::Float(12.3), at(2)));
+        assert!(read_at(&mut sensor, SensorValue::Float(11.8), at(3)));
+
+        let data = sensor.get_sensor_data();
+        assert_eq!(data.current_value, Some(SensorValue::Float(11.8)));
+        assert_eq!(data.suppressed_glitches, 0);
+    }
+
+    #[test]
+    fn analog_jitter_without_a_deadband_never_settles() {
+        let mut sensor = debounced_sensor(PlcElementType::Real, None);
+        assert!(read_at(&mut sensor, SensorValue::Float(10.0), at(0)));
+
+        assert!(!read_at(&mut sensor, SensorValue::Float(12.0), at(1)));
+        assert!(!read_at(&mut sensor, SensorValue::Float(12.3), at(2)));
+        assert!(!read_at(&mut sensor, SensorValue::Float(11.8), at(3)));
+
+        assert_eq!(sensor.get_sensor_data().current_value, Some(SensorValue::Float(10.0)));
+    }
+}
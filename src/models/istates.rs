@@ -1,8 +1,9 @@
+use std::fmt;
 use serde::{Deserialize, Serialize};
 use derive_more::FromStr;
 
 /// Represents the different states a dock door can be in.
-#[derive(Debug, Clone, PartialEq, Copy, Serialize, Deserialize, FromStr)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Copy, Serialize, Deserialize, FromStr)]
 pub enum DoorState {
     /// The door is not assigned to any shipment.
     Unassigned,
@@ -26,8 +27,61 @@ pub enum DoorState {
     WaitingForExit,
 }
 
+impl DoorState {
+    /// Returns `true` if a door may move from this state to `next` in the normal course of a shipment.
+    ///
+    /// Any state may be left for the states before the trailer is at the door, which follow the WMS
+    /// assignment; once loading has started, the door only moves forward until the trailer leaves or the
+    /// shipment is unassigned, and a completed load may be followed by the next assignment.
+    pub fn can_transition_to(&self, next: DoorState) -> bool {
+        use DoorState::*;
+        if *self == next {
+            return true;
+        }
+        match self {
+            Unassigned | Assigned | DriverCheckedIn => matches!(
+                next,
+                Unassigned | Assigned | DriverCheckedIn | TrailerApproaching | TrailerDocking | TrailerDocked | DoorReady
+            ),
+            TrailerApproaching | TrailerDocking => matches!(
+                next,
+                Unassigned | Assigned | DriverCheckedIn | TrailerApproaching | TrailerDocking | TrailerDocked
+            ),
+            TrailerDocked => matches!(next, Unassigned | Assigned | DriverCheckedIn | DoorReady | Loading | WaitingForExit),
+            DoorReady => matches!(next, Unassigned | Assigned | DriverCheckedIn | TrailerDocked | Loading),
+            Loading => matches!(next, Unassigned | DoorReady | LoadingCompleted | WaitingForExit),
+            LoadingCompleted => matches!(next, Unassigned | Assigned | WaitingForExit),
+            WaitingForExit => matches!(next, Unassigned | Assigned | TrailerDocked),
+        }
+    }
+}
+
+/// What caused a change of a door's state.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum TransitionCause {
+    /// A sensor of the door changed.
+    Sensor { sensor_name: String },
+    /// The loading status reported by the WMS changed.
+    WmsStatus { loading_status: String },
+    /// A door event was handled, e.g. a dock assignment received from the WMS.
+    Event { event_type: String },
+    /// An analysis rule requested the transition.
+    Rule,
+}
+
+impl fmt::Display for TransitionCause {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransitionCause::Sensor { sensor_name } => write!(f, "sensor {}", sensor_name),
+            TransitionCause::WmsStatus { loading_status } => write!(f, "WMS loading status {}", loading_status),
+            TransitionCause::Event { event_type } => write!(f, "event {}", event_type),
+            TransitionCause::Rule => write!(f, "analysis rule"),
+        }
+    }
+}
+
 /// Represents the two possible states of a trailer: docked or undocked.
-#[derive(Debug, Clone, PartialEq, Copy, Serialize, Deserialize, FromStr)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Copy, Serialize, Deserialize, FromStr)]
 pub enum TrailerState {
     /// The trailer is docked at a door.
     Docked,
@@ -108,4 +162,58 @@ pub enum LoadTypeState {
     LiveLoad,
     /// The Load is a Prelaod Load Shipment
     Preload,
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::DoorState::{self, *};
+
+    const ALLOWED: &[(DoorState, DoorState)] = &[
+        (Unassigned, Assigned),
+        (Assigned, TrailerDocked),
+        (DriverCheckedIn, TrailerApproaching),
+        (TrailerApproaching, TrailerDocking),
+        (TrailerDocking, TrailerDocked),
+        (TrailerDocked, DoorReady),
+        (TrailerDocked, WaitingForExit),
+        (DoorReady, Loading),
+        (Loading, DoorReady),
+        (Loading, LoadingCompleted),
+        (Loading, Unassigned),
+        (Loading, WaitingForExit),
+        (LoadingCompleted, Assigned),
+        (LoadingCompleted, Unassigned),
+        (LoadingCompleted, WaitingForExit),
+        (WaitingForExit, Unassigned),
+        (WaitingForExit, TrailerDocked),
+        (Loading, Loading),
+    ];
+
+    const REJECTED: &[(DoorState, DoorState)] = &[
+        (Unassigned, Loading),
+        (Assigned, LoadingCompleted),
+        (TrailerApproaching, DoorReady),
+        (TrailerDocking, Loading),
+        (TrailerDocked, LoadingCompleted),
+        (DoorReady, WaitingForExit),
+        (Loading, Assigned),
+        (Loading, TrailerDocked),
+        (LoadingCompleted, Loading),
+        (LoadingCompleted, DoorReady),
+        (WaitingForExit, Loading),
+        (WaitingForExit, LoadingCompleted),
+    ];
+
+    #[test]
+    fn allowed_transitions() {
+        for (from, to) in ALLOWED {
+            assert!(from.can_transition_to(*to), "{:?} -> {:?} should be allowed", from, to);
+        }
+    }
+
+    #[test]
+    fn rejected_transitions() {
+        for (from, to) in REJECTED {
+            assert!(!from.can_transition_to(*to), "{:?} -> {:?} should be rejected", from, to);
+        }
+    }
+}
@@ -10,7 +10,7 @@ use serde::{Deserialize, Serialize};
 use sqlx_oldapi::FromRow;
 
 /// Represents the various loading statuses a shipment can have in the WMS.
-#[derive(Debug, Clone, PartialEq, Copy, Serialize, Deserialize, FromStr, Display)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Copy, Serialize, Deserialize, FromStr, Display)]
 pub enum LoadingStatus {
     /// The dock is idle, not assigned to any shipment.
     Idle,
@@ -6,6 +6,13 @@ pub mod ievents;
 pub mod istates;
 pub mod idb_log;
 pub mod consolidated_dock_event;
+pub mod trailer_visit;
+pub mod shipment_record;
+pub mod plant_occupancy;
+pub mod plc_address;
+pub mod modbus_address;
+#[cfg(test)]
+pub mod test_support;
 
 pub use idoor::*;
 pub use istatus::*;
@@ -13,6 +20,11 @@ pub use isensor::*;
 pub use ievents::*;
 pub use istates::*;
 pub use idb_log::*;
+pub use trailer_visit::*;
+pub use shipment_record::*;
+pub use plant_occupancy::*;
+pub use plc_address::*;
+pub use modbus_address::*;
 
 use chrono::{Local, NaiveDateTime};
 use serde::{Deserialize, Serialize};
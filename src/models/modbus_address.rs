@@ -0,0 +1,157 @@
+//! # Modbus Addresses
+//!
+//! This module parses the addresses of tags read over Modbus TCP. An address names a coil, discrete input, input
+//! register or holding register, either with a table prefix and a zero-based offset (e.g. `coil:12`, `di:0`,
+//! `ir:3`, `hr:100/4`) or in the classic one-based Modicon notation (e.g. `00013`, `10001`, `30004`,
+//! `400101/4`). A `/<bit>` suffix selects a single bit of a register.
+
+use std::fmt;
+use std::str::FromStr;
+use crate::errors::DockManagerError;
+
+/// The default Modbus TCP port
+pub const DEFAULT_MODBUS_PORT: u16 = 502;
+
+/// The default Modbus unit identifier
+pub const DEFAULT_MODBUS_UNIT_ID: u8 = 1;
+
+/// The Modbus data tables a tag can be read from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModbusTable {
+    /// Read/write single bits (function code 1)
+    Coil,
+    /// Read-only single bits (function code 2)
+    DiscreteInput,
+    /// Read-only 16-bit registers (function code 4)
+    InputRegister,
+    /// Read/write 16-bit registers (function code 3)
+    HoldingRegister,
+}
+
+impl ModbusTable {
+    /// The function code used to read the table
+    pub fn function_code(&self) -> u8 {
+        match self {
+            ModbusTable::Coil => 0x01,
+            ModbusTable::DiscreteInput => 0x02,
+            ModbusTable::HoldingRegister => 0x03,
+            ModbusTable::InputRegister => 0x04,
+        }
+    }
+
+    /// Returns `true` if the table holds single bits rather than registers
+    pub fn is_bit_table(&self) -> bool {
+        matches!(self, ModbusTable::Coil | ModbusTable::DiscreteInput)
+    }
+
+    /// The prefix used for the table in addresses
+    fn prefix(&self) -> &'static str {
+        match self {
+            ModbusTable::Coil => "coil",
+            ModbusTable::DiscreteInput => "di",
+            ModbusTable::InputRegister => "ir",
+            ModbusTable::HoldingRegister => "hr",
+        }
+    }
+}
+
+/// A parsed Modbus address
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModbusAddress {
+    /// The table the value is read from
+    pub table: ModbusTable,
+    /// The zero-based offset of the coil, input or register
+    pub offset: u16,
+    /// The bit within the register, if the address refers to a single bit of a register
+    pub bit: Option<u8>,
+}
+
+impl FromStr for ModbusAddress {
+    type Err = DockManagerError;
+
+    /// Parses an address of the form `<table>:<offset>[/<bit>]` or a Modicon reference such as `40001[/<bit>]`
+    fn from_str(address: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| DockManagerError::ConfigError(format!("Invalid Modbus address '{}': {}", address, reason));
+        let trimmed = address.trim().to_ascii_lowercase();
+
+        let (reference, bit) = match trimmed.split_once('/') {
+            Some((reference, bit)) => {
+                let bit = bit.parse::<u8>().ok().filter(|bit| *bit < 16)
+                    .ok_or_else(|| invalid("bit must be between 0 and 15"))?;
+                (reference, Some(bit))
+            }
+            None => (trimmed.as_str(), None),
+        };
+
+        let (table, offset) = if let Some((prefix, offset)) = reference.split_once(':') {
+            let table = match prefix {
+                "coil" | "co" => ModbusTable::Coil,
+                "di" | "input" => ModbusTable::DiscreteInput,
+                "ir" => ModbusTable::InputRegister,
+                "hr" | "holding" => ModbusTable::HoldingRegister,
+                _ => return Err(invalid("unknown table, expected coil, di, ir or hr")),
+            };
+            let offset = offset.parse::<u16>().map_err(|_| invalid("offset is not a number between 0 and 65535"))?;
+            (table, offset)
+        } else {
+            if !(5..=6).contains(&reference.len()) || !reference.chars().all(|c| c.is_ascii_digit()) {
+                return Err(invalid("expected a table prefix (e.g. 'hr:0') or a 5 or 6 digit reference (e.g. '40001')"));
+            }
+            let (table_digit, number) = reference.split_at(1);
+            let table = match table_digit {
+                "0" => ModbusTable::Coil,
+                "1" => ModbusTable::DiscreteInput,
+                "3" => ModbusTable::InputRegister,
+                "4" => ModbusTable::HoldingRegister,
+                _ => return Err(invalid("reference must start with 0, 1, 3 or 4")),
+            };
+            let number = number.parse::<u32>().map_err(|_| invalid("reference is not a number"))?;
+            let offset = number.checked_sub(1).and_then(|offset| u16::try_from(offset).ok())
+                .ok_or_else(|| invalid("reference is out of range"))?;
+            (table, offset)
+        };
+
+        if bit.is_some() && table.is_bit_table() {
+            return Err(invalid("a bit can only be selected from a register"));
+        }
+
+        Ok(Self { table, offset, bit })
+    }
+}
+
+impl fmt::Display for ModbusAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.table.prefix(), self.offset)?;
+        if let Some(bit) = self.bit {
+            write!(f, "/{}", bit)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_modicon_references() {
+        let address: ModbusAddress = "40001".parse().unwrap();
+        assert_eq!(address, ModbusAddress { table: ModbusTable::HoldingRegister, offset: 0, bit: None });
+
+        let address: ModbusAddress = "10001".parse().unwrap();
+        assert_eq!(address, ModbusAddress { table: ModbusTable::DiscreteInput, offset: 0, bit: None });
+    }
+
+    #[test]
+    fn parses_prefixed_addresses_with_a_bit() {
+        let address: ModbusAddress = "hr:100/4".parse().unwrap();
+        assert_eq!(address, ModbusAddress { table: ModbusTable::HoldingRegister, offset: 100, bit: Some(4) });
+        assert_eq!(address.to_string(), "hr:100/4");
+    }
+
+    #[test]
+    fn rejects_a_bit_of_a_bit_table() {
+        assert!("coil:1/2".parse::<ModbusAddress>().is_err());
+        assert!("hr:1/16".parse::<ModbusAddress>().is_err());
+    }
+}
@@ -0,0 +1,141 @@
+//! # Plant Occupancy
+
+//! This module defines the `PlantOccupancy` struct, which counts the doors of a plant by their state. The counts are
+//! kept up to date by the `DoorStateRepository` as doors change, so reporting, alerts and dashboards can read them
+//! without going through every door.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use crate::models::{local_now, DockDoor, DoorState, LoadingStatus, ManualMode, TrailerState};
+
+/// The number of doors of a plant with each kind of fault
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FaultCounts {
+    /// Doors with a trailer door fault
+    pub trailer_door_fault: usize,
+    /// Doors with a dock lock fault
+    pub dock_lock_fault: usize,
+    /// Doors with a door fault
+    pub door_fault: usize,
+    /// Doors with the emergency stop pressed
+    pub emergency_stop: usize,
+    /// Doors with a leveler fault
+    pub leveler_fault: usize,
+}
+
+/// The number of doors of a plant in each state
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlantOccupancy {
+    /// The plant the doors belong to
+    pub plant_id: String,
+    /// The number of doors of the plant
+    pub total_doors: usize,
+    /// The number of doors in each `DoorState`; states without doors are left out
+    pub by_door_state: HashMap<DoorState, usize>,
+    /// The number of doors in each `LoadingStatus` reported by the WMS; statuses without doors are left out
+    pub by_loading_status: HashMap<LoadingStatus, usize>,
+    /// The number of doors in each `TrailerState`; states without doors are left out
+    pub by_trailer_state: HashMap<TrailerState, usize>,
+    /// The number of doors with each kind of fault
+    pub faults: FaultCounts,
+    /// The number of doors with at least one fault
+    pub faulted: usize,
+    /// The number of doors in manual mode
+    pub manual_mode: usize,
+    /// The number of doors whose PLC is offline
+    pub offline: usize,
+    /// When a door of the plant last changed
+    pub updated_at: NaiveDateTime,
+}
+
+impl PlantOccupancy {
+    /// Creates the occupancy of a plant without doors
+    ///
+    /// # Arguments
+    ///
+    /// * `plant_id`: The plant the doors belong to
+    pub fn new(plant_id: &str) -> Self {
+        Self {
+            plant_id: plant_id.to_string(),
+            total_doors: 0,
+            by_door_state: HashMap::new(),
+            by_loading_status: HashMap::new(),
+            by_trailer_state: HashMap::new(),
+            faults: FaultCounts::default(),
+            faulted: 0,
+            manual_mode: 0,
+            offline: 0,
+            updated_at: local_now(),
+        }
+    }
+
+    /// Counts a door
+    ///
+    /// # Arguments
+    ///
+    /// * `door`: The `DockDoor` to count
+    pub fn add(&mut self, door: &DockDoor) {
+        self.adjust(door, true);
+    }
+
+    /// Stops counting a door, typically in the state it had before a change
+    ///
+    /// # Arguments
+    ///
+    /// * `door`: The `DockDoor` to stop counting, as it was counted
+    pub fn remove(&mut self, door: &DockDoor) {
+        self.adjust(door, false);
+    }
+
+    /// Returns the number of doors in a `DoorState`
+    pub fn door_state_count(&self, state: DoorState) -> usize {
+        self.by_door_state.get(&state).copied().unwrap_or(0)
+    }
+
+    /// Returns the number of doors in a `LoadingStatus`
+    pub fn loading_status_count(&self, status: LoadingStatus) -> usize {
+        self.by_loading_status.get(&status).copied().unwrap_or(0)
+    }
+
+    /// Returns the number of doors in a `TrailerState`
+    pub fn trailer_state_count(&self, state: TrailerState) -> usize {
+        self.by_trailer_state.get(&state).copied().unwrap_or(0)
+    }
+
+    /// Adds a door to every count that applies to it, or removes it from them
+    fn adjust(&mut self, door: &DockDoor, add: bool) {
+        let step = |count: &mut usize, applies: bool| {
+            if applies {
+                *count = if add { *count + 1 } else { count.saturating_sub(1) };
+            }
+        };
+        step(&mut self.total_doors, true);
+        adjust_key(&mut self.by_door_state, door.door_state, add);
+        adjust_key(&mut self.by_loading_status, door.loading_status.loading_status, add);
+        adjust_key(&mut self.by_trailer_state, door.trailer_state, add);
+        step(&mut self.faults.trailer_door_fault, door.trailer_door_fault);
+        step(&mut self.faults.dock_lock_fault, door.dock_lock_fault);
+        step(&mut self.faults.door_fault, door.door_fault);
+        step(&mut self.faults.emergency_stop, door.emergency_stop);
+        step(&mut self.faults.leveler_fault, door.leveler_fault);
+        step(&mut self.faulted, door.trailer_door_fault || door.dock_lock_fault || door.door_fault
+            || door.emergency_stop || door.leveler_fault);
+        step(&mut self.manual_mode, door.manual_mode == ManualMode::Enabled);
+        step(&mut self.offline, !door.plc_health.online);
+        self.updated_at = local_now();
+    }
+}
+
+/// Adds one to the count of a key, or removes one and drops the key once its count reaches zero
+fn adjust_key<K: Eq + Hash>(counts: &mut HashMap<K, usize>, key: K, add: bool) {
+    if add {
+        *counts.entry(key).or_insert(0) += 1;
+    } else if let Some(count) = counts.get_mut(&key) {
+        *count = count.saturating_sub(1);
+        if *count == 0 {
+            counts.remove(&key);
+        }
+    }
+}
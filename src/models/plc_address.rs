@@ -0,0 +1,135 @@
+//! # PLC Addresses
+//!
+//! This module parses PCCC data table addresses, as used by MicroLogix, SLC 500 and PLC-5 controllers
+//! (e.g. `B9:0/9`, `N7:3`, `B3/35` or `I:1/4`).
+
+use std::fmt;
+use std::str::FromStr;
+use crate::errors::DockManagerError;
+
+/// The number of bits in a PCCC data table word
+const WORD_BITS: u16 = 16;
+
+/// A parsed PCCC data table address
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlcAddress {
+    /// The file type letter(s), e.g. `B`, `N` or `I`
+    pub file_type: String,
+    /// The file number, absent for the I/O files (e.g. `I:1/4`)
+    pub file_number: Option<u16>,
+    /// The element (word) within the file
+    pub element: u16,
+    /// The bit within the element, if the address refers to a single bit
+    pub bit: Option<u8>,
+}
+
+impl PlcAddress {
+    /// Returns the data file the address belongs to, e.g. `B9` for `B9:0/9`
+    pub fn data_file(&self) -> String {
+        match self.file_number {
+            Some(number) => format!("{}{}", self.file_type, number),
+            None => self.file_type.clone(),
+        }
+    }
+
+    /// Returns `true` if the address lives in a file made of 16-bit words whose bits can be decoded
+    /// from a block read
+    pub fn is_word_file(&self) -> bool {
+        matches!(self.file_type.as_str(), "B" | "N" | "I" | "O" | "S")
+    }
+
+    /// Returns `true` if the address refers to a single bit of a 16-bit word file
+    pub fn is_block_readable_bit(&self) -> bool {
+        self.bit.is_some() && self.is_word_file()
+    }
+}
+
+impl FromStr for PlcAddress {
+    type Err = DockManagerError;
+
+    /// Parses a PCCC address of the form `<type><file>:<element>[/<bit>]` or `<type><file>/<bit>`
+    fn from_str(address: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| DockManagerError::ConfigError(format!("Invalid PLC address '{}': {}", address, reason));
+        let trimmed = address.trim().to_ascii_uppercase();
+
+        let type_len = trimmed.chars().take_while(|c| c.is_ascii_alphabetic()).count();
+        if type_len == 0 {
+            return Err(invalid("missing file type"));
+        }
+        let (file_type, rest) = trimmed.split_at(type_len);
+
+        let number_len = rest.chars().take_while(|c| c.is_ascii_digit()).count();
+        let (file_number, rest) = rest.split_at(number_len);
+        let file_number = if file_number.is_empty() {
+            None
+        } else {
+            Some(file_number.parse::<u16>().map_err(|_| invalid("file number out of range"))?)
+        };
+
+        let parse_u16 = |value: &str, what: &str| value.parse::<u16>()
+            .map_err(|_| invalid(&format!("{} '{}' is not a number", what, value)));
+
+        let (element, bit) = if let Some(rest) = rest.strip_prefix(':') {
+            match rest.split_once('/') {
+                Some((element, bit)) => (parse_u16(element, "element")?, Some(parse_u16(bit, "bit")?)),
+                None => (parse_u16(rest, "element")?, None),
+            }
+        } else if let Some(bit) = rest.strip_prefix('/') {
+            // Bit-addressed form: B3/35 is bit 3 of word 2
+            let bit = parse_u16(bit, "bit")?;
+            (bit / WORD_BITS, Some(bit % WORD_BITS))
+        } else {
+            return Err(invalid("expected ':' or '/' after the file"));
+        };
+
+        if bit.is_some_and(|bit| bit >= WORD_BITS) {
+            return Err(invalid(&format!("bit must be between 0 and {}", WORD_BITS - 1)));
+        }
+
+        if file_number.is_none() && !matches!(file_type, "I" | "O" | "S") {
+            return Err(invalid("missing file number"));
+        }
+
+        Ok(Self {
+            file_type: file_type.to_string(),
+            file_number,
+            element,
+            bit: bit.map(|b| b as u8),
+        })
+    }
+}
+
+impl fmt::Display for PlcAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.data_file(), self.element)?;
+        if let Some(bit) = self.bit {
+            write!(f, "/{}", bit)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn address(address: &str) -> PlcAddress {
+        address.parse().unwrap()
+    }
+
+    #[test]
+    fn bit_addressed_form_counts_bits_across_words() {
+        let parsed = address("B3/35");
+
+        assert_eq!(parsed.data_file(), "B3");
+        assert_eq!(parsed.element, 2);
+        assert_eq!(parsed.bit, Some(3));
+    }
+
+    #[test]
+    fn bits_beyond_a_word_are_rejected() {
+        assert_eq!(address("B9:0/15").bit, Some(15));
+        assert!("B9:0/16".parse::<PlcAddress>().is_err());
+        assert!("N7:3/20".parse::<PlcAddress>().is_err());
+    }
+}
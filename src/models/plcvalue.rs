@@ -3,10 +3,15 @@
 //! This module defines the `PlcVal` struct, which represents a value read from a PLC (Programmable Logic Controller) sensor. 
 //! It encapsulates the essential information associated with a sensor reading, including the plant ID, door name, door IP address, 
 //! sensor name, the actual sensor value, and the timestamp of the reading.
+//! It also defines `SensorReadings`, which pairs the values of a polling cycle with the read status of each door.
 
 
+use std::collections::HashSet;
+use std::time::Duration;
 use chrono::{NaiveDateTime, Local};
 use serde::{Serialize, Deserialize};
+use crate::config::PlantSettings;
+use crate::models::SensorValue;
 
 /// Represents a value read from a PLC sensor.
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -20,7 +25,7 @@ pub struct PlcVal {
     /// The name of the sensor.
     pub sensor_name: String,
     /// The value read from the sensor.
-    pub value: u8,
+    pub value: SensorValue,
     /// The timestamp when the sensor value was read.
     pub timestamp: NaiveDateTime,
 }
@@ -39,7 +44,7 @@ impl PlcVal {
     /// # Returns
     /// 
     /// A new `PlcVal` instance with the provided information and the current local time as the timestamp.
-    pub fn new(plant_id: &str, door_name: &str, door_ip: &str, sensor_name: &str, value: u8) -> Self {
+    pub fn new(plant_id: &str, door_name: &str, door_ip: &str, sensor_name: &str, value: SensorValue) -> Self {
         PlcVal {
             plant_id: plant_id.to_string(),
             door_name: door_name.to_string(),
@@ -49,4 +54,86 @@ impl PlcVal {
             timestamp: Local::now().naive_local(),
         }
     }
-}
\ No newline at end of file
+}
+
+/// The outcome of reading the sensors of a single dock door during a polling cycle
+#[derive(Debug, Clone, Default)]
+pub struct DoorReadStatus {
+    /// The ID of the plant where the door is located
+    pub plant_id: String,
+    /// The name of the dock door
+    pub door_name: String,
+    /// The number of sensors that were read successfully
+    pub successful_reads: usize,
+    /// The names of the sensors that could not be read
+    pub failed_sensors: Vec<String>,
+    /// How long it took to read the door's sensors, if measured
+    pub latency: Option<Duration>,
+    /// The last error reported while reading the door's sensors
+    pub last_error: Option<String>,
+}
+
+impl DoorReadStatus {
+    /// Returns `true` if the door's PLC answered during the cycle
+    ///
+    /// A door is considered reachable as long as at least one of its sensors was read,
+    /// so a single misconfigured tag does not take the whole door offline.
+    pub fn is_reachable(&self) -> bool {
+        self.successful_reads > 0 || self.failed_sensors.is_empty()
+    }
+}
+
+/// The sensor values and per-door read statuses produced by a polling cycle
+#[derive(Debug, Clone, Default)]
+pub struct SensorReadings {
+    /// The sensor values that were read
+    pub values: Vec<PlcVal>,
+    /// The read status of every door that was polled
+    pub doors: Vec<DoorReadStatus>,
+}
+
+impl SensorReadings {
+    /// Builds the readings of a plant from its values alone, deriving each door's status from the tags it reported
+    ///
+    /// Every configured tag of a door that has no value is counted as a failed read.
+    /// This is used by sources that do not talk to a PLC directly, such as the replay source.
+    ///
+    /// # Arguments
+    ///
+    /// * `plant`: The plant the values belong to
+    /// * `values`: The sensor values produced for the plant
+    ///
+    /// # Returns
+    ///
+    /// The values along with one `DoorReadStatus` per configured door
+    pub fn from_values(plant: &PlantSettings, values: Vec<PlcVal>) -> Self {
+        let doors = plant.dock_doors.dock_door_config.iter()
+            .map(|door| {
+                let reported: HashSet<&str> = values.iter()
+                    .filter(|value| value.door_name == door.dock_name)
+                    .map(|value| value.sensor_name.as_str())
+                    .collect();
+                let failed_sensors: Vec<String> = plant.dock_doors.dock_plc_tags.iter()
+                    .filter(|tag| !reported.contains(tag.tag_name.as_str()))
+                    .map(|tag| tag.tag_name.clone())
+                    .collect();
+                DoorReadStatus {
+                    plant_id: plant.plant_id.clone(),
+                    door_name: door.dock_name.clone(),
+                    successful_reads: reported.len(),
+                    last_error: (!failed_sensors.is_empty()).then(|| "No value reported".to_string()),
+                    failed_sensors,
+                    latency: None,
+                }
+            })
+            .collect();
+
+        Self { values, doors }
+    }
+
+    /// Appends the readings of another polling cycle
+    pub fn extend(&mut self, other: SensorReadings) {
+        self.values.extend(other.values);
+        self.doors.extend(other.doors);
+    }
+}
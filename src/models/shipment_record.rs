@@ -0,0 +1,202 @@
+//! # Shipment Records
+
+//! This module defines the `ShipmentRecord` struct, the plant-level history of a shipment. Unlike the
+//! `AssignedShipment` of a `DockDoor`, a record follows its shipment when the WMS moves it to another door, and keeps
+//! every door it was assigned to, its status changes and its WMS events in one place.
+
+use chrono::{Duration, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use crate::models::WmsEvent;
+
+/// The lifecycle status of a shipment, as reported by its WMS events
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ShipmentStatus {
+    /// The shipment is assigned to a door but has not been started
+    Assigned,
+    /// The shipment has been started or resumed
+    Started,
+    /// The shipment has been suspended
+    Suspended,
+    /// The loading of the shipment is completed
+    Completed,
+    /// The shipment has been checked out
+    CheckedOut,
+    /// The shipment has been forced closed
+    ForcedClosed,
+    /// The shipment has been cancelled
+    Cancelled,
+}
+
+impl ShipmentStatus {
+    /// Returns the status a WMS message type moves a shipment to, if any
+    ///
+    /// # Arguments
+    ///
+    /// * `message_type`: The `MESSAGE_TYPE` of a WMS event
+    pub fn from_message_type(message_type: &str) -> Option<Self> {
+        match message_type {
+            "STARTED_SHIPMENT" | "RESUMED_SHIPMENT" => Some(Self::Started),
+            "SUSPENDED_SHIPMENT" => Some(Self::Suspended),
+            "COMPLETED_LOAD" => Some(Self::Completed),
+            "CHECKOUT" => Some(Self::CheckedOut),
+            "SHIPMENT_FORCED_CLOSED" => Some(Self::ForcedClosed),
+            "CANCELLED_SHIPMENT" => Some(Self::Cancelled),
+            _ => None,
+        }
+    }
+}
+
+/// A change of a shipment's status
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShipmentStatusChange {
+    /// The status the shipment moved to
+    pub status: ShipmentStatus,
+    /// When the shipment moved to the status
+    pub at: NaiveDateTime,
+}
+
+/// A period during which a shipment was assigned to a door
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DoorStay {
+    /// The name of the door
+    pub dock_name: String,
+    /// When the shipment was assigned to the door
+    pub assigned_at: NaiveDateTime,
+    /// When the shipment was unassigned from the door or moved to another one; `None` while it is still there
+    pub unassigned_at: Option<NaiveDateTime>,
+}
+
+impl DoorStay {
+    /// Returns how long the shipment has been assigned to the door, counting up to `now` while it still is
+    pub fn duration(&self, now: NaiveDateTime) -> Duration {
+        self.unassigned_at.unwrap_or(now).signed_duration_since(self.assigned_at)
+    }
+}
+
+/// The history of a shipment across the doors of a plant
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShipmentRecord {
+    /// The plant of the shipment
+    pub plant_id: String,
+    /// The ID of the shipment
+    pub shipment_id: String,
+    /// The current status of the shipment
+    pub status: ShipmentStatus,
+    /// The status changes of the shipment, oldest first
+    pub status_history: Vec<ShipmentStatusChange>,
+    /// The doors the shipment has been assigned to, oldest first
+    pub door_stays: Vec<DoorStay>,
+    /// The WMS events of the shipment, in the order they were received, from whichever door they came
+    pub events: Vec<WmsEvent>,
+    /// When the shipment was first seen
+    pub first_seen: NaiveDateTime,
+    /// When the shipment was last assigned, unassigned or received a WMS event
+    pub last_updated: NaiveDateTime,
+}
+
+impl ShipmentRecord {
+    /// Creates the record of a shipment seen for the first time
+    ///
+    /// # Arguments
+    ///
+    /// * `plant_id`: The plant of the shipment
+    /// * `shipment_id`: The ID of the shipment
+    /// * `at`: When the shipment was first seen
+    ///
+    /// # Returns
+    ///
+    /// * A new `ShipmentRecord` with the `Assigned` status and no door stays or events
+    pub fn new(plant_id: &str, shipment_id: &str, at: NaiveDateTime) -> Self {
+        Self {
+            plant_id: plant_id.to_string(),
+            shipment_id: shipment_id.to_string(),
+            status: ShipmentStatus::Assigned,
+            status_history: vec![ShipmentStatusChange { status: ShipmentStatus::Assigned, at }],
+            door_stays: Vec::new(),
+            events: Vec::new(),
+            first_seen: at,
+            last_updated: at,
+        }
+    }
+
+    /// Returns the door the shipment is currently assigned to, if any
+    pub fn current_door(&self) -> Option<&str> {
+        self.door_stays.last()
+            .filter(|stay| stay.unassigned_at.is_none())
+            .map(|stay| stay.dock_name.as_str())
+    }
+
+    /// Records the shipment being assigned to a door, closing its stay at the door it was moved from
+    ///
+    /// # Arguments
+    ///
+    /// * `dock_name`: The name of the door
+    /// * `at`: When the shipment was assigned
+    ///
+    /// # Returns
+    ///
+    /// * `Some(String)` with the door the shipment was moved from, if it was still assigned to another door
+    /// * `None` otherwise, including when it was already assigned to this door
+    pub fn assign(&mut self, dock_name: &str, at: NaiveDateTime) -> Option<String> {
+        if self.current_door() == Some(dock_name) {
+            return None;
+        }
+        let moved_from = self.current_door().map(str::to_string);
+        self.unassign(at);
+        self.door_stays.push(DoorStay { dock_name: dock_name.to_string(), assigned_at: at, unassigned_at: None });
+        self.last_updated = at;
+        moved_from
+    }
+
+    /// Records the shipment being unassigned from the door it is assigned to, if any
+    ///
+    /// # Arguments
+    ///
+    /// * `at`: When the shipment was unassigned
+    pub fn unassign(&mut self, at: NaiveDateTime) {
+        if let Some(stay) = self.door_stays.last_mut().filter(|stay| stay.unassigned_at.is_none()) {
+            stay.unassigned_at = Some(at);
+            self.last_updated = at;
+        }
+    }
+
+    /// Adds a WMS event to the shipment, updating its status if the event changes it
+    ///
+    /// Events already recorded are ignored, as the WMS may report the same event more than once.
+    ///
+    /// # Arguments
+    ///
+    /// * `event`: The `WmsEvent` of the shipment
+    /// * `at`: When the event occurred
+    pub fn add_event(&mut self, event: &WmsEvent, at: NaiveDateTime) {
+        if self.events.contains(event) {
+            return;
+        }
+        self.events.push(event.clone());
+        self.last_updated = at;
+        if let Some(status) = ShipmentStatus::from_message_type(&event.message_type).filter(|status| *status != self.status) {
+            self.status = status;
+            self.status_history.push(ShipmentStatusChange { status, at });
+        }
+    }
+
+    /// Returns how long the shipment has spent at each door, in the order the doors were first assigned
+    ///
+    /// # Arguments
+    ///
+    /// * `now`: The time the stay at the current door is counted up to
+    ///
+    /// # Returns
+    ///
+    /// * The name of every door the shipment was assigned to, with the total time of its stays there
+    pub fn time_at_doors(&self, now: NaiveDateTime) -> Vec<(String, Duration)> {
+        let mut totals: Vec<(String, Duration)> = Vec::new();
+        for stay in &self.door_stays {
+            match totals.iter_mut().find(|(dock_name, _)| *dock_name == stay.dock_name) {
+                Some((_, total)) => *total += stay.duration(now),
+                None => totals.push((stay.dock_name.clone(), stay.duration(now))),
+            }
+        }
+        totals
+    }
+}
@@ -0,0 +1,52 @@
+//! Builders for the plants and doors used by the unit tests
+
+use serde_json::json;
+use crate::config::PlantSettings;
+use crate::models::DockDoor;
+
+/// The ID of the plant built by `plant`
+pub const PLANT_ID: &str = "P1";
+/// The name of the door built by `door`
+pub const DOOR_NAME: &str = "DOOR_1";
+/// The IP address of the door built by `door`
+pub const DOOR_IP: &str = "10.0.0.1";
+
+/// Builds a plant with a single door reading the given tags
+///
+/// # Arguments
+///
+/// * `tags`: The `dock_plc_tags` of the plant, as they would appear in the configuration
+pub fn plant_with_tags(tags: serde_json::Value) -> PlantSettings {
+    serde_json::from_value(json!({
+        "plant_id": PLANT_ID,
+        "alert_webhook_url": "http://localhost/alerts",
+        "lgv_wms_database": {
+            "host": "localhost",
+            "port": 1433,
+            "username": null,
+            "password": null,
+            "database_name": "LGV_WMS",
+            "app_name": "dockmonitor-tests",
+            "win_auth": true,
+            "trusted": true
+        },
+        "dock_doors": {
+            "dock_door_config": [{ "dock_name": DOOR_NAME, "dock_ip": DOOR_IP }],
+            "dock_plc_tags": tags
+        }
+    }))
+    .expect("test plant settings should deserialize")
+}
+
+/// Builds a plant whose door reads the trailer presence and dock ready sensors
+pub fn plant() -> PlantSettings {
+    plant_with_tags(json!([
+        { "tag_name": "TRAILER_AT_DOOR", "address": "B9:0/0" },
+        { "tag_name": "RH_DOCK_READY", "address": "B9:0/1" }
+    ]))
+}
+
+/// Builds the door of the given plant
+pub fn door(plant: &PlantSettings) -> DockDoor {
+    DockDoor::new(PLANT_ID.to_string(), DOOR_NAME.to_string(), DOOR_IP.to_string(), plant)
+}
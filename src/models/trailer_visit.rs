@@ -0,0 +1,118 @@
+//! # Trailer Visits
+
+//! This module defines the `TrailerVisit` struct, the timeline of a single trailer's stay at a dock door from its
+//! arrival to its departure. A visit is started when the trailer docks, collects the milestones of the door events
+//! handled while it is there, and is persisted once the trailer leaves.
+
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx_oldapi::FromRow;
+use crate::models::{DockDoorEvent, LoadingStatus};
+
+/// The timeline of one trailer's occupancy of a dock door
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRow)]
+pub struct TrailerVisit {
+    /// The ID of the visit, made of the plant, door and arrival time
+    #[sqlx(rename = "VISIT_ID")]
+    pub visit_id: String,
+    /// The plant of the door
+    #[sqlx(rename = "PLANT")]
+    pub plant_id: String,
+    /// The name of the door
+    #[sqlx(rename = "DOOR_NAME")]
+    pub dock_name: String,
+    /// The shipment loaded during the visit, if any
+    #[sqlx(rename = "SHIPMENT_ID")]
+    pub shipment_id: Option<String>,
+    /// When the trailer arrived at the door
+    #[sqlx(rename = "ARRIVED_AT")]
+    pub arrived_at: NaiveDateTime,
+    /// When the restraint first locked the trailer
+    #[sqlx(rename = "RESTRAINT_LOCKED_AT")]
+    pub restraint_locked_at: Option<NaiveDateTime>,
+    /// When the dock first reported ready
+    #[sqlx(rename = "DOCK_READY_AT")]
+    pub dock_ready_at: Option<NaiveDateTime>,
+    /// When the shipment was started in the WMS
+    #[sqlx(rename = "SHIPMENT_STARTED_AT")]
+    pub shipment_started_at: Option<NaiveDateTime>,
+    /// When the first pallet was dropped in the trailer
+    #[sqlx(rename = "FIRST_DROP_AT")]
+    pub first_drop_at: Option<NaiveDateTime>,
+    /// When the loading was completed
+    #[sqlx(rename = "LOAD_COMPLETED_AT")]
+    pub load_completed_at: Option<NaiveDateTime>,
+    /// When the restraint last released the trailer
+    #[sqlx(rename = "RESTRAINT_RELEASED_AT")]
+    pub restraint_released_at: Option<NaiveDateTime>,
+    /// When the trailer left the door; not set for a visit closed by the next arrival
+    #[sqlx(rename = "DEPARTED_AT")]
+    pub departed_at: Option<NaiveDateTime>,
+}
+
+impl TrailerVisit {
+    /// Starts the visit of a trailer that arrived at a door
+    ///
+    /// # Arguments
+    ///
+    /// * `plant_id`: The plant of the door
+    /// * `dock_name`: The name of the door
+    /// * `shipment_id`: The shipment assigned to the door, if any
+    /// * `arrived_at`: When the trailer arrived
+    ///
+    /// # Returns
+    ///
+    /// * A new `TrailerVisit` without milestones
+    pub fn start(plant_id: &str, dock_name: &str, shipment_id: Option<String>, arrived_at: NaiveDateTime) -> Self {
+        Self {
+            visit_id: format!("{}-{}-{}", plant_id, dock_name, arrived_at.format("%Y%m%d%H%M%S%3f")),
+            plant_id: plant_id.to_string(),
+            dock_name: dock_name.to_string(),
+            shipment_id,
+            arrived_at,
+            restraint_locked_at: None,
+            dock_ready_at: None,
+            shipment_started_at: None,
+            first_drop_at: None,
+            load_completed_at: None,
+            restraint_released_at: None,
+            departed_at: None,
+        }
+    }
+
+    /// Records the milestone a door event marks, if any
+    ///
+    /// The first restraint lock, dock ready, shipment start, first drop and load completion are kept, as well
+    /// as the last restraint release. Departures are recorded by the door, which also closes the visit.
+    ///
+    /// # Arguments
+    ///
+    /// * `event`: A `DockDoorEvent` of the visit's door
+    pub fn record(&mut self, event: &DockDoorEvent) {
+        match event {
+            DockDoorEvent::SensorStateChanged(e) if e.sensor_name == "RH_RESTRAINT_ENGAGED" && e.is_on() => {
+                self.restraint_locked_at.get_or_insert(e.timestamp);
+            },
+            DockDoorEvent::SensorStateChanged(e) if e.sensor_name == "RH_RESTRAINT_ENGAGED" && e.is_off() => {
+                self.restraint_released_at = Some(e.timestamp);
+            },
+            DockDoorEvent::SensorStateChanged(e) if e.sensor_name == "RH_DOCK_READY" && e.is_on() => {
+                self.dock_ready_at.get_or_insert(e.timestamp);
+            },
+            DockDoorEvent::ShipmentStarted(e) => {
+                self.shipment_started_at.get_or_insert(e.base_event.timestamp);
+                self.shipment_id.get_or_insert_with(|| e.base_event.shipment_id.clone());
+            },
+            DockDoorEvent::FirstDrop(e) => {
+                self.first_drop_at.get_or_insert(e.base_event.timestamp);
+            },
+            DockDoorEvent::LoadingCompleted(e) => {
+                self.load_completed_at.get_or_insert(e.timestamp);
+            },
+            DockDoorEvent::LoadingStatusChanged(e) if e.new_status == LoadingStatus::Completed => {
+                self.load_completed_at.get_or_insert(e.timestamp);
+            },
+            _ => {},
+        }
+    }
+}
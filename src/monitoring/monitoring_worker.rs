@@ -7,6 +7,7 @@ use crate::config::Settings;
 use crate::models::{LoadingStatus, TrailerState, ManualMode};
 use crate::state_management::door_state_repository::DoorStateRepository;
 use crate::utils::format_duration;
+use crate::utils::shutdown::ShutdownSignal;
 use super::monitoring_queue::{MonitoringQueue, MonitoringItem};
 
 /// Represents a worker that monitors and processes items from a monitoring queue
@@ -45,13 +46,16 @@ impl MonitoringWorker {
         }
     }
 
-    /// Runs the monitoring worker, continuously processing items from the queue
-    pub async fn run(&self) {
+    /// Runs the monitoring worker, continuously processing items from the queue until shutdown
+    ///
+    /// # Arguments
+    ///
+    /// * `shutdown` - The signal that stops the worker once the current loop has completed
+    pub async fn run(&self, mut shutdown: ShutdownSignal) {
         let monitoring_check_interval = self.settings.monitoring.check_interval;
         let mut interval = interval(tokio::time::Duration::from_secs(monitoring_check_interval));
 
-        loop {
-            interval.tick().await;
+        while shutdown.tick(&mut interval).await {
             info!("Starting Monitoring Worker Loop...");
 
             // Remove items older than 4 hours
@@ -171,11 +175,10 @@ impl MonitoringWorker {
             );
 
             let door_check = door_state.sensors.get("TRAILER_AT_DOOR")
-                .and_then(|sensor| sensor.get_sensor_data().current_value)
-                .unwrap_or(0);
+                .and_then(|sensor| sensor.get_sensor_data().current_value);
 
-            if door_check == 0 {
-                info!("Trailer is not at door {} sensor value = {}", door_name, door_check);
+            if !door_check.is_some_and(|value| value.is_on()) {
+                info!("Trailer is not at door {} sensor value = {:?}", door_name, door_check);
                 return false // Remove from queue
             }
 
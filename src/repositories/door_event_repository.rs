@@ -12,6 +12,13 @@ pub struct DoorEventRepository {
     client: DatabaseClient,
 }
 
+/// The number of values bound for each dock door event.
+const PARAMS_PER_EVENT: usize = 11;
+
+/// The largest number of dock door events inserted by one statement, keeping it below SQL Server's
+/// limit of 2100 parameters.
+pub const MAX_BATCH_EVENTS: usize = 150;
+
 impl DoorEventRepository {
     /// Creates a new `DoorEventRepository`.
     ///
@@ -20,6 +27,60 @@ impl DoorEventRepository {
     pub fn new(client: DatabaseClient) -> Self {
         Self { client }
     }
+
+    /// Inserts up to `MAX_BATCH_EVENTS` dock door events with a single statement, so either all or none of
+    /// them are inserted.
+    ///
+    /// # Arguments
+    /// * `events`: The `DbInsert` records to be inserted, in order
+    ///
+    /// # Returns
+    /// * `Ok(())` if the insertion was successful
+    /// * `Err(DockManagerError)` if there was an error during the database operation
+    pub async fn insert_batch(&self, events: &[DbInsert]) -> Result<(), DockManagerError> {
+        if events.is_empty() {
+            return Ok(());
+        }
+        debug_assert!(events.len() <= MAX_BATCH_EVENTS);
+
+        let rows: Vec<String> = (0..events.len())
+            .map(|row| {
+                let params: Vec<String> = (1..=PARAMS_PER_EVENT)
+                    .map(|column| format!("@p{}", row * PARAMS_PER_EVENT + column))
+                    .collect();
+                format!("({})", params.join(", "))
+            })
+            .collect();
+        let query = format!(
+            "INSERT INTO DOCK_DOOR_EVENTS
+            (LOG_DTTM, PLANT, DOOR_NAME, SHIPMENT_ID, EVENT_TYPE, SUCCESS, NOTES, ID_USER, SEVERITY, PREVIOUS_STATE, PREVIOUS_STATE_DTTM)
+            VALUES
+            {}",
+            rows.join(", ")
+        );
+
+        let mut statement = sqlx_oldapi::query::<Mssql>(&query);
+        for event in events {
+            statement = statement
+                .bind(event.LOG_DTTM)
+                .bind(&event.PLANT)
+                .bind(&event.DOOR_NAME)
+                .bind(&event.SHIPMENT_ID)
+                .bind(&event.EVENT_TYPE)
+                .bind(event.SUCCESS)
+                .bind(&event.NOTES)
+                .bind(&event.ID_USER)
+                .bind(event.SEVERITY)
+                .bind(&event.PREVIOUS_STATE)
+                .bind(event.PREVIOUS_STATE_DTTM);
+        }
+        statement
+            .execute(&*self.client.pool)
+            .await
+            .map_err(DockManagerError::DatabaseError)?;
+
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -41,17 +102,17 @@ impl Repository<DbInsert> for DoorEventRepository {
         "#;
 
         sqlx_oldapi::query::<Mssql>(query)
-            .bind(&event.LOG_DTTM)
+            .bind(event.LOG_DTTM)
             .bind(&event.PLANT)
             .bind(&event.DOOR_NAME)
             .bind(&event.SHIPMENT_ID)
             .bind(&event.EVENT_TYPE)
-            .bind(&event.SUCCESS)
+            .bind(event.SUCCESS)
             .bind(&event.NOTES)
             .bind(&event.ID_USER)
-            .bind(&event.SEVERITY)
+            .bind(event.SEVERITY)
             .bind(&event.PREVIOUS_STATE)
-            .bind(&event.PREVIOUS_STATE_DTTM)
+            .bind(event.PREVIOUS_STATE_DTTM)
             .execute(&*self.client.pool)
             .await
             .map_err(DockManagerError::DatabaseError)?;
@@ -2,6 +2,7 @@ pub mod door_event_repository;
 pub mod wms_status_repository;
 pub mod repository_trait;
 pub mod consolidated;
+pub mod trailer_visit;
 
 pub use door_event_repository::*;
 pub use wms_status_repository::*;
@@ -0,0 +1,106 @@
+use chrono::NaiveDateTime;
+use crate::errors::DockManagerError;
+use crate::services::DatabaseClient;
+use crate::repositories::repository_trait::Repository;
+use async_trait::async_trait;
+use sqlx_oldapi::Mssql;
+use crate::models::TrailerVisit;
+
+/// A repository responsible for managing trailer visits in the database.
+pub struct TrailerVisitRepository {
+    /// The database client used to interact with the database.
+    client: DatabaseClient,
+}
+
+impl TrailerVisitRepository {
+    /// Creates a new `TrailerVisitRepository`.
+    ///
+    /// # Arguments
+    /// * `client`: The `DatabaseClient` to use for database operations.
+    pub fn new(client: DatabaseClient) -> Self {
+        Self { client }
+    }
+
+    /// Fetches the visits of a door that arrived at or after the given time, oldest first
+    ///
+    /// # Arguments
+    /// * `plant_id`: The plant of the door
+    /// * `door_name`: The name of the door
+    /// * `since`: The earliest arrival time to return
+    ///
+    /// # Returns
+    /// * `Ok(Vec<TrailerVisit>)`: The visits of the door
+    /// * `Err(DockManagerError)` if there was an error during the database operation
+    pub async fn fetch_for_door(&self, plant_id: &str, door_name: &str, since: NaiveDateTime) -> Result<Vec<TrailerVisit>, DockManagerError> {
+        let query = r#"
+            SELECT VISIT_ID, PLANT, DOOR_NAME, SHIPMENT_ID, ARRIVED_AT, RESTRAINT_LOCKED_AT, DOCK_READY_AT,
+                   SHIPMENT_STARTED_AT, FIRST_DROP_AT, LOAD_COMPLETED_AT, RESTRAINT_RELEASED_AT, DEPARTED_AT
+            FROM TRAILER_VISITS
+            WHERE PLANT = @p1 AND DOOR_NAME = @p2 AND ARRIVED_AT >= @p3
+            ORDER BY ARRIVED_AT
+        "#;
+
+        sqlx_oldapi::query_as::<Mssql, TrailerVisit>(query)
+            .bind(plant_id)
+            .bind(door_name)
+            .bind(since)
+            .fetch_all(&*self.client.pool)
+            .await
+            .map_err(DockManagerError::DatabaseError)
+    }
+}
+
+#[async_trait]
+impl Repository<TrailerVisit> for TrailerVisitRepository {
+    /// Inserts a trailer visit into the database.
+    ///
+    /// # Arguments
+    /// * `visit`: The `TrailerVisit` to be inserted
+    ///
+    /// # Returns
+    /// * `Ok(())` if the insertion was successful
+    /// * `Err(DockManagerError)` if there was an error during the database operation
+    async fn insert(&self, visit: &TrailerVisit) -> Result<(), DockManagerError> {
+        let query = r#"
+            INSERT INTO TRAILER_VISITS
+            (VISIT_ID, PLANT, DOOR_NAME, SHIPMENT_ID, ARRIVED_AT, RESTRAINT_LOCKED_AT, DOCK_READY_AT,
+             SHIPMENT_STARTED_AT, FIRST_DROP_AT, LOAD_COMPLETED_AT, RESTRAINT_RELEASED_AT, DEPARTED_AT)
+            VALUES
+            (@p1, @p2, @p3, @p4, @p5, @p6, @p7, @p8, @p9, @p10, @p11, @p12)
+        "#;
+
+        sqlx_oldapi::query::<Mssql>(query)
+            .bind(&visit.visit_id)
+            .bind(&visit.plant_id)
+            .bind(&visit.dock_name)
+            .bind(&visit.shipment_id)
+            .bind(visit.arrived_at)
+            .bind(visit.restraint_locked_at)
+            .bind(visit.dock_ready_at)
+            .bind(visit.shipment_started_at)
+            .bind(visit.first_drop_at)
+            .bind(visit.load_completed_at)
+            .bind(visit.restraint_released_at)
+            .bind(visit.departed_at)
+            .execute(&*self.client.pool)
+            .await
+            .map_err(DockManagerError::DatabaseError)?;
+
+        Ok(())
+    }
+
+    /// Fetches trailer visits from the database based on the provided query
+    ///
+    /// # Arguments
+    /// * `query`: The SQL query to execute for fetching the visits.
+    ///
+    /// # Returns
+    /// * `Ok(Vec<TrailerVisit>)`: A vector of the fetched `TrailerVisit`s
+    /// * `Err(DockManagerError)` if there was an error during the database operation
+    async fn fetch(&self, query: &str) -> Result<Vec<TrailerVisit>, DockManagerError> {
+        sqlx_oldapi::query_as::<_, TrailerVisit>(query)
+            .fetch_all(&*self.client.pool)
+            .await
+            .map_err(DockManagerError::DatabaseError)
+    }
+}
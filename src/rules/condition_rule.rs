@@ -0,0 +1,493 @@
+//! # Condition Rule
+//!
+//! This module defines the `ConditionRule`, a rule configured entirely in `rules.json`. It matches door events by
+//! their variant name, tests a list of conditions over the door's fields, its sensor values and the event's fields,
+//! and emits the configured alerts, log entries and database inserts when all of them hold. Rules that do not test
+//! the event are also evaluated on the analyzer's periodic tick, so conditions on durations are noticed once they are
+//! reached even if the door has no further events.
+
+use std::collections::HashSet;
+use std::sync::Mutex;
+use chrono::NaiveDateTime;
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use async_trait::async_trait;
+use crate::analysis::context_analyzer::{AnalysisRule, RuleContext, AnalysisResult, AlertType, LogEntry};
+use crate::config::Settings;
+use crate::models::{DbInsert, DockDoor, DockDoorEvent};
+
+/// Configuration for the ConditionRule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConditionRuleConfig {
+    /// The name the rule's alerts and logs are reported under
+    pub name: String,
+    /// The names of the `DockDoorEvent` variants the rule is evaluated on, such as `SensorStateChanged`;
+    /// every event when empty
+    #[serde(default)]
+    pub events: Vec<String>,
+    /// The conditions that must all hold for the rule to fire
+    #[serde(default)]
+    pub conditions: Vec<Condition>,
+    /// What the rule emits when it fires
+    pub actions: Vec<ConditionAction>,
+}
+
+impl ConditionRuleConfig {
+    /// Lists the problems in the rule's configuration
+    ///
+    /// Sensor names must be configured as a tag of at least one plant, and door field paths must lead to a field of
+    /// a door of the first configured plant. A path may continue past a field that is empty on a new door, such as
+    /// `current_visit.arrived_at`, as its contents cannot be checked. The rule also needs at least one action.
+    ///
+    /// # Arguments
+    ///
+    /// * `settings`: The application settings the sensors and doors are configured in
+    ///
+    /// # Returns
+    ///
+    /// A description of every problem found, empty if the configuration is valid
+    pub fn problems(&self, settings: &Settings) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.actions.is_empty() {
+            problems.push(format!("rule {} has no actions", self.name));
+        }
+
+        let template_door = settings.plants.iter()
+            .find_map(|plant| plant.dock_doors.dock_door_config.first().map(|door| (plant, door)))
+            .map(|(plant, door)| DockDoor::new(plant.plant_id.clone(), door.dock_name.clone(), door.dock_ip.clone(), plant))
+            .and_then(|door| serde_json::to_value(door).ok());
+
+        for condition in &self.conditions {
+            match &condition.operand {
+                Operand::Sensor(name) | Operand::SensorAge(name) => {
+                    let configured = settings.plants.iter()
+                        .any(|plant| plant.dock_doors.dock_plc_tags.iter().any(|tag| tag.tag_name == *name));
+                    if !configured {
+                        problems.push(format!("rule {}: sensor {} is not configured for any plant", self.name, name));
+                    }
+                },
+                Operand::Field(path) | Operand::SecondsSince(path) => {
+                    if template_door.as_ref().is_some_and(|door| !path_exists(door, path)) {
+                        problems.push(format!("rule {}: door field {} does not exist", self.name, path));
+                    }
+                },
+                Operand::EventField(_) => {},
+            }
+        }
+        problems
+    }
+}
+
+/// A comparison of a value of the door or event against a configured value
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Condition {
+    /// The value to test
+    #[serde(flatten)]
+    pub operand: Operand,
+    /// How the tested value is compared
+    pub op: Comparison,
+    /// The value the tested value is compared with
+    pub value: Value,
+}
+
+/// The value of the door or event a condition tests
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Operand {
+    /// A field of the door, by its dotted path in the door's JSON form, such as `loading_status.loading_status`
+    Field(String),
+    /// The current value of a sensor of the door, by its tag name, as a number (booleans are 1 or 0)
+    Sensor(String),
+    /// The number of seconds since a sensor of the door last changed, by its tag name
+    SensorAge(String),
+    /// The number of seconds since a timestamp field of the door, by its dotted path
+    SecondsSince(String),
+    /// A field of the event, by its dotted path in the event's JSON form, such as `sensor_name`
+    EventField(String),
+}
+
+/// How a tested value is compared with the configured value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Comparison {
+    /// Equal
+    Eq,
+    /// Not equal
+    Ne,
+    /// Greater than
+    Gt,
+    /// Greater than or equal
+    Gte,
+    /// Less than
+    Lt,
+    /// Less than or equal
+    Lte,
+}
+
+/// What a condition rule emits when it fires
+///
+/// The texts may contain `{rule}`, `{plant_id}`, `{dock_name}` and `{shipment_id}`, which are replaced by the
+/// rule's name and the door's values.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ConditionAction {
+    /// Sends an alert with the given message
+    Alert {
+        message: String,
+    },
+    /// Records a log entry in `DOCK_DOOR_EVENTS`
+    Log {
+        event_type: String,
+        notes: String,
+        #[serde(default)]
+        severity: i32,
+        #[serde(default)]
+        success: bool,
+    },
+    /// Inserts a record in `DOCK_DOOR_EVENTS`, optionally attributed to a user
+    DbInsert {
+        event_type: String,
+        notes: String,
+        #[serde(default)]
+        severity: i32,
+        #[serde(default)]
+        success: bool,
+        #[serde(default)]
+        id_user: Option<String>,
+    },
+}
+
+/// Rule for checks configured in `rules.json` instead of code
+///
+/// The rule fires when its conditions start to hold for a door, and fires again for that door only after they
+/// have stopped holding. Conditions are evaluated when the door has an event the rule listens to and, unless one of
+/// them tests an `event_field`, on every periodic tick of the analyzer.
+pub struct ConditionRule {
+    /// The parsed configuration for this rule
+    config: ConditionRuleConfig,
+    /// The doors the conditions currently hold for, keyed by plant and door name
+    matching: Mutex<HashSet<(String, String)>>,
+}
+
+impl ConditionRule {
+    /// Creates a new ConditionRule with the given configuration
+    pub fn new(config: ConditionRuleConfig) -> Self {
+        Self { config, matching: Mutex::new(HashSet::new()) }
+    }
+
+    /// Evaluates the conditions for a door and fires the configured actions if they started to hold
+    ///
+    /// # Arguments
+    ///
+    /// * `door` - The DockDoor to evaluate the conditions for
+    /// * `event_json` - The JSON form of the event being analyzed, or `Null` on a tick
+    /// * `trigger` - What caused the evaluation, for the log
+    /// * `context` - The RuleContext the door's JSON form and the current time are read from
+    fn evaluate(&self, door: &DockDoor, event_json: &Value, trigger: &str, context: &RuleContext) -> Vec<AnalysisResult> {
+        let now = context.clock.now();
+        let holds = match context.door_json.get(door) {
+            Ok(door_json) => self.conditions_hold(door, door_json, event_json, now),
+            Err(e) => {
+                warn!("ConditionRule {}: failed to read door {}: {}", self.config.name, door.dock_name, e);
+                false
+            },
+        };
+        let key = (door.plant_id.clone(), door.dock_name.clone());
+        let started = {
+            let mut matching = self.matching.lock().unwrap();
+            if holds { matching.insert(key) } else { matching.remove(&key); false }
+        };
+        if !started {
+            return Vec::new();
+        }
+        info!("ConditionRule {} fired for door {} on {}", self.config.name, door.dock_name, trigger);
+        self.fire(door, now)
+    }
+
+    /// Returns `true` if every condition holds for the door and the event, given along with their JSON forms
+    fn conditions_hold(&self, door: &DockDoor, door_json: &Value, event_json: &Value, now: NaiveDateTime) -> bool {
+        let event_fields = event_json.as_object().and_then(|variant| variant.values().next()).unwrap_or(&Value::Null);
+
+        self.config.conditions.iter().all(|condition| {
+            let actual = match &condition.operand {
+                Operand::Field(path) => lookup(door_json, path).cloned(),
+                Operand::Sensor(name) => door.sensors.get(name)
+                    .and_then(|sensor| sensor.get_sensor_data().current_value)
+                    .map(|value| Value::from(value.as_f64())),
+                Operand::SensorAge(name) => door.sensors.get(name)
+                    .map(|sensor| Value::from(now.signed_duration_since(sensor.get_sensor_data().last_updated).num_seconds())),
+                Operand::SecondsSince(path) => lookup(door_json, path)
+                    .and_then(|value| serde_json::from_value::<NaiveDateTime>(value.clone()).ok())
+                    .map(|since| Value::from(now.signed_duration_since(since).num_seconds())),
+                Operand::EventField(path) => lookup(event_fields, path).cloned(),
+            };
+            let holds = actual.as_ref().is_some_and(|actual| compare(actual, condition.op, &condition.value));
+            debug!("ConditionRule {}: {:?} is {:?} on door {} -> {}", self.config.name, condition.operand, actual, door.dock_name, holds);
+            holds
+        })
+    }
+
+    /// Creates the results of the configured actions for a door
+    fn fire(&self, door: &DockDoor, now: NaiveDateTime) -> Vec<AnalysisResult> {
+        let fill = |text: &str| {
+            text.replace("{rule}", &self.config.name)
+                .replace("{plant_id}", &door.plant_id)
+                .replace("{dock_name}", &door.dock_name)
+                .replace("{shipment_id}", door.assigned_shipment.current_shipment.as_deref().unwrap_or("none"))
+        };
+        self.config.actions.iter().map(|action| match action {
+            ConditionAction::Alert { message } => AnalysisResult::Alert(AlertType::ConditionMatched {
+                door_name: door.dock_name.clone(),
+                shipment_id: door.assigned_shipment.current_shipment.clone(),
+                rule: self.config.name.clone(),
+                message: fill(message),
+            }),
+            ConditionAction::Log { event_type, notes, severity, success } => AnalysisResult::Log(LogEntry::ConditionMatched {
+                log_dttm: now,
+                plant: door.plant_id.clone(),
+                door_name: door.dock_name.clone(),
+                shipment_id: door.assigned_shipment.current_shipment.clone(),
+                event_type: fill(event_type),
+                success: *success,
+                notes: fill(notes),
+                severity: *severity,
+                previous_state: None,
+                previous_state_dttm: None,
+            }),
+            ConditionAction::DbInsert { event_type, notes, severity, success, id_user } => AnalysisResult::DbInsert(DbInsert {
+                LOG_DTTM: now,
+                PLANT: door.plant_id.clone(),
+                DOOR_NAME: door.dock_name.clone(),
+                SHIPMENT_ID: door.assigned_shipment.current_shipment.clone(),
+                EVENT_TYPE: fill(event_type),
+                SUCCESS: i32::from(*success),
+                NOTES: fill(notes),
+                ID_USER: id_user.clone(),
+                SEVERITY: *severity,
+                PREVIOUS_STATE: None,
+                PREVIOUS_STATE_DTTM: None,
+            }),
+        }).collect()
+    }
+}
+
+#[async_trait]
+impl AnalysisRule for ConditionRule {
+    /// Applies the rule to a dock door event, firing the configured actions when the conditions start to hold
+    ///
+    /// # Arguments
+    ///
+    /// * `door` - The DockDoor associated with the event
+    /// * `event` - The DockDoorEvent to analyze
+    /// * `context` - The RuleContext the door's JSON form and the current time are read from
+    ///
+    /// # Returns
+    ///
+    /// A vector of AnalysisResult items generated by applying the rule
+    async fn apply(&self, door: &DockDoor, event: &DockDoorEvent, context: &RuleContext) -> Vec<AnalysisResult> {
+        let event_json = match serde_json::to_value(event) {
+            Ok(event_json) => event_json,
+            Err(e) => {
+                warn!("ConditionRule {}: failed to read event {:?}: {}", self.config.name, event, e);
+                return Vec::new();
+            },
+        };
+        let event_name = event_json.as_object().and_then(|variant| variant.keys().next()).map(String::as_str).unwrap_or("");
+        if !self.config.events.is_empty() && !self.config.events.iter().any(|name| name == event_name) {
+            return Vec::new();
+        }
+
+        self.evaluate(door, &event_json, &format!("{} event", event_name), context)
+    }
+
+    /// Evaluates the conditions for a door on the periodic tick, unless they test the event
+    ///
+    /// # Arguments
+    ///
+    /// * `door` - The DockDoor to evaluate the conditions for
+    /// * `context` - The RuleContext the door's JSON form and the current time are read from
+    ///
+    /// # Returns
+    ///
+    /// The results of the configured actions if the conditions started to hold
+    async fn tick(&self, door: &DockDoor, context: &RuleContext) -> Vec<AnalysisResult> {
+        if self.config.conditions.iter().any(|condition| matches!(condition.operand, Operand::EventField(_))) {
+            return Vec::new();
+        }
+        self.evaluate(door, &Value::Null, "tick", context)
+    }
+
+    fn name(&self) -> &str {
+        &self.config.name
+    }
+}
+
+/// Returns the value at a dotted path in a JSON value
+fn lookup<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.').try_fold(value, |value, key| value.get(key))
+}
+
+/// Returns `true` if a dotted path leads to a field of a JSON value, or passes through a field that is empty
+fn path_exists(value: &Value, path: &str) -> bool {
+    let mut current = value;
+    for key in path.split('.') {
+        match current {
+            Value::Null => return true,
+            Value::Object(fields) => match fields.get(key) {
+                Some(field) => current = field,
+                None => return false,
+            },
+            _ => return false,
+        }
+    }
+    true
+}
+
+/// Returns a JSON value as a number, counting booleans as 1 or 0
+fn as_number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(number) => number.as_f64(),
+        Value::Bool(flag) => Some(f64::from(u8::from(*flag))),
+        _ => None,
+    }
+}
+
+/// Compares a tested value with a configured one, as numbers when both are numeric and as text otherwise
+fn compare(actual: &Value, op: Comparison, expected: &Value) -> bool {
+    let ordering = match (as_number(actual), as_number(expected)) {
+        (Some(actual), Some(expected)) => actual.partial_cmp(&expected),
+        _ => match (actual.as_str(), expected.as_str()) {
+            (Some(actual), Some(expected)) => Some(actual.cmp(expected)),
+            _ => return match op {
+                Comparison::Eq => actual == expected,
+                Comparison::Ne => actual != expected,
+                _ => false,
+            },
+        },
+    };
+    let Some(ordering) = ordering else { return false };
+    match op {
+        Comparison::Eq => ordering.is_eq(),
+        Comparison::Ne => ordering.is_ne(),
+        Comparison::Gt => ordering.is_gt(),
+        Comparison::Gte => ordering.is_ge(),
+        Comparison::Lt => ordering.is_lt(),
+        Comparison::Lte => ordering.is_le(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use super::*;
+    use crate::analysis::context_analyzer::DoorJson;
+    use crate::models::DoorState;
+    use crate::models::test_support;
+
+    fn rule(conditions: Value) -> ConditionRule {
+        ConditionRule::new(serde_json::from_value(json!({
+            "name": "test",
+            "conditions": conditions,
+            "actions": [{ "type": "alert", "message": "{rule} matched" }]
+        })).expect("test rule should deserialize"))
+    }
+
+    #[test]
+    fn numbers_are_compared_by_value() {
+        let cases = [
+            (json!(5), Comparison::Eq, json!(5.0), true),
+            (json!(5), Comparison::Ne, json!(4), true),
+            (json!(5), Comparison::Gt, json!(4.5), true),
+            (json!(5), Comparison::Gt, json!(5), false),
+            (json!(5), Comparison::Gte, json!(5), true),
+            (json!(4), Comparison::Lt, json!(5), true),
+            (json!(5), Comparison::Lte, json!(5), true),
+            (json!(6), Comparison::Lte, json!(5), false),
+            (json!(true), Comparison::Eq, json!(1), true),
+            (json!(false), Comparison::Lt, json!(true), true),
+        ];
+        for (actual, op, expected, holds) in cases {
+            assert_eq!(compare(&actual, op, &expected), holds, "{} {:?} {}", actual, op, expected);
+        }
+    }
+
+    #[test]
+    fn strings_are_compared_as_text() {
+        let cases = [
+            (json!("Loading"), Comparison::Eq, json!("Loading"), true),
+            (json!("Loading"), Comparison::Ne, json!("Unassigned"), true),
+            (json!("b"), Comparison::Gt, json!("a"), true),
+            (json!("10"), Comparison::Lt, json!("9"), true),
+        ];
+        for (actual, op, expected, holds) in cases {
+            assert_eq!(compare(&actual, op, &expected), holds, "{} {:?} {}", actual, op, expected);
+        }
+    }
+
+    #[test]
+    fn mismatched_types_are_only_equal_or_not() {
+        assert!(!compare(&json!("5"), Comparison::Eq, &json!(5)));
+        assert!(compare(&json!("5"), Comparison::Ne, &json!(5)));
+        assert!(compare(&Value::Null, Comparison::Eq, &Value::Null));
+        assert!(compare(&Value::Null, Comparison::Ne, &json!("Loading")));
+        for op in [Comparison::Gt, Comparison::Gte, Comparison::Lt, Comparison::Lte] {
+            assert!(!compare(&json!("5"), op, &json!(5)), "{:?}", op);
+            assert!(!compare(&Value::Null, op, &json!(0)), "{:?}", op);
+        }
+    }
+
+    #[test]
+    fn lookup_follows_dotted_paths() {
+        let value = json!({ "loading_status": { "loading_status": "Loading", "wms_shipment_status": null } });
+
+        assert_eq!(lookup(&value, "loading_status.loading_status"), Some(&json!("Loading")));
+        assert_eq!(lookup(&value, "loading_status.wms_shipment_status"), Some(&Value::Null));
+        assert_eq!(lookup(&value, "loading_status.missing"), None);
+        assert_eq!(lookup(&value, "loading_status.loading_status.deeper"), None);
+        assert_eq!(lookup(&value, "missing.loading_status"), None);
+    }
+
+    #[test]
+    fn paths_may_continue_past_empty_fields_only() {
+        let value = json!({ "current_visit": null, "door_state": "Unassigned", "loading_status": { "loading_status": "Idle" } });
+
+        assert!(path_exists(&value, "loading_status.loading_status"));
+        assert!(path_exists(&value, "current_visit.arrived_at"));
+        assert!(!path_exists(&value, "loading_status.missing"));
+        assert!(!path_exists(&value, "missing"));
+        assert!(!path_exists(&value, "door_state.deeper"));
+    }
+
+    #[test]
+    fn conditions_test_the_door_event_and_sensors() {
+        let door = test_support::door(&test_support::plant());
+        let door_json = serde_json::to_value(&door).unwrap();
+        let event_json = json!({ "SensorStateChanged": { "sensor_name": "TRAILER_AT_DOOR" } });
+        let now = door.last_updated;
+
+        let holds = |conditions: Value| rule(conditions).conditions_hold(&door, &door_json, &event_json, now);
+
+        assert!(holds(json!([{ "field": "door_state", "op": "eq", "value": "Unassigned" }])));
+        assert!(holds(json!([{ "event_field": "sensor_name", "op": "eq", "value": "TRAILER_AT_DOOR" }])));
+        assert!(!holds(json!([
+            { "field": "door_state", "op": "eq", "value": "Unassigned" },
+            { "event_field": "sensor_name", "op": "eq", "value": "RH_DOCK_READY" }
+        ])));
+        assert!(!holds(json!([{ "field": "no_such_field", "op": "ne", "value": "Unassigned" }])));
+        assert!(!holds(json!([{ "sensor": "TRAILER_AT_DOOR", "op": "eq", "value": 0 }])), "a sensor with no reading yet never matches");
+        assert!(!holds(json!([{ "sensor": "NO_SUCH_SENSOR", "op": "ne", "value": 1 }])));
+    }
+
+    #[test]
+    fn door_json_is_serialized_once_per_evaluation() {
+        let mut door = test_support::door(&test_support::plant());
+        let door_json = DoorJson::default();
+        let shared = door_json.clone();
+
+        assert_eq!(door_json.get(&door).unwrap()["door_state"], json!("Unassigned"));
+        door.door_state = DoorState::Loading;
+        assert_eq!(shared.get(&door).unwrap()["door_state"], json!("Unassigned"));
+        assert_eq!(DoorJson::default().get(&door).unwrap()["door_state"], json!("Loading"));
+    }
+}
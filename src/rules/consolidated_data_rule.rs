@@ -1,5 +1,6 @@
 use chrono::NaiveDateTime;
-use crate::analysis::context_analyzer::{AnalysisRule, AnalysisResult};
+use async_trait::async_trait;
+use crate::analysis::context_analyzer::{AnalysisRule, RuleContext, AnalysisResult};
 use crate::models::{DockDoor, DockDoorEvent, FirstDropEvent};
 use crate::models::consolidated_dock_event::ConsolidatedDockEvent;
 
@@ -60,8 +61,9 @@ impl ConsolidatedDataRule {
     }
 }
 
+#[async_trait]
 impl AnalysisRule for ConsolidatedDataRule {
-    fn apply(&self, door: &DockDoor, event: &DockDoorEvent) -> Vec<AnalysisResult> {
+    async fn apply(&self, door: &DockDoor, event: &DockDoorEvent, _context: &RuleContext) -> Vec<AnalysisResult> {
         match event {
             DockDoorEvent::FirstDrop(e) => {
                 self.build_consolidated(door, e)
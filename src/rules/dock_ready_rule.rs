@@ -1,19 +1,21 @@
 use crate::models::{DockDoor, DockDoorEvent, DoorState};
-use crate::analysis::context_analyzer::{AnalysisRule, AnalysisResult, AlertType, LogEntry};
+use async_trait::async_trait;
+use crate::analysis::context_analyzer::{AnalysisRule, RuleContext, AnalysisResult, AlertType, LogEntry};
 use chrono::Local;
 use log::info;
 
 pub struct DockReadyRule;
 
+#[async_trait]
 impl AnalysisRule for DockReadyRule {
-    fn apply(&self, dock_door: &DockDoor, event: &DockDoorEvent) -> Vec<AnalysisResult> {
+    async fn apply(&self, dock_door: &DockDoor, event: &DockDoorEvent, _context: &RuleContext) -> Vec<AnalysisResult> {
         info!("DockReadyRule applying to event: {:?}", event);
 
         let mut results = Vec::new();
 
         match event {
             DockDoorEvent::SensorStateChanged(e) if e.sensor_name == "RH_DOCK_READY" => {
-                if e.new_value == Some(1) && e.old_value == Some(0)  {
+                if e.turned_on() {
                     info!("Door ready detected, checking conditions...");
 
                     results.push(AnalysisResult::Alert(AlertType::DockReady {
@@ -1,9 +1,11 @@
 use crate::models::{DockDoor, DockDoorEvent, TrailerState};
-use crate::analysis::context_analyzer::{AnalysisRule, AnalysisResult, LogEntry};
+use async_trait::async_trait;
+use crate::analysis::context_analyzer::{AnalysisRule, RuleContext, AnalysisResult, LogEntry};
 
 /// An analysis rule that logs trailer state changes (docked/undocked).
 pub struct TrailerStateChangeRule;
 
+#[async_trait]
 impl AnalysisRule for TrailerStateChangeRule {
     /// Applies the rule to a dock door event, generating a log entry if the event is a trailer state change
     ///
@@ -21,7 +23,7 @@ impl AnalysisRule for TrailerStateChangeRule {
     /// # Returns
     ///
     /// A vector containing an `AnalysisResult::Log` if the event is a trailer state change, otherwise an empty vector
-    fn apply(&self, door: &DockDoor, event: &DockDoorEvent) -> Vec<AnalysisResult> {
+    async fn apply(&self, door: &DockDoor, event: &DockDoorEvent, _context: &RuleContext) -> Vec<AnalysisResult> {
         if let DockDoorEvent::TrailerStateChanged(e) = event {
             let event_type = match e.new_state {
                 TrailerState::Docked => "TRAILER_DOCKED",
@@ -1,11 +1,13 @@
 use std::fs::File;
 use std::io::BufReader;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
 use serde::{Deserialize, Serialize};
 use anyhow::{Result, Context};
 use log::info;
 use crate::analysis::context_analyzer::AnalysisRule;
+use crate::config::Settings;
 use crate::rules::rule_factory::RuleFactory;
 
 /// Represents the configuration for a dynamically loaded analysis rule
@@ -15,6 +17,17 @@ pub struct RuleConfig {
     pub rule_type: String,
     /// The parameters specific to the rule type, serialized as a JSON value
     pub parameters: serde_json::Value,
+    /// The number of seconds the rule may take to analyze an event, overriding `analysis.rule_timeout_secs`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+}
+
+/// An analysis rule created from its configuration
+pub struct LoadedRule {
+    /// The created rule
+    pub rule: Arc<dyn AnalysisRule>,
+    /// The time the rule may take to analyze an event, if it overrides the default
+    pub timeout: Option<Duration>,
 }
 
 /// Manages the dynamic loading and configuration of analysis rules from a JSON file
@@ -31,13 +44,19 @@ impl DynamicRuleManager {
     /// # Arguments
     ///
     /// * `config_path`: The path to the JSON file containing rule configurations
-    pub fn new(config_path: PathBuf) -> Self {
+    /// * `settings`: The application settings the rule configurations are checked against
+    pub fn new(config_path: PathBuf, settings: Arc<Settings>) -> Self {
         DynamicRuleManager {
-            rule_factory: RuleFactory::new(),
+            rule_factory: RuleFactory::new(settings),
             config_path,
         }
     }
 
+    /// Returns the path to the JSON file containing the rule configurations
+    pub fn config_path(&self) -> &Path {
+        &self.config_path
+    }
+
     /// Loads analysis rules from the configuration file
     ///
     /// This method reads the JSON configuration file, parses the rule configurations, and uses the `RuleFactory` 
@@ -46,38 +65,73 @@ impl DynamicRuleManager {
     ///
     /// # Returns
     ///
-    /// * `Ok(Vec<Arc<dyn AnalysisRule>>)`: A vector of dynamically loaded analysis rules
-    /// * `Err(anyhow::Error)`: If there's an error opening, reading, parsing the configuration file, or creating the rules
-    pub fn load_rules(&self) -> Result<Vec<Arc<dyn AnalysisRule>>> {
+    /// * `Ok(Vec<LoadedRule>)`: A vector of dynamically loaded analysis rules, with their timeouts
+    /// * `Err(anyhow::Error)`: If there's an error opening, reading, parsing the configuration file, or creating the rules;
+    ///   every rule that could not be created is listed
+    pub fn load_rules(&self) -> Result<Vec<LoadedRule>> {
         info!("Loading rules from config file: {:?}", self.config_path);
-        let file = File::open(&self.config_path)
-            .with_context(|| format!("Failed to open config file: {:?}", self.config_path))?;
-        let reader = BufReader::new(file);
-        let configs: Vec<RuleConfig> = serde_json::from_reader(reader)
+        let configs = self.load_rule_configs()?;
+        self.create_rules(&configs)
+    }
+
+    /// Creates the analysis rules described by the content of a rules file, already read from `config_path`
+    ///
+    /// # Arguments
+    /// * `content`: The JSON content of the rules file
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<LoadedRule>)`: The rules, with their timeouts
+    /// * `Err(anyhow::Error)`: If the content cannot be parsed or any of the rules cannot be created
+    pub fn parse_rules(&self, content: &[u8]) -> Result<Vec<LoadedRule>> {
+        let configs: Vec<RuleConfig> = serde_json::from_slice(content)
             .with_context(|| "Failed to parse rule configurations")?;
+        self.create_rules(&configs)
+    }
+
+    /// Creates the analysis rules of a list of rule configurations, listing every rule that could not be created
+    fn create_rules(&self, configs: &[RuleConfig]) -> Result<Vec<LoadedRule>> {
         info!("Loaded {} rule configurations", configs.len());
-        configs.into_iter()
-            .map(|config| {
-                info!("Creating rule: {}", config.rule_type);
-                self.rule_factory.create_rule(&config.rule_type, &config.parameters)
-            })
-            .collect()
+        let mut rules = Vec::with_capacity(configs.len());
+        let mut failures = Vec::new();
+        for (index, config) in configs.iter().enumerate() {
+            info!("Creating rule: {}", config.rule_type);
+            match self.create_rule(config) {
+                Ok(rule) => rules.push(rule),
+                Err(e) => failures.push(format!("rule {} ({}): {:#}", index + 1, config.rule_type, e)),
+            }
+        }
+        if !failures.is_empty() {
+            anyhow::bail!("Failed to create {} of {} rules in {:?}: {}", failures.len(), configs.len(), self.config_path, failures.join("; "));
+        }
+        Ok(rules)
     }
 
     /// Adds a new rule configuration to the existing ones and saves them
     ///
+    /// The rule is created first, so a configuration that does not make a valid rule is never saved.
+    ///
     /// # Arguments
     /// * `rule_config`: The new `RuleConfig` to add
     ///
     /// # Returns
     /// * `Ok(())` if the rule was added and saved successfully
-    /// * `Err(anyhow::Error)` if there is an error loading or saving the configurations
+    /// * `Err(anyhow::Error)` if the rule is invalid or there is an error loading or saving the configurations
     pub fn add_rule(&self, rule_config: RuleConfig) -> Result<()> {
+        self.create_rule(&rule_config)
+            .with_context(|| format!("Invalid {} rule", rule_config.rule_type))?;
         let mut configs = self.load_rule_configs()?;
         configs.push(rule_config);
         self.save_rule_configs(&configs)
     }
 
+    /// Creates the analysis rule described by a rule configuration
+    fn create_rule(&self, config: &RuleConfig) -> Result<LoadedRule> {
+        let rule = self.rule_factory.create_rule(&config.rule_type, &config.parameters)?;
+        let timeout = config.timeout_secs.map(Duration::from_secs);
+        Ok(LoadedRule { rule, timeout })
+    }
+
     /// Loads rule configurations from the JSON file
     ///
     /// # Returns
@@ -1,7 +1,8 @@
 use std::collections::HashMap;
 use chrono::{NaiveDateTime, Local, Duration};
 use serde::{Deserialize, Serialize};
-use crate::analysis::context_analyzer::{AnalysisRule, AnalysisResult, AlertType};
+use async_trait::async_trait;
+use crate::analysis::context_analyzer::{AnalysisRule, RuleContext, AnalysisResult, AlertType};
 use crate::models::{DockDoor, DockDoorEvent, LoadingStatus};
 
 /// Configuration for the LongLoadingStartRule
@@ -30,14 +31,13 @@ impl LongLoadingStartRule {
     ///
     /// # Returns
     ///
-    /// A new instance of LongLoadingStartRule
-    pub fn new(config: serde_json::Value) -> Self {
-        let parsed_config: LongLoadingStartRuleConfig = serde_json::from_value(config)
-            .expect("Failed to parse LongLoadingStartRule configuration");
-        Self {
+    /// A new instance of LongLoadingStartRule, or the error if the configuration could not be parsed
+    pub fn new(config: serde_json::Value) -> serde_json::Result<Self> {
+        let parsed_config: LongLoadingStartRuleConfig = serde_json::from_value(config)?;
+        Ok(Self {
             config: parsed_config,
             last_alert_time: HashMap::new(),
-        }
+        })
     }
 
     /// Checks if an alert should be sent based on the last alert time and repeat interval
@@ -63,6 +63,7 @@ impl LongLoadingStartRule {
     }
 }
 
+#[async_trait]
 impl AnalysisRule for LongLoadingStartRule {
     /// Applies the LongLoadingStartRule to the given dock door and event
     ///
@@ -74,7 +75,7 @@ impl AnalysisRule for LongLoadingStartRule {
     /// # Returns
     ///
     /// A vector of AnalysisResult, which may contain alerts if the rule conditions are met
-    fn apply(&self, dock_door: &DockDoor, event: &DockDoorEvent) -> Vec<AnalysisResult> {
+    async fn apply(&self, dock_door: &DockDoor, event: &DockDoorEvent, _context: &RuleContext) -> Vec<AnalysisResult> {
         let mut results = Vec::new();
 
         match event {
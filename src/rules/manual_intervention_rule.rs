@@ -3,7 +3,8 @@ use std::sync::{Arc, Mutex};
 use chrono::{Duration, Local, NaiveDateTime};
 use derive_more::Constructor;
 use crate::models::{DockDoor, DockDoorEvent, ManualMode};
-use crate::analysis::context_analyzer::{AnalysisRule, AnalysisResult, AlertType, LogEntry};
+use async_trait::async_trait;
+use crate::analysis::context_analyzer::{AnalysisRule, RuleContext, AnalysisResult, AlertType, LogEntry};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
@@ -36,14 +37,13 @@ impl ManualInterventionRule {
     ///
     /// # Returns
     ///
-    /// A new instance of `ManualInterventionRule`
-    pub fn new(config: Value) -> Self {
-        let parsed_config: ManualInterventionRuleConfig = serde_json::from_value(config)
-            .expect("Failed to parse ManualInterventionRule configuration");
-        ManualInterventionRule {
+    /// A new instance of `ManualInterventionRule`, or the error if the configuration could not be parsed
+    pub fn new(config: Value) -> serde_json::Result<Self> {
+        let parsed_config: ManualInterventionRuleConfig = serde_json::from_value(config)?;
+        Ok(ManualInterventionRule {
             config: parsed_config,
             monitoring: Arc::new(Mutex::new(HashMap::new())),
-        }
+        })
     }
 
     /// Starts monitoring a dock door for manual intervention
@@ -138,6 +138,7 @@ impl ManualInterventionRule {
     }
 }
 
+#[async_trait]
 impl AnalysisRule for ManualInterventionRule {
     /// Applies the rule to a dock door event, generating appropriate analysis results
     ///
@@ -152,10 +153,10 @@ impl AnalysisRule for ManualInterventionRule {
     /// # Returns
     ///
     /// A vector of `AnalysisResult` containing logs and alerts generated based on the event
-    fn apply(&self, dock_door: &DockDoor, event: &DockDoorEvent) -> Vec<AnalysisResult> {
+    async fn apply(&self, dock_door: &DockDoor, event: &DockDoorEvent, _context: &RuleContext) -> Vec<AnalysisResult> {
         match event {
             DockDoorEvent::SensorStateChanged(e) if e.sensor_name == "RH_MANUAL_MODE" => {
-                if e.new_value == Some(1) && e.old_value == Some(0) && dock_door.assigned_shipment.current_shipment.is_some() {
+                if e.turned_on() && dock_door.assigned_shipment.current_shipment.is_some() {
                     self.start_monitoring(e.dock_name.clone(), dock_door.assigned_shipment.current_shipment.clone().unwrap_or_default());
                     vec![AnalysisResult::Log(LogEntry::ManualInterventionStarted {
                         log_dttm: e.timestamp,
@@ -169,7 +170,7 @@ impl AnalysisRule for ManualInterventionRule {
                         previous_state: None,
                         previous_state_dttm: None,
                     })]
-                } else if e.new_value == Some(0) && e.old_value == Some(1) {
+                } else if e.turned_off() {
                     if let Some((start_time, _shipment_id)) = self.stop_monitoring(&e.dock_name) {
                         vec![AnalysisResult::Log(LogEntry::ManualInterventionSuccess {
                             log_dttm: e.timestamp,
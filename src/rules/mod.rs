@@ -1,5 +1,6 @@
 pub mod dynamic_rule_manager;
 pub mod rule_factory;
+pub mod rule_reloader;
 pub mod trailer_docking_rule;
 pub mod new_shipment_old_trailer_rule;
 pub mod manual_intervention_rule;
@@ -15,9 +16,14 @@ pub mod dock_ready_rule;
 pub mod consolidated_data_rule;
 pub mod wms_events_rule;
 pub mod trailer_at_door_db;
+pub mod plc_connectivity_rule;
+pub mod sensor_threshold_rule;
+pub mod stuck_sensor_rule;
+pub mod condition_rule;
 
 pub use dynamic_rule_manager::*;
 pub use rule_factory::*;
+pub use rule_reloader::*;
 pub use trailer_docking_rule::*;
 pub use docking_state_rule::*;
 pub use new_shipment_old_trailer_rule::*;
@@ -1,5 +1,6 @@
 use crate::models::{DockDoor, DockDoorEvent, TrailerState};
-use crate::analysis::context_analyzer::{AnalysisRule, AnalysisResult, AlertType};
+use async_trait::async_trait;
+use crate::analysis::context_analyzer::{AnalysisRule, RuleContext, AnalysisResult, AlertType};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use crate::analysis::LogEntry;
@@ -19,10 +20,9 @@ pub struct NewShipmentPreviousTrailerPresentRule {
 
 impl NewShipmentPreviousTrailerPresentRule {
     /// Creates a new `NewShipmentPreviousTrailerPresentRule` with the given configuration
-    pub fn new(config: Value) -> Self {
-        let parsed_config: NewShipmentPreviousTrailerPresentRuleConfig = serde_json::from_value(config)
-            .expect("Failed to parse NewShipmentPreviousTrailerPresentRule configuration");
-        NewShipmentPreviousTrailerPresentRule { config: parsed_config }
+    pub fn new(config: Value) -> serde_json::Result<Self> {
+        let parsed_config: NewShipmentPreviousTrailerPresentRuleConfig = serde_json::from_value(config)?;
+        Ok(NewShipmentPreviousTrailerPresentRule { config: parsed_config })
     }
 
     /// Checks if the previous shipment associated with the dock door is considered complete
@@ -46,6 +46,7 @@ impl NewShipmentPreviousTrailerPresentRule {
     }
 }
 
+#[async_trait]
 impl AnalysisRule for NewShipmentPreviousTrailerPresentRule {
     /// Applies the rule to a dock door event, generating an alert and a log entry if a new shipment is assigned while the previous trailer is still present
     ///
@@ -68,7 +69,7 @@ impl AnalysisRule for NewShipmentPreviousTrailerPresentRule {
     /// # Returns
     ///
     /// A vector containing an alert and a log entry if the rule conditions are met, otherwise an empty vector
-    fn apply(&self, dock_door: &DockDoor, event: &DockDoorEvent) -> Vec<AnalysisResult> {
+    async fn apply(&self, dock_door: &DockDoor, event: &DockDoorEvent, _context: &RuleContext) -> Vec<AnalysisResult> {
         match event {
             DockDoorEvent::ShipmentAssigned(e) => {
                 if dock_door.trailer_state == TrailerState::Docked && self.is_previous_shipment_complete(dock_door) {
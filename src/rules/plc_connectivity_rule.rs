@@ -0,0 +1,107 @@
+use chrono::Local;
+use log::info;
+use serde::{Deserialize, Serialize};
+use async_trait::async_trait;
+use crate::analysis::context_analyzer::{AnalysisRule, RuleContext, AnalysisResult, AlertType, LogEntry};
+use crate::models::{DockDoor, DockDoorEvent, DoorOfflineEvent, DoorOnlineEvent};
+
+/// Configuration for the PlcConnectivityRule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlcConnectivityRuleConfig {
+    /// Whether an alert is also sent when a door's PLC comes back online (defaults to true)
+    #[serde(default = "default_alert_on_recovery")]
+    pub alert_on_recovery: bool,
+}
+
+fn default_alert_on_recovery() -> bool {
+    true
+}
+
+/// Rule for alerting on and logging the loss and recovery of a door's PLC connection
+pub struct PlcConnectivityRule {
+    /// The parsed configuration for this rule
+    config: PlcConnectivityRuleConfig,
+}
+
+impl PlcConnectivityRule {
+    /// Creates a new PlcConnectivityRule with the given configuration
+    pub fn new(config: PlcConnectivityRuleConfig) -> Self {
+        Self { config }
+    }
+
+    /// Generates the alert and log entry for a door whose PLC went offline
+    fn handle_offline(&self, door: &DockDoor, event: &DoorOfflineEvent) -> Vec<AnalysisResult> {
+        let notes = format!(
+            "PLC not responding after {} polling cycles: {}",
+            event.consecutive_failures,
+            event.reason.as_deref().unwrap_or("no error reported")
+        );
+
+        vec![
+            AnalysisResult::Alert(AlertType::DoorOffline {
+                door_name: door.dock_name.clone(),
+                shipment_id: door.assigned_shipment.current_shipment.clone(),
+                consecutive_failures: event.consecutive_failures,
+                last_good_read: event.last_good_read,
+                reason: event.reason.clone(),
+            }),
+            AnalysisResult::Log(LogEntry::PlcConnectivity {
+                log_dttm: Local::now().naive_local(),
+                plant: door.plant_id.clone(),
+                door_name: door.dock_name.clone(),
+                shipment_id: door.assigned_shipment.current_shipment.clone(),
+                event_type: "PLC_OFFLINE".to_string(),
+                success: false,
+                notes,
+                severity: 2,
+                previous_state: Some("PLC_ONLINE".to_string()),
+                previous_state_dttm: event.last_good_read,
+            }),
+        ]
+    }
+
+    /// Generates the log entry, and optionally the alert, for a door whose PLC came back online
+    fn handle_online(&self, door: &DockDoor, event: &DoorOnlineEvent) -> Vec<AnalysisResult> {
+        let offline_duration = event.offline_since.map(|since| event.timestamp.signed_duration_since(since));
+        let mut results = Vec::new();
+
+        if self.config.alert_on_recovery {
+            results.push(AnalysisResult::Alert(AlertType::DoorOnline {
+                door_name: door.dock_name.clone(),
+                shipment_id: door.assigned_shipment.current_shipment.clone(),
+                offline_duration,
+            }));
+        }
+
+        results.push(AnalysisResult::Log(LogEntry::PlcConnectivity {
+            log_dttm: Local::now().naive_local(),
+            plant: door.plant_id.clone(),
+            door_name: door.dock_name.clone(),
+            shipment_id: door.assigned_shipment.current_shipment.clone(),
+            event_type: "PLC_ONLINE".to_string(),
+            success: true,
+            notes: match offline_duration {
+                Some(duration) => format!("PLC responding again after {} seconds offline", duration.num_seconds()),
+                None => "PLC responding again".to_string(),
+            },
+            severity: 0,
+            previous_state: Some("PLC_OFFLINE".to_string()),
+            previous_state_dttm: event.offline_since,
+        }));
+
+        results
+    }
+}
+
+#[async_trait]
+impl AnalysisRule for PlcConnectivityRule {
+    async fn apply(&self, door: &DockDoor, event: &DockDoorEvent, _context: &RuleContext) -> Vec<AnalysisResult> {
+        let results = match event {
+            DockDoorEvent::DoorOffline(e) => self.handle_offline(door, e),
+            DockDoorEvent::DoorOnline(e) => self.handle_online(door, e),
+            _ => return Vec::new(),
+        };
+        info!("PlcConnectivityRule results for door {}: {:?}", door.dock_name, results);
+        results
+    }
+}
@@ -2,20 +2,32 @@ use std::sync::Arc;
 use anyhow::{Result};
 use serde_json::Value;
 use crate::analysis::context_analyzer::AnalysisRule;
+use crate::config::Settings;
 use crate::rules::{suspended_door_rule::{SuspendedDoorRule}, long_loading_start_rule::{LongLoadingStartRule}, trailer_hostage_rule::{TrailerHostageRule}, shipment_started_load_not_ready_rule::{ShipmentStartedLoadNotReadyRule}, trailer_pattern_rule::{TrailerPatternRule}, trailer_docking_rule::{TrailerDockingRule}, manual_intervention_rule::{ManualInterventionRule}, NewShipmentPreviousTrailerPresentRule, TrailerUndockingRule};
+use crate::rules::condition_rule::{ConditionRule, ConditionRuleConfig};
 use crate::rules::consolidated_data_rule::ConsolidatedDataRule;
 use crate::rules::dock_ready_rule::DockReadyRule;
+use crate::rules::plc_connectivity_rule::{PlcConnectivityRule, PlcConnectivityRuleConfig};
+use crate::rules::sensor_threshold_rule::{SensorThresholdRule, SensorThresholdRuleConfig};
+use crate::rules::stuck_sensor_rule::{StuckSensorRule, StuckSensorRuleConfig};
 use crate::rules::trailer_at_door_db::{TrailerAtDoorUpdateRule, TrailerAtDoorUpdateRuleConfig};
 use crate::rules::wms_events_rule::WmsEventsRule;
 
 /// A factory for creating analysis rules based on their configuration
-#[derive(Debug, Default)]
-pub struct RuleFactory;
+#[derive(Debug)]
+pub struct RuleFactory {
+    /// The application settings the rule configurations are checked against
+    settings: Arc<Settings>,
+}
 
 impl RuleFactory {
     /// Creates a new `RuleFactory`
-    pub fn new() -> Self {
-        RuleFactory
+    ///
+    /// # Arguments
+    ///
+    /// * `settings`: The application settings the rule configurations are checked against
+    pub fn new(settings: Arc<Settings>) -> Self {
+        RuleFactory { settings }
     }
 
     /// Creates an analysis rule based on the provided rule type and configuration
@@ -44,6 +56,10 @@ impl RuleFactory {
             "ConsolidatedDataRule" => Ok(Arc::new(ConsolidatedDataRule::new())),
             "WmsEventsRule" => Ok(Arc::new(WmsEventsRule)),
             "TrailerAtDoorUpdateRule" => self.create_trailer_at_door_update_rule(config),
+            "PlcConnectivityRule" => self.create_plc_connectivity_rule(config),
+            "SensorThresholdRule" => self.create_sensor_threshold_rule(config),
+            "StuckSensorRule" => self.create_stuck_sensor_rule(config),
+            "ConditionRule" => self.create_condition_rule(config),
 
             _ => Err(anyhow::anyhow!("Unknown rule type: {}", rule_type)),
         }
@@ -51,50 +67,78 @@ impl RuleFactory {
 
     /// Creates a `SuspendedDoorRule` based on the provided configuration
     fn create_suspended_door_rule(&self, config: &Value) -> Result<Arc<dyn AnalysisRule>> {
-        Ok(Arc::new(SuspendedDoorRule::new(config.clone())))
+        Ok(Arc::new(SuspendedDoorRule::new(config.clone())?))
     }
 
     /// Creates a `LongLoadingStartRule` based on the provided configuration
     fn create_long_loading_start_rule(&self, config: &Value) -> Result<Arc<dyn AnalysisRule>> {
-        Ok(Arc::new(LongLoadingStartRule::new(config.clone())))
+        Ok(Arc::new(LongLoadingStartRule::new(config.clone())?))
     }
 
     /// Creates a `TrailerHostageRule` based on the provided configuration
     fn create_trailer_hostage_rule(&self, config: &Value) -> Result<Arc<dyn AnalysisRule>> {
-        Ok(Arc::new(TrailerHostageRule::new(config.clone())))
+        Ok(Arc::new(TrailerHostageRule::new(config.clone())?))
     }
 
     /// Creates a `ShipmentStartedLoadNotReadyRule` based on the provided configuration
     fn create_shipment_started_load_not_ready_rule(&self, config: &Value) -> Result<Arc<dyn AnalysisRule>> {
-        Ok(Arc::new(ShipmentStartedLoadNotReadyRule::new(config.clone())))
+        Ok(Arc::new(ShipmentStartedLoadNotReadyRule::new(config.clone())?))
     }
 
     /// Creates a `TrailerPatternRule` based on the provided configuration
     fn create_trailer_pattern_rule(&self, config: &Value) -> Result<Arc<dyn AnalysisRule>> {
-        Ok(Arc::new(TrailerPatternRule::new(config.clone())))
+        Ok(Arc::new(TrailerPatternRule::new(config.clone())?))
     }
 
     /// Creates a `TrailerDockingRule` based on the provided configuration
     fn create_trailer_docking_rule(&self, config: &Value) -> Result<Arc<dyn AnalysisRule>> {
-        Ok(Arc::new(TrailerDockingRule::new(config.clone())))
+        Ok(Arc::new(TrailerDockingRule::new(config.clone())?))
     }
 
     /// Creates a `NewShipmentPreviousTrailerPresentRule` based on the provided configuration
     fn create_new_shipment_previous_trailer_present_rule(&self, config: &Value) -> Result<Arc<dyn AnalysisRule>> {
-        Ok(Arc::new(NewShipmentPreviousTrailerPresentRule::new(config.clone())))
+        Ok(Arc::new(NewShipmentPreviousTrailerPresentRule::new(config.clone())?))
     }
 
     /// Creates a `ManualInterventionRule` based on the provided configuration
     fn create_manual_intervention_rule(&self, config: &Value) -> Result<Arc<dyn AnalysisRule>> {
-        Ok(Arc::new(ManualInterventionRule::new(config.clone())))
+        Ok(Arc::new(ManualInterventionRule::new(config.clone())?))
     }
 
     fn create_trailer_undocking_rule(&self, config: &Value) -> Result<Arc<dyn AnalysisRule>> {
-        Ok(Arc::new(TrailerUndockingRule::new(config.clone())))
+        Ok(Arc::new(TrailerUndockingRule::new(config.clone())?))
     }
 
     fn create_trailer_at_door_update_rule(&self, config: &Value) -> Result<Arc<dyn AnalysisRule>> {
         let rule_config: TrailerAtDoorUpdateRuleConfig = serde_json::from_value(config.clone())?;
         Ok(Arc::new(TrailerAtDoorUpdateRule::new(rule_config)))
     }
+
+    /// Creates a `PlcConnectivityRule` based on the provided configuration
+    fn create_plc_connectivity_rule(&self, config: &Value) -> Result<Arc<dyn AnalysisRule>> {
+        let rule_config: PlcConnectivityRuleConfig = serde_json::from_value(config.clone())?;
+        Ok(Arc::new(PlcConnectivityRule::new(rule_config)))
+    }
+
+    /// Creates a `SensorThresholdRule` based on the provided configuration
+    fn create_sensor_threshold_rule(&self, config: &Value) -> Result<Arc<dyn AnalysisRule>> {
+        let rule_config: SensorThresholdRuleConfig = serde_json::from_value(config.clone())?;
+        Ok(Arc::new(SensorThresholdRule::new(rule_config)))
+    }
+
+    /// Creates a `StuckSensorRule` based on the provided configuration
+    fn create_stuck_sensor_rule(&self, config: &Value) -> Result<Arc<dyn AnalysisRule>> {
+        let rule_config: StuckSensorRuleConfig = serde_json::from_value(config.clone())?;
+        Ok(Arc::new(StuckSensorRule::new(rule_config)))
+    }
+
+    /// Creates a `ConditionRule` based on the provided configuration
+    fn create_condition_rule(&self, config: &Value) -> Result<Arc<dyn AnalysisRule>> {
+        let rule_config: ConditionRuleConfig = serde_json::from_value(config.clone())?;
+        let problems = rule_config.problems(&self.settings);
+        if !problems.is_empty() {
+            return Err(anyhow::anyhow!(problems.join("; ")));
+        }
+        Ok(Arc::new(ConditionRule::new(rule_config)))
+    }
 }
\ No newline at end of file
@@ -0,0 +1,110 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use anyhow::{Context, Result};
+use log::{error, info};
+use crate::analysis::context_analyzer::ContextAnalyzer;
+use crate::rules::dynamic_rule_manager::DynamicRuleManager;
+
+/// Identifies a version of the rules file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct RulesFileVersion {
+    /// The modification time of the file, if the platform reports one
+    modified: Option<SystemTime>,
+    /// The size of the file, in bytes
+    len: usize,
+    /// A hash of the file's content
+    hash: u64,
+}
+
+impl RulesFileVersion {
+    fn of(modified: Option<SystemTime>, content: &[u8]) -> Self {
+        let mut hasher = DefaultHasher::new();
+        content.hash(&mut hasher);
+        Self { modified, len: content.len(), hash: hasher.finish() }
+    }
+}
+
+/// Keeps the rules of a running `ContextAnalyzer` in line with the rules file
+///
+/// The file is reloaded when its modification time, size or content changes, so an edit saved within the same
+/// modification time is still noticed. The new rules are all created before any of them is put in place, and the
+/// analyzer's loaded rules are then replaced in one step; if any rule fails, every failure is reported and the
+/// previous rules stay in place. Rules keep no state across a reload, so stateful rules such as the
+/// `StuckSensorRule` start over.
+pub struct RuleReloader {
+    /// The manager the rules are created with
+    rule_manager: DynamicRuleManager,
+    /// The analyzer whose loaded rules are replaced
+    analyzer: Arc<ContextAnalyzer>,
+    /// The version of the rules file when it was last loaded, if it could be read
+    loaded_version: Mutex<Option<RulesFileVersion>>,
+}
+
+impl RuleReloader {
+    /// Creates a new `RuleReloader` for an analyzer whose rules were just loaded from the manager's file
+    ///
+    /// # Arguments
+    ///
+    /// * `rule_manager` - The manager the rules are created with
+    /// * `analyzer` - The analyzer whose loaded rules are replaced
+    pub async fn new(rule_manager: DynamicRuleManager, analyzer: Arc<ContextAnalyzer>) -> Self {
+        let loaded_version = Self::read_rules_file(&rule_manager).await.ok().map(|(version, _)| version);
+        Self { rule_manager, analyzer, loaded_version: Mutex::new(loaded_version) }
+    }
+
+    /// Loads the rules file and replaces the analyzer's loaded rules with its rules
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(usize)` with the number of rules now in place
+    /// * `Err(anyhow::Error)` if the file could not be read or any of its rules could not be created,
+    ///   in which case the previous rules stay in place
+    pub async fn reload(&self) -> Result<usize> {
+        let file = Self::read_rules_file(&self.rule_manager).await;
+        self.load(file)
+    }
+
+    /// Reloads the rules file if it changed since it was last loaded, reporting any failure
+    ///
+    /// A file that failed to load is not retried until it changes again.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the file changed and its rules were put in place
+    pub async fn reload_if_changed(&self) -> bool {
+        let file = Self::read_rules_file(&self.rule_manager).await;
+        let version = file.as_ref().ok().map(|(version, _)| *version);
+        if version == *self.loaded_version.lock().unwrap() {
+            return false;
+        }
+        info!("Rules file {:?} changed, reloading it", self.rule_manager.config_path());
+        match self.load(file) {
+            Ok(_) => true,
+            Err(e) => {
+                error!("Keeping the previous rules, the changed rules file could not be loaded: {:#}", e);
+                false
+            },
+        }
+    }
+
+    /// Puts the rules of a read rules file in place, remembering its version whether or not they load
+    fn load(&self, file: io::Result<(RulesFileVersion, Vec<u8>)>) -> Result<usize> {
+        *self.loaded_version.lock().unwrap() = file.as_ref().ok().map(|(version, _)| *version);
+        let (_, content) = file.with_context(|| format!("Failed to read rules file {:?}", self.rule_manager.config_path()))?;
+        let rules = self.rule_manager.parse_rules(&content)?;
+        let count = self.analyzer.replace_loaded_rules(rules);
+        info!("Reloaded {} rules from {:?}", count, self.rule_manager.config_path());
+        Ok(count)
+    }
+
+    /// Reads the manager's rules file along with its version
+    async fn read_rules_file(rule_manager: &DynamicRuleManager) -> io::Result<(RulesFileVersion, Vec<u8>)> {
+        let path = rule_manager.config_path();
+        let modified = tokio::fs::metadata(path).await?.modified().ok();
+        let content = tokio::fs::read(path).await?;
+        Ok((RulesFileVersion::of(modified, &content), content))
+    }
+}
@@ -0,0 +1,255 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Mutex;
+use chrono::Local;
+use log::info;
+use serde::{Deserialize, Serialize};
+use async_trait::async_trait;
+use crate::analysis::context_analyzer::{AnalysisRule, RuleContext, AnalysisResult, AlertType, LogEntry};
+use crate::models::{DockDoor, DockDoorEvent, SensorStateChangedEvent, SensorValue};
+
+/// Comparison applied between a sensor value and the configured threshold
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ThresholdOperator {
+    #[serde(rename = ">")]
+    GreaterThan,
+    #[serde(rename = ">=")]
+    GreaterOrEqual,
+    #[serde(rename = "<")]
+    LessThan,
+    #[serde(rename = "<=")]
+    LessOrEqual,
+    #[serde(rename = "==")]
+    Equal,
+    #[serde(rename = "!=")]
+    NotEqual,
+}
+
+impl ThresholdOperator {
+    /// Returns `true` if `value` satisfies the comparison against `threshold`
+    pub fn matches(&self, value: f64, threshold: f64) -> bool {
+        match self {
+            ThresholdOperator::GreaterThan => value > threshold,
+            ThresholdOperator::GreaterOrEqual => value >= threshold,
+            ThresholdOperator::LessThan => value < threshold,
+            ThresholdOperator::LessOrEqual => value <= threshold,
+            ThresholdOperator::Equal => value == threshold,
+            ThresholdOperator::NotEqual => value != threshold,
+        }
+    }
+}
+
+impl fmt::Display for ThresholdOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            ThresholdOperator::GreaterThan => ">",
+            ThresholdOperator::GreaterOrEqual => ">=",
+            ThresholdOperator::LessThan => "<",
+            ThresholdOperator::LessOrEqual => "<=",
+            ThresholdOperator::Equal => "==",
+            ThresholdOperator::NotEqual => "!=",
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
+/// Configuration for the SensorThresholdRule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SensorThresholdRuleConfig {
+    /// The name of the sensor to watch, e.g. `TRAILER_ANGLE`
+    pub sensor: String,
+    /// The comparison that puts the sensor out of range
+    pub operator: ThresholdOperator,
+    /// The value the sensor is compared against
+    pub threshold: f64,
+    /// The severity recorded with the log entry (defaults to 1)
+    #[serde(default = "default_severity")]
+    pub severity: i32,
+    /// An optional text added to the alert and log entry
+    #[serde(default)]
+    pub message: Option<String>,
+    /// How far back past the threshold the sensor must return before the condition clears, so that a value
+    /// hovering around the threshold does not raise an alert on every crossing (defaults to 0); only used with
+    /// the `>`, `>=`, `<` and `<=` operators
+    #[serde(default)]
+    pub hysteresis: f64,
+}
+
+fn default_severity() -> i32 {
+    1
+}
+
+/// Rule for alerting when a sensor value crosses a numeric threshold
+///
+/// An alert and a log entry are generated when the sensor's value starts satisfying the configured
+/// comparison, and a log entry when it stops; values that stay on the same side of the threshold
+/// generate nothing. With a `hysteresis`, the condition only clears once the value is back past the
+/// threshold by that much.
+pub struct SensorThresholdRule {
+    /// The parsed configuration for this rule
+    config: SensorThresholdRuleConfig,
+    /// Whether the condition is met at each door, keyed by plant and door name
+    exceeded: Mutex<HashMap<(String, String), bool>>,
+}
+
+impl SensorThresholdRule {
+    /// Creates a new SensorThresholdRule with the given configuration
+    pub fn new(config: SensorThresholdRuleConfig) -> Self {
+        Self { config, exceeded: Mutex::new(HashMap::new()) }
+    }
+
+    /// Returns `true` if the given value is out of range
+    fn exceeds(&self, value: Option<SensorValue>) -> bool {
+        value.is_some_and(|value| self.config.operator.matches(value.as_f64(), self.config.threshold))
+    }
+
+    /// Returns `true` if the given value is still out of range once the condition has been met, i.e. has not
+    /// returned past the threshold by the hysteresis
+    fn still_exceeds(&self, value: SensorValue) -> bool {
+        let release_threshold = match self.config.operator {
+            ThresholdOperator::GreaterThan | ThresholdOperator::GreaterOrEqual => self.config.threshold - self.config.hysteresis,
+            ThresholdOperator::LessThan | ThresholdOperator::LessOrEqual => self.config.threshold + self.config.hysteresis,
+            ThresholdOperator::Equal | ThresholdOperator::NotEqual => self.config.threshold,
+        };
+        self.config.operator.matches(value.as_f64(), release_threshold)
+    }
+
+    /// Generates the log entry, and the alert if the threshold was crossed, for a change of the watched sensor
+    fn handle_change(&self, door: &DockDoor, event: &SensorStateChangedEvent) -> Vec<AnalysisResult> {
+        let Some(value) = event.new_value else {
+            return Vec::new();
+        };
+        let is_exceeded = {
+            let mut exceeded = self.exceeded.lock().unwrap();
+            let door_exceeded = exceeded.entry((door.plant_id.clone(), door.dock_name.clone()))
+                .or_insert_with(|| self.exceeds(event.old_value));
+            let was_exceeded = *door_exceeded;
+            *door_exceeded = if was_exceeded { self.still_exceeds(value) } else { self.exceeds(Some(value)) };
+            if *door_exceeded == was_exceeded {
+                return Vec::new();
+            }
+            *door_exceeded
+        };
+
+        let condition = format!("{} {} {}", self.config.sensor, self.config.operator, self.config.threshold);
+        let mut notes = if is_exceeded {
+            format!("{} reported {}, condition {} met", self.config.sensor, value, condition)
+        } else {
+            format!("{} reported {}, condition {} cleared", self.config.sensor, value, condition)
+        };
+        if let Some(message) = &self.config.message {
+            notes = format!("{}: {}", message, notes);
+        }
+
+        let mut results = Vec::new();
+        if is_exceeded {
+            results.push(AnalysisResult::Alert(AlertType::SensorThreshold {
+                door_name: door.dock_name.clone(),
+                shipment_id: door.assigned_shipment.current_shipment.clone(),
+                sensor: self.config.sensor.clone(),
+                value: value.as_f64(),
+                operator: self.config.operator.to_string(),
+                threshold: self.config.threshold,
+                message: self.config.message.clone(),
+            }));
+        }
+
+        results.push(AnalysisResult::Log(LogEntry::SensorThreshold {
+            log_dttm: Local::now().naive_local(),
+            plant: door.plant_id.clone(),
+            door_name: door.dock_name.clone(),
+            shipment_id: door.assigned_shipment.current_shipment.clone(),
+            event_type: if is_exceeded { "SENSOR_THRESHOLD_EXCEEDED" } else { "SENSOR_THRESHOLD_CLEARED" }.to_string(),
+            success: !is_exceeded,
+            notes,
+            severity: if is_exceeded { self.config.severity } else { 0 },
+            previous_state: event.old_value.map(|value| value.to_string()),
+            previous_state_dttm: None,
+        }));
+
+        results
+    }
+}
+
+#[async_trait]
+impl AnalysisRule for SensorThresholdRule {
+    async fn apply(&self, door: &DockDoor, event: &DockDoorEvent, _context: &RuleContext) -> Vec<AnalysisResult> {
+        match event {
+            DockDoorEvent::SensorStateChanged(e) if e.sensor_name == self.config.sensor => {
+                let results = self.handle_change(door, e);
+                if !results.is_empty() {
+                    info!("SensorThresholdRule results for door {}: {:?}", door.dock_name, results);
+                }
+                results
+            },
+            _ => Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::test_support::{self, DOOR_NAME, PLANT_ID};
+
+    fn rule(hysteresis: f64) -> SensorThresholdRule {
+        SensorThresholdRule::new(SensorThresholdRuleConfig {
+            sensor: "TRAILER_ANGLE".to_string(),
+            operator: ThresholdOperator::GreaterThan,
+            threshold: 5.0,
+            severity: 1,
+            message: None,
+            hysteresis,
+        })
+    }
+
+    /// Feeds a sequence of values to the rule, returning the kinds of results of each change
+    fn crossings(rule: &SensorThresholdRule, values: &[f64]) -> Vec<&'static str> {
+        let door = test_support::door(&test_support::plant());
+        values.windows(2)
+            .map(|change| {
+                let event = SensorStateChangedEvent {
+                    plant_id: PLANT_ID.to_string(),
+                    dock_name: DOOR_NAME.to_string(),
+                    sensor_name: "TRAILER_ANGLE".to_string(),
+                    old_value: Some(SensorValue::Float(change[0])),
+                    new_value: Some(SensorValue::Float(change[1])),
+                    timestamp: Local::now().naive_local(),
+                };
+                match rule.handle_change(&door, &event).as_slice() {
+                    [] => "none",
+                    [AnalysisResult::Alert(_), AnalysisResult::Log(_)] => "exceeded",
+                    [AnalysisResult::Log(_)] => "cleared",
+                    other => panic!("unexpected results: {:?}", other),
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn value_hovering_around_the_threshold_alerts_once_with_hysteresis() {
+        let rule = rule(1.0);
+
+        assert_eq!(
+            crossings(&rule, &[4.0, 6.0, 4.5, 5.5, 4.1, 3.9, 4.8, 5.2]),
+            ["exceeded", "none", "none", "none", "cleared", "none", "exceeded"]
+        );
+    }
+
+    #[test]
+    fn every_crossing_counts_without_hysteresis() {
+        let rule = rule(0.0);
+
+        assert_eq!(
+            crossings(&rule, &[4.0, 6.0, 4.5, 5.5, 5.0]),
+            ["exceeded", "cleared", "exceeded", "cleared"]
+        );
+    }
+
+    #[test]
+    fn condition_met_before_the_first_change_is_not_reported_again() {
+        let rule = rule(1.0);
+
+        assert_eq!(crossings(&rule, &[6.0, 7.0, 4.5, 3.0]), ["none", "none", "cleared"]);
+    }
+}
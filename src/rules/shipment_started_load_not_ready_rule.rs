@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
-use crate::analysis::context_analyzer::{AnalysisRule, AnalysisResult, AlertType};
+use async_trait::async_trait;
+use crate::analysis::context_analyzer::{AnalysisRule, RuleContext, AnalysisResult, AlertType};
 use crate::models::{DockDoor, DockDoorEvent, DockLockState, DoorPosition, LevelerPosition};
 
 /// Configuration for the ShipmentStartedLoadNotReadyRule
@@ -28,11 +29,10 @@ impl ShipmentStartedLoadNotReadyRule {
     ///
     /// # Returns
     ///
-    /// A new instance of ShipmentStartedLoadNotReadyRule
-    pub fn new(config: serde_json::Value) -> Self {
-        let parsed_config: ShipmentStartedLoadNotReadyRuleConfig = serde_json::from_value(config)
-            .expect("Failed to parse ShipmentStartedLoadNotReadyRule configuration");
-        Self { config: parsed_config }
+    /// A new instance of ShipmentStartedLoadNotReadyRule, or the error if the configuration could not be parsed
+    pub fn new(config: serde_json::Value) -> serde_json::Result<Self> {
+        let parsed_config: ShipmentStartedLoadNotReadyRuleConfig = serde_json::from_value(config)?;
+        Ok(Self { config: parsed_config })
     }
 
     /// Checks if the dock is ready for loading based on the rule configuration
@@ -63,6 +63,7 @@ impl ShipmentStartedLoadNotReadyRule {
     }
 }
 
+#[async_trait]
 impl AnalysisRule for ShipmentStartedLoadNotReadyRule {
     /// Applies the ShipmentStartedLoadNotReadyRule to the given dock door and event
     ///
@@ -74,7 +75,7 @@ impl AnalysisRule for ShipmentStartedLoadNotReadyRule {
     /// # Returns
     ///
     /// A vector of AnalysisResult, which may contain alerts if the rule conditions are met
-    fn apply(&self, dock_door: &DockDoor, event: &DockDoorEvent) -> Vec<AnalysisResult> {
+    async fn apply(&self, dock_door: &DockDoor, event: &DockDoorEvent, _context: &RuleContext) -> Vec<AnalysisResult> {
         let mut results = Vec::new();
 
         match event {
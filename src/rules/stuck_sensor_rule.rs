@@ -0,0 +1,189 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use chrono::{Local, NaiveDateTime};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use async_trait::async_trait;
+use crate::analysis::context_analyzer::{AnalysisRule, RuleContext, AnalysisResult, AlertType, LogEntry};
+use crate::models::{DockDoor, DockDoorEvent, TrailerState};
+
+/// The door activity that ends a cycle in which the monitored sensors are expected to change
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CycleEvent {
+    /// A trailer arrived at the door
+    TrailerArrival,
+    /// A trailer left the door
+    TrailerDeparture,
+    /// The door's loading status changed in the WMS
+    LoadingStatusChange,
+}
+
+/// Configuration for the StuckSensorRule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StuckSensorRuleConfig {
+    /// The sensors expected to change during every cycle
+    #[serde(default = "default_sensors")]
+    pub sensors: Vec<String>,
+    /// The number of cycles in a row without a change after which a sensor is reported as stuck (defaults to 3)
+    #[serde(default = "default_max_idle_cycles")]
+    pub max_idle_cycles: u32,
+    /// The door activity that ends a cycle (defaults to trailer departures)
+    #[serde(default = "default_cycle_events")]
+    pub cycle_events: Vec<CycleEvent>,
+}
+
+fn default_sensors() -> Vec<String> {
+    vec!["RH_DOOR_OPEN".to_string(), "RH_RESTRAINT_ENGAGED".to_string(), "RH_LEVELR_READY".to_string()]
+}
+
+fn default_max_idle_cycles() -> u32 {
+    3
+}
+
+fn default_cycle_events() -> Vec<CycleEvent> {
+    vec![CycleEvent::TrailerDeparture]
+}
+
+/// The cycle tracking of a single door
+#[derive(Debug)]
+struct DoorCycles {
+    /// The time the current cycle started
+    cycle_started: NaiveDateTime,
+    /// The number of cycles in a row each sensor has not changed in
+    idle_cycles: HashMap<String, u32>,
+    /// The sensors currently reported as stuck
+    stuck: HashSet<String>,
+}
+
+/// Rule for detecting sensors that stay frozen while the door around them is in use
+///
+/// Every configured cycle event ends a cycle of the door. A monitored sensor whose `last_updated` time is older
+/// than the start of the cycle did not change during it; once that has happened in `max_idle_cycles` cycles in a
+/// row, a maintenance alert naming the sensor and door is raised. The sensor's next change logs its recovery.
+pub struct StuckSensorRule {
+    /// The parsed configuration for this rule
+    config: StuckSensorRuleConfig,
+    /// The cycle tracking of each door, keyed by plant and door name
+    doors: Mutex<HashMap<(String, String), DoorCycles>>,
+}
+
+impl StuckSensorRule {
+    /// Creates a new StuckSensorRule with the given configuration
+    pub fn new(config: StuckSensorRuleConfig) -> Self {
+        Self { config, doors: Mutex::new(HashMap::new()) }
+    }
+
+    /// Returns the cycle event matching a door event, if any
+    fn cycle_event(event: &DockDoorEvent) -> Option<CycleEvent> {
+        match event {
+            DockDoorEvent::TrailerStateChanged(e) if e.new_state == TrailerState::Docked => Some(CycleEvent::TrailerArrival),
+            DockDoorEvent::TrailerStateChanged(e) if e.new_state == TrailerState::Undocked => Some(CycleEvent::TrailerDeparture),
+            DockDoorEvent::LoadingStatusChanged(_) => Some(CycleEvent::LoadingStatusChange),
+            _ => None,
+        }
+    }
+
+    /// Ends the current cycle of a door, generating an alert and log entry for every sensor that became stuck
+    fn end_cycle(&self, door: &DockDoor, timestamp: NaiveDateTime) -> Vec<AnalysisResult> {
+        let mut doors = self.doors.lock().unwrap();
+        let key = (door.plant_id.clone(), door.dock_name.clone());
+        let Some(cycles) = doors.get_mut(&key) else {
+            // The first cycle event only marks the start of the first cycle
+            doors.insert(key, DoorCycles { cycle_started: timestamp, idle_cycles: HashMap::new(), stuck: HashSet::new() });
+            return Vec::new();
+        };
+
+        let mut results = Vec::new();
+        for sensor_name in &self.config.sensors {
+            let Some(sensor) = door.sensors.get(sensor_name).map(|sensor| sensor.get_sensor_data()) else {
+                continue;
+            };
+            let idle = cycles.idle_cycles.entry(sensor_name.clone()).or_insert(0);
+            if sensor.last_updated >= cycles.cycle_started {
+                *idle = 0;
+                continue;
+            }
+
+            *idle += 1;
+            if *idle < self.config.max_idle_cycles.max(1) || !cycles.stuck.insert(sensor_name.clone()) {
+                continue;
+            }
+
+            let value = sensor.current_value.map(|value| value.to_string());
+            warn!(
+                "Sensor {} on door {} has not changed in {} cycles (value {})",
+                sensor_name, door.dock_name, idle, value.as_deref().unwrap_or("unknown")
+            );
+            results.push(AnalysisResult::Alert(AlertType::StuckSensor {
+                door_name: door.dock_name.clone(),
+                shipment_id: door.assigned_shipment.current_shipment.clone(),
+                sensor: sensor_name.clone(),
+                value: value.clone(),
+                idle_cycles: *idle,
+                last_change: sensor.last_updated,
+            }));
+            results.push(AnalysisResult::Log(LogEntry::StuckSensor {
+                log_dttm: Local::now().naive_local(),
+                plant: door.plant_id.clone(),
+                door_name: door.dock_name.clone(),
+                shipment_id: door.assigned_shipment.current_shipment.clone(),
+                event_type: "SENSOR_STUCK".to_string(),
+                success: false,
+                notes: format!(
+                    "{} has not changed in {} cycles, stuck at {}",
+                    sensor_name, idle, value.as_deref().unwrap_or("unknown")
+                ),
+                severity: 2,
+                previous_state: value,
+                previous_state_dttm: Some(sensor.last_updated),
+            }));
+        }
+
+        cycles.cycle_started = timestamp;
+        results
+    }
+
+    /// Clears the stuck state of a sensor that changed, logging its recovery
+    fn sensor_changed(&self, door: &DockDoor, sensor_name: &str, timestamp: NaiveDateTime) -> Vec<AnalysisResult> {
+        let mut doors = self.doors.lock().unwrap();
+        let Some(cycles) = doors.get_mut(&(door.plant_id.clone(), door.dock_name.clone())) else {
+            return Vec::new();
+        };
+        let idle_cycles = cycles.idle_cycles.insert(sensor_name.to_string(), 0).unwrap_or(0);
+        if !cycles.stuck.remove(sensor_name) {
+            return Vec::new();
+        }
+
+        info!("Sensor {} on door {} is changing again", sensor_name, door.dock_name);
+        vec![AnalysisResult::Log(LogEntry::StuckSensor {
+            log_dttm: Local::now().naive_local(),
+            plant: door.plant_id.clone(),
+            door_name: door.dock_name.clone(),
+            shipment_id: door.assigned_shipment.current_shipment.clone(),
+            event_type: "SENSOR_RECOVERED".to_string(),
+            success: true,
+            notes: format!("{} changed again after {} idle cycles", sensor_name, idle_cycles),
+            severity: 0,
+            previous_state: Some("SENSOR_STUCK".to_string()),
+            previous_state_dttm: Some(timestamp),
+        })]
+    }
+}
+
+#[async_trait]
+impl AnalysisRule for StuckSensorRule {
+    async fn apply(&self, door: &DockDoor, event: &DockDoorEvent, _context: &RuleContext) -> Vec<AnalysisResult> {
+        match event {
+            DockDoorEvent::SensorStateChanged(e) if self.config.sensors.contains(&e.sensor_name) => {
+                self.sensor_changed(door, &e.sensor_name, e.timestamp)
+            },
+            _ => match Self::cycle_event(event) {
+                Some(cycle_event) if self.config.cycle_events.contains(&cycle_event) => {
+                    self.end_cycle(door, Local::now().naive_local())
+                },
+                _ => Vec::new(),
+            },
+        }
+    }
+}
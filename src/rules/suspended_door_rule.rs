@@ -2,7 +2,8 @@
     use std::sync::Mutex;
     use chrono::{NaiveDateTime, Local, Duration};
     use serde::{Deserialize, Serialize};
-    use crate::analysis::context_analyzer::{AnalysisRule, AnalysisResult, AlertType, LogEntry};
+    use async_trait::async_trait;
+    use crate::analysis::context_analyzer::{AnalysisRule, RuleContext, AnalysisResult, AlertType, LogEntry};
     use crate::models::{DockDoor, DockDoorEvent, LoadingStatus};
     use log::{debug, info};
 
@@ -30,14 +31,13 @@
         ///
         /// # Returns
         ///
-        /// A new instance of SuspendedDoorRule
-        pub fn new(config: serde_json::Value) -> Self {
-            let parsed_config: SuspendedDoorRuleConfig = serde_json::from_value(config)
-                .expect("Failed to parse SuspendedDoorRule configuration");
-            Self {
+        /// A new instance of SuspendedDoorRule, or the error if the configuration could not be parsed
+        pub fn new(config: serde_json::Value) -> serde_json::Result<Self> {
+            let parsed_config: SuspendedDoorRuleConfig = serde_json::from_value(config)?;
+            Ok(Self {
                 config: parsed_config,
                 last_alert_time: Mutex::new(HashMap::new()),
-            }
+            })
         }
 
         /// Determines if an alert should be sent based on the last alert time
@@ -127,6 +127,7 @@
         }
     }
 
+    #[async_trait]
     impl AnalysisRule for SuspendedDoorRule {
         /// Applies the rule to a dock door event, generating appropriate analysis results
         ///
@@ -138,7 +139,7 @@
         /// # Returns
         ///
         /// A vector of AnalysisResult items generated by applying the rule
-        fn apply(&self, dock_door: &DockDoor, event: &DockDoorEvent) -> Vec<AnalysisResult> {
+        async fn apply(&self, dock_door: &DockDoor, event: &DockDoorEvent, _context: &RuleContext) -> Vec<AnalysisResult> {
             info!("SuspendedDoorRule applying to event: {:?}", event);
             match event {
                 DockDoorEvent::ShipmentSuspended(e) => {
@@ -1,43 +1,80 @@
-use tokio::sync::Mutex;
-use crate::analysis::context_analyzer::{AnalysisRule, AnalysisResult};
+use std::sync::{Arc, OnceLock};
+use async_trait::async_trait;
+use tokio::sync::mpsc;
+use crate::analysis::context_analyzer::{AnalysisRule, RuleContext, AnalysisResult};
 use crate::models::{DockDoor, DockDoorEvent};
 use crate::services::db::DatabaseService;
+use crate::utils::shutdown::ShutdownSignal;
 use serde::{Deserialize, Serialize};
-use crate::config::Settings;
-use once_cell::sync::OnceCell;
 use anyhow::Result;
 
-static DB_SERVICE: OnceCell<Mutex<DatabaseService>> = OnceCell::new();
+/// The number of updates waiting to be written before the rule waits for the writer
+const UPDATE_QUEUE_CAPACITY: usize = 1000;
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct TrailerAtDoorUpdateRuleConfig {
     // Add any configuration parameters here if needed
 }
 
+/// A change of the trailer presence of a door, waiting to be written
+struct TrailerAtDoorUpdate {
+    door_name: String,
+    trailer_at_door: u8,
+}
+
+/// Rule mirroring the trailer presence of every door into `DOCK_DOOR_PLCS`
+///
+/// The updates are written one at a time, in the order of the events, by a single writer task started on the first
+/// update and registered with the context's shutdown coordinator, so a door's row always ends up with its latest
+/// value and the queued updates are written before the service stops.
 pub struct TrailerAtDoorUpdateRule {
-    config: TrailerAtDoorUpdateRuleConfig,
+    /// The queue of the writer task, once started
+    updates: OnceLock<mpsc::Sender<TrailerAtDoorUpdate>>,
 }
 
 impl TrailerAtDoorUpdateRule {
-    pub fn new(config: TrailerAtDoorUpdateRuleConfig) -> Self {
-        Self { config }
+    pub fn new(_config: TrailerAtDoorUpdateRuleConfig) -> Self {
+        Self { updates: OnceLock::new() }
     }
 
-    async fn get_db_service() -> &'static Mutex<DatabaseService> {
-        DB_SERVICE.get_or_init(|| {
-            let settings = Settings::new().expect("Failed to load settings");
-            let db_service = tokio::task::block_in_place(|| {
-                tokio::runtime::Runtime::new()
-                    .expect("Failed to create runtime")
-                    .block_on(async {
-                        DatabaseService::new(settings).await.expect("Failed to create DatabaseService")
-                    })
-            });
-            Mutex::new(db_service)
+    /// Returns the queue of the writer task, starting the task on first use
+    fn updates(&self, context: &RuleContext) -> &mpsc::Sender<TrailerAtDoorUpdate> {
+        self.updates.get_or_init(|| {
+            let (sender, receiver) = mpsc::channel(UPDATE_QUEUE_CAPACITY);
+            let db_service = Arc::clone(&context.db_service);
+            let drain = context.shutdown.drain_signal();
+            context.shutdown.spawn_drain("TRAILER_AT_DOOR writer", Self::write_updates(db_service, receiver, drain));
+            sender
         })
     }
 
-    async fn update_trailer_at_door(door_name: &str, trailer_at_door: u8) -> Result<()> {
+    /// Writes the queued updates in order until the rule is dropped, or until the drain signal is triggered,
+    /// after which the updates already queued are written
+    async fn write_updates(db_service: Arc<DatabaseService>, mut receiver: mpsc::Receiver<TrailerAtDoorUpdate>, mut drain: ShutdownSignal) {
+        loop {
+            let update = tokio::select! {
+                update = receiver.recv() => match update {
+                    Some(update) => update,
+                    None => return,
+                },
+                _ = drain.wait() => break,
+            };
+            Self::write_update(&db_service, update).await;
+        }
+
+        receiver.close();
+        while let Some(update) = receiver.recv().await {
+            Self::write_update(&db_service, update).await;
+        }
+    }
+
+    async fn write_update(db_service: &DatabaseService, update: TrailerAtDoorUpdate) {
+        if let Err(err) = Self::update_trailer_at_door(db_service, &update.door_name, update.trailer_at_door).await {
+            log::error!("Failed to update TRAILER_AT_DOOR for door {}: {:?}", update.door_name, err);
+        }
+    }
+
+    async fn update_trailer_at_door(db_service: &DatabaseService, door_name: &str, trailer_at_door: u8) -> Result<()> {
         let query = r#"
         UPDATE [NETWORK].[RCH].[DOCK_DOOR_PLCS]
         SET TRAILER_AT_DOOR = @P1,
@@ -45,9 +82,6 @@ impl TrailerAtDoorUpdateRule {
         WHERE DOOR_NAME = @P2
     "#;
 
-        let db_service = Self::get_db_service().await;
-        let db_service = db_service.lock().await;
-
         sqlx_oldapi::query(query)
             .bind(trailer_at_door as i32)
             .bind(door_name)
@@ -58,23 +92,22 @@ impl TrailerAtDoorUpdateRule {
     }
 }
 
+#[async_trait]
 impl AnalysisRule for TrailerAtDoorUpdateRule {
-    fn apply(&self, _door: &DockDoor, event: &DockDoorEvent) -> Vec<AnalysisResult> {
-        let _ = self.config.clone();
+    async fn apply(&self, _door: &DockDoor, event: &DockDoorEvent, context: &RuleContext) -> Vec<AnalysisResult> {
         match event {
             DockDoorEvent::SensorStateChanged(e) if e.sensor_name == "TRAILER_AT_DOOR" => {
-                let trailer_at_door = e.new_value.unwrap_or(0);
-                let door_name = e.dock_name.clone();
-
-                tokio::spawn(async move {
-                    if let Err(err) = Self::update_trailer_at_door(&door_name, trailer_at_door).await {
-                        log::error!("Failed to update TRAILER_AT_DOOR for door {}: {:?}", door_name, err);
-                    }
-                });
+                let update = TrailerAtDoorUpdate {
+                    door_name: e.dock_name.clone(),
+                    trailer_at_door: u8::from(e.is_on()),
+                };
+                if self.updates(context).send(update).await.is_err() {
+                    log::error!("Dropped TRAILER_AT_DOOR update for door {}, its writer has stopped", e.dock_name);
+                }
 
                 vec![]
             },
             _ => vec![],
         }
     }
-}
\ No newline at end of file
+}
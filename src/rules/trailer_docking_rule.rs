@@ -5,12 +5,13 @@
 //! It provides detailed feedback on why a docking operation might fail, which is
 //! crucial for maintenance and troubleshooting.
 
-use std::thread;
+use std::sync::Arc;
 use std::time::Duration;
-use crate::models::{DockDoor, DockDoorEvent, TrailerState};
-use crate::analysis::context_analyzer::{AnalysisRule, AnalysisResult, LogEntry, AlertType};
+use crate::models::{DockDoor, DockDoorEvent, TrailerState, TrailerStateChangedEvent};
+use async_trait::async_trait;
+use crate::analysis::context_analyzer::{AnalysisRule, RuleContext, AnalysisResult, LogEntry, AlertType, DeferredResults};
 use chrono::Local;
-use log::{info, debug};
+use log::{info, debug, error};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
@@ -23,6 +24,13 @@ pub struct TrailerDockingRuleConfig {
     pub invalid_wms_shipment_status: String,
     /// Sensors to monitor during the docking process
     pub sensors_to_monitor: Vec<SensorConfig>,
+    /// The number of seconds to let the door's sensors settle after docking before checking them (defaults to 5)
+    #[serde(default = "default_settle_secs")]
+    pub settle_secs: u64,
+}
+
+fn default_settle_secs() -> u64 {
+    5
 }
 
 /// Configuration for a sensor to monitor
@@ -35,9 +43,15 @@ pub struct SensorConfig {
 }
 
 /// Rule for analyzing trailer docking events
+///
+/// The docking is checked once the door's sensors had `settle_secs` to settle. The wait runs in a task registered
+/// with the context's shutdown coordinator, so the events of the other doors are not held up, and its results are
+/// sent back through the context's `deferred_results`. Once shutdown has been triggered the wait is cut short, and
+/// a docking seen during shutdown is checked right away.
+#[derive(Clone)]
 pub struct TrailerDockingRule {
     /// The configuration for this rule
-    config: TrailerDockingRuleConfig,
+    config: Arc<TrailerDockingRuleConfig>,
 }
 
 impl TrailerDockingRule {
@@ -49,11 +63,84 @@ impl TrailerDockingRule {
     ///
     /// # Returns
     ///
-    /// A new instance of TrailerDockingRule
-    pub fn new(config: Value) -> Self {
-        let parsed_config: TrailerDockingRuleConfig = serde_json::from_value(config)
-            .expect("Failed to parse TrailerDockingRule configuration");
-        TrailerDockingRule { config: parsed_config }
+    /// A new instance of TrailerDockingRule, or the error if the configuration could not be parsed
+    pub fn new(config: Value) -> serde_json::Result<Self> {
+        let parsed_config: TrailerDockingRuleConfig = serde_json::from_value(config)?;
+        Ok(TrailerDockingRule { config: Arc::new(parsed_config) })
+    }
+
+    /// Waits for the door's sensors to settle after docking and returns the door's latest state
+    ///
+    /// The wait ends early once shutdown has been triggered.
+    ///
+    /// # Arguments
+    ///
+    /// * `dock_door` - The DockDoor the trailer docked at
+    /// * `context` - The RuleContext to wait on and read the door from
+    ///
+    /// # Returns
+    ///
+    /// The latest state of the door, or the given one if the door cannot be read
+    async fn settled_door(&self, dock_door: &DockDoor, context: &RuleContext) -> DockDoor {
+        let mut signal = context.shutdown.signal();
+        tokio::select! {
+            _ = context.clock.sleep(Duration::from_secs(self.config.settle_secs)) => {},
+            _ = signal.wait() => debug!("Shutting down, checking door {} before it has settled", dock_door.dock_name),
+        }
+        context.door_repository.get_door_state(&dock_door.plant_id, &dock_door.dock_name).await
+            .unwrap_or_else(|| dock_door.clone())
+    }
+
+    /// Creates the alert and log entry of a docking, checked against the door's state once it has settled
+    ///
+    /// # Arguments
+    ///
+    /// * `dock_door` - The settled state of the DockDoor the trailer docked at
+    /// * `event` - The TrailerStateChangedEvent of the docking
+    ///
+    /// # Returns
+    ///
+    /// The TrailerDocked alert if the docking is successful, and the docking log entry
+    fn docking_results(&self, dock_door: &DockDoor, event: &TrailerStateChangedEvent) -> Vec<AnalysisResult> {
+        let mut results = Vec::new();
+        let is_successful = self.is_docking_successful(dock_door);
+        info!("Docking success: {}", is_successful);
+        let failure_reason = if !is_successful {
+            Some(self.get_failure_reason(dock_door))
+        } else {
+            None
+        };
+
+        if is_successful {
+            results.push(AnalysisResult::Alert(AlertType::TrailerDocked {
+                door_name: dock_door.dock_name.clone(),
+                shipment_id: dock_door.assigned_shipment.current_shipment.clone(),
+                timestamp: event.timestamp,
+                success: is_successful,
+                failure_reason: failure_reason.clone(),
+            }));
+        }
+
+        let log_entry = LogEntry::DockingTime {
+            log_dttm: Local::now().naive_local(),
+            plant: dock_door.plant_id.clone(),
+            door_name: dock_door.dock_name.clone(),
+            shipment_id: dock_door.assigned_shipment.current_shipment.clone(),
+            event_type: "TRAILER_DOCKING".to_string(),
+            success: is_successful,
+            notes: if is_successful {
+                "Trailer docked successfully".to_string()
+            } else {
+                format!("Trailer docking failed: {}", failure_reason.unwrap_or_else(|| "Unknown reason".to_string()))
+            },
+            severity: if is_successful { 0 } else { 2 },
+            previous_state: Some(format!("{:?}", event.old_state)),
+            previous_state_dttm: Some(event.timestamp),
+        };
+
+        info!("TrailerDockingRule: Generated docking log entry: {:?}", log_entry);
+        results.push(AnalysisResult::Log(log_entry));
+        results
     }
 
     /// Checks if the docking is successful based on loading status, WMS shipment status, and sensor values
@@ -66,7 +153,6 @@ impl TrailerDockingRule {
     ///
     /// A boolean indicating whether the docking is successful
     fn is_docking_successful(&self, dock_door: &DockDoor) -> bool {
-        thread::sleep(Duration::from_secs(5));
         let loading_status_condition = self.check_loading_status(dock_door);
         let wms_status_condition = self.check_wms_status(dock_door);
         let shipment_condition = dock_door.assigned_shipment.current_shipment.is_some();
@@ -124,7 +210,7 @@ impl TrailerDockingRule {
         let sensor_states: Vec<(String, bool)> = self.config.sensors_to_monitor.iter().map(|sensor| {
             let sensor_state = dock_door.sensors.get(&sensor.name)
                 .and_then(|s| s.get_sensor_data().current_value)
-                .map(|value| value == sensor.success_value)
+                .map(|value| value.as_f64() == f64::from(sensor.success_value))
                 .unwrap_or(false);
             (sensor.name.clone(), sensor_state)
         }).collect();
@@ -185,24 +271,25 @@ impl TrailerDockingRule {
     }
 }
 
+#[async_trait]
 impl AnalysisRule for TrailerDockingRule {
     /// Applies the rule to a dock door event, generating appropriate analysis results
     ///
-    /// This method analyzes the given event and generates relevant alerts and log entries
-    /// based on the trailer docking process. It skips alert generation during the initial update
-    /// to prevent false alerts during system initialization.
+    /// This method analyzes the given event and, once the door has settled, sends the relevant alerts and log
+    /// entries of the trailer docking process through the context's `deferred_results`. It skips alert generation
+    /// during the initial update to prevent false alerts during system initialization.
     ///
     /// # Arguments
     ///
     /// * `dock_door` - The DockDoor associated with the event
     /// * `event` - The DockDoorEvent to analyze
+    /// * `context` - The RuleContext to wait on, read the door from and send the results through
     ///
     /// # Returns
     ///
-    /// A vector of AnalysisResult items generated by applying the rule
-    fn apply(&self, dock_door: &DockDoor, event: &DockDoorEvent) -> Vec<AnalysisResult> {
+    /// No results, as they are sent once the door has settled, unless shutdown has been triggered
+    async fn apply(&self, dock_door: &DockDoor, event: &DockDoorEvent, context: &RuleContext) -> Vec<AnalysisResult> {
         info!("TrailerDockingRule applying to event: {:?}", event);
-        let mut results = Vec::new();
         match event {
             DockDoorEvent::TrailerStateChanged(e) => {
                 if e.new_state == TrailerState::Docked && e.old_state == TrailerState::Undocked {
@@ -210,51 +297,34 @@ impl AnalysisRule for TrailerDockingRule {
                     // Skip alert generation if this is the first update
                     if self.is_first_update(dock_door) {
                         info!("Skipping alert generation for initial update on door: {}", dock_door.dock_name);
-                        return results;
+                        return Vec::new();
                     }
 
-                    let is_successful = self.is_docking_successful(dock_door);
-                    info!("Docking success: {}", is_successful);
-                    let failure_reason = if !is_successful {
-                        Some(self.get_failure_reason(dock_door))
-                    } else {
-                        None
-                    };
-					
-					if is_successful {
-						results.push(AnalysisResult::Alert(AlertType::TrailerDocked {
-							door_name: dock_door.dock_name.clone(),
-							shipment_id: dock_door.assigned_shipment.current_shipment.clone(),
-							timestamp: e.timestamp,
-							success: is_successful,
-							failure_reason: failure_reason.clone(),
-						}));
-					}
-					
-                    let log_entry = LogEntry::DockingTime {
-                        log_dttm: Local::now().naive_local(),
-                        plant: dock_door.plant_id.clone(),
-                        door_name: dock_door.dock_name.clone(),
-                        shipment_id: dock_door.assigned_shipment.current_shipment.clone(),
-                        event_type: "TRAILER_DOCKING".to_string(),
-                        success: is_successful,
-                        notes: if is_successful {
-                            "Trailer docked successfully".to_string()
-                        } else {
-                            format!("Trailer docking failed: {}", failure_reason.unwrap_or_else(|| "Unknown reason".to_string()))
-                        },
-                        severity: if is_successful { 0 } else { 2 },
-                        previous_state: Some(format!("{:?}", e.old_state)),
-                        previous_state_dttm: Some(e.timestamp),
-                    };
+                    if context.shutdown.is_triggered() {
+                        let settled_door = self.settled_door(dock_door, context).await;
+                        return self.docking_results(&settled_door, e);
+                    }
 
-                    info!("TrailerDockingRule: Generated docking log entry: {:?}", log_entry);
-                    results.push(AnalysisResult::Log(log_entry));
+                    let rule = self.clone();
+                    let context = context.clone();
+                    let shutdown = Arc::clone(&context.shutdown);
+                    let dock_door = dock_door.clone();
+                    let event = e.clone();
+                    shutdown.spawn(&format!("Docking check for door {}", dock_door.dock_name), async move {
+                        let settled_door = rule.settled_door(&dock_door, &context).await;
+                        let deferred = DeferredResults {
+                            plant_id: settled_door.plant_id.clone(),
+                            dock_name: settled_door.dock_name.clone(),
+                            results: rule.docking_results(&settled_door, &event),
+                        };
+                        if context.deferred_results.send(deferred).await.is_err() {
+                            error!("Docking check for door {} was dropped, the results are no longer handled", dock_door.dock_name);
+                        }
+                    });
                 }
             },
             _ => {},
         }
-        info!("TrailerDockingRule results: {:?}", results);
-        results
+        Vec::new()
     }
 }
\ No newline at end of file
@@ -2,7 +2,8 @@ use std::sync::Arc;
 use chrono::{NaiveDateTime, Local, Duration};
 use dashmap::DashMap;
 use serde::{Deserialize, Serialize};
-use crate::analysis::context_analyzer::{AnalysisRule, AnalysisResult, AlertType, LogEntry};
+use async_trait::async_trait;
+use crate::analysis::context_analyzer::{AnalysisRule, RuleContext, AnalysisResult, AlertType, LogEntry};
 use crate::models::{DockDoor, DockDoorEvent, LoadingStatus, TrailerState, ManualMode};
 use log::{debug, info};
 
@@ -32,14 +33,13 @@ impl TrailerHostageRule {
     ///
     /// # Returns
     ///
-    /// A new instance of TrailerHostageRule
-    pub fn new(config: serde_json::Value) -> Self {
-        let parsed_config: TrailerHostageRuleConfig = serde_json::from_value(config)
-            .expect("Failed to parse TrailerHostageRule configuration");
-        Self {
+    /// A new instance of TrailerHostageRule, or the error if the configuration could not be parsed
+    pub fn new(config: serde_json::Value) -> serde_json::Result<Self> {
+        let parsed_config: TrailerHostageRuleConfig = serde_json::from_value(config)?;
+        Ok(Self {
             config: parsed_config,
             last_alert_time: Arc::new(DashMap::new()),
-        }
+        })
     }
 
     /// Checks if an alert should be sent based on the last alert time and repeat interval
@@ -115,6 +115,7 @@ impl TrailerHostageRule {
     }
 }
 
+#[async_trait]
 impl AnalysisRule for TrailerHostageRule {
     /// Applies the rule to a dock door event, generating appropriate analysis results
     ///
@@ -130,7 +131,7 @@ impl AnalysisRule for TrailerHostageRule {
     /// # Returns
     ///
     /// A vector of AnalysisResult items generated by applying the rule
-    fn apply(&self, dock_door: &DockDoor, event: &DockDoorEvent) -> Vec<AnalysisResult> {
+    async fn apply(&self, dock_door: &DockDoor, event: &DockDoorEvent, _context: &RuleContext) -> Vec<AnalysisResult> {
         match event {
             DockDoorEvent::LoadingStatusChanged(e) => {
                 if (e.new_status == LoadingStatus::Completed || e.new_status == LoadingStatus::WaitingForExit)
@@ -147,7 +148,7 @@ impl AnalysisRule for TrailerHostageRule {
                     Vec::new()
                 }
             },
-            DockDoorEvent::SensorStateChanged(e) if e.sensor_name == "RH_MANUAL_MODE" && e.new_value == Some(1) => {
+            DockDoorEvent::SensorStateChanged(e) if e.sensor_name == "RH_MANUAL_MODE" && e.is_on() => {
                 if self.is_hostage_situation(dock_door) {
                     let duration = dock_door.trailer_state_changed
                         .map(|t| Local::now().naive_local().signed_duration_since(t))
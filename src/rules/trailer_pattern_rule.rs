@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
-use crate::analysis::context_analyzer::{AnalysisRule, AnalysisResult, AlertType, LogEntry};
+use async_trait::async_trait;
+use crate::analysis::context_analyzer::{AnalysisRule, RuleContext, AnalysisResult, AlertType, LogEntry};
 use crate::models::{DockDoor, DockDoorEvent};
 use chrono::Local;
 
@@ -13,10 +14,9 @@ pub struct TrailerPatternRule {
 }
 
 impl TrailerPatternRule {
-    pub fn new(config: serde_json::Value) -> Self {
-        let parsed_config: TrailerPatternRuleConfig = serde_json::from_value(config)
-            .expect("Failed to parse TrailerPatternRule configuration");
-        Self { config: parsed_config }
+    pub fn new(config: serde_json::Value) -> serde_json::Result<Self> {
+        let parsed_config: TrailerPatternRuleConfig = serde_json::from_value(config)?;
+        Ok(Self { config: parsed_config })
     }
 
     fn parse_trl_ptn_value(&self, message_notes: &str) -> Option<i32> {
@@ -24,8 +24,9 @@ impl TrailerPatternRule {
     }
 }
 
+#[async_trait]
 impl AnalysisRule for TrailerPatternRule {
-    fn apply(&self, dock_door: &DockDoor, event: &DockDoorEvent) -> Vec<AnalysisResult> {
+    async fn apply(&self, dock_door: &DockDoor, event: &DockDoorEvent, _context: &RuleContext) -> Vec<AnalysisResult> {
         let mut results = Vec::new();
         match event {
             DockDoorEvent::WmsEvent(e) if e.event_type == "TRK_PTRN" => {
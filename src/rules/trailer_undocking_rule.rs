@@ -1,5 +1,6 @@
 use crate::models::{DockDoor, DockDoorEvent};
-use crate::analysis::context_analyzer::{AnalysisRule, AnalysisResult, LogEntry};
+use async_trait::async_trait;
+use crate::analysis::context_analyzer::{AnalysisRule, RuleContext, AnalysisResult, LogEntry};
 use chrono::{Local};
 use log::debug;
 use serde::{Deserialize, Serialize};
@@ -14,10 +15,9 @@ pub struct TrailerUndockingRule {
 }
 
 impl TrailerUndockingRule {
-    pub fn new(config: serde_json::Value) -> Self {
-        let parsed_config: TrailerUndockingRuleConfig = serde_json::from_value(config)
-            .expect("Failed to parse TrailerUndockingRule configuration");
-        Self { config: parsed_config }
+    pub fn new(config: serde_json::Value) -> serde_json::Result<Self> {
+        let parsed_config: TrailerUndockingRuleConfig = serde_json::from_value(config)?;
+        Ok(Self { config: parsed_config })
     }
 
     fn generate_undocking_results(&self, door: &DockDoor, timestamp: chrono::NaiveDateTime, previous_state: &str) -> Vec<AnalysisResult> {
@@ -44,11 +44,12 @@ impl TrailerUndockingRule {
     }
 }
 
+#[async_trait]
 impl AnalysisRule for TrailerUndockingRule {
-    fn apply(&self, door: &DockDoor, event: &DockDoorEvent) -> Vec<AnalysisResult> {
+    async fn apply(&self, door: &DockDoor, event: &DockDoorEvent, _context: &RuleContext) -> Vec<AnalysisResult> {
         match event {
             DockDoorEvent::SensorStateChanged(e) => {
-                if e.sensor_name == "TRAILER_AT_DOOR" && e.new_value == Some(0) {
+                if e.sensor_name == "TRAILER_AT_DOOR" && e.is_off() {
                     self.generate_undocking_results(door, e.timestamp, "TRAILER_DOCKING")
                 } else {
                     Vec::new()
@@ -1,11 +1,13 @@
 use log::info;
-use crate::analysis::context_analyzer::{AnalysisRule, AnalysisResult};
+use async_trait::async_trait;
+use crate::analysis::context_analyzer::{AnalysisRule, RuleContext, AnalysisResult};
 use crate::models::{DockDoor, DockDoorEvent, WmsEventWrapper, DbInsert};
 
 pub struct WmsEventsRule;
 
+#[async_trait]
 impl AnalysisRule for WmsEventsRule {
-    fn apply(&self, door: &DockDoor, event: &DockDoorEvent) -> Vec<AnalysisResult> {
+    async fn apply(&self, door: &DockDoor, event: &DockDoorEvent, _context: &RuleContext) -> Vec<AnalysisResult> {
         info!("WmsEventsRule for: {:?}", event);
         match event {
             DockDoorEvent::WmsEvent(e) => vec![create_wms_db_insert(door, e)],
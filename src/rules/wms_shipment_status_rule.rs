@@ -1,9 +1,11 @@
 use crate::models::{DockDoor, DockDoorEvent};
-use crate::analysis::context_analyzer::{AnalysisRule, AnalysisResult, LogEntry};
+use async_trait::async_trait;
+use crate::analysis::context_analyzer::{AnalysisRule, RuleContext, AnalysisResult, LogEntry};
 
 /// An analysis rule that handles events related to WMS shipment status and loading status changes
 pub struct WmsShipmentStatus;
 
+#[async_trait]
 impl AnalysisRule for WmsShipmentStatus {
     /// Applies the rule to a dock door event, generating log entries for shipment assignment, unassignment, and loading status changes
     ///
@@ -21,7 +23,7 @@ impl AnalysisRule for WmsShipmentStatus {
     /// # Returns
     ///
     /// A vector containing a `LogEntry` wrapped in an `AnalysisResult` if the event is relevant, otherwise an empty vector
-    fn apply(&self, door: &DockDoor, event: &DockDoorEvent) -> Vec<AnalysisResult> {
+    async fn apply(&self, door: &DockDoor, event: &DockDoorEvent, _context: &RuleContext) -> Vec<AnalysisResult> {
         match event {
             DockDoorEvent::ShipmentAssigned(e) => {
                 vec![
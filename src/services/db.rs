@@ -6,14 +6,16 @@
 
 use std::collections::HashMap;
 use std::sync::Arc;
+use chrono::NaiveDateTime;
 use secrecy::ExposeSecret;
 use tokio::sync::Mutex;
 use crate::config::Settings;
 use crate::errors::{DockManagerError, DockManagerResult};
-use crate::models::{DbInsert, TrailerPatternData, WmsDoorStatus, WmsEvent};
+use crate::models::{DbInsert, TrailerPatternData, TrailerVisit, WmsDoorStatus, WmsEvent};
 use crate::models::consolidated_dock_event::ConsolidatedDockEvent;
-use crate::repositories::{DoorEventRepository, WmsStatusRepository, Repository};
+use crate::repositories::{DoorEventRepository, WmsStatusRepository, Repository, MAX_BATCH_EVENTS};
 use crate::repositories::consolidated::ConsolidatedDockEventRepository;
+use crate::repositories::trailer_visit::TrailerVisitRepository;
 use crate::services::DatabaseClient;
 
 /// A factory for creating and managing database connections on a per-plant basis
@@ -111,22 +113,40 @@ impl DatabaseService {
 
     /// Inserts a batch of dock door events into the local database
     ///
+    /// The events are inserted `MAX_BATCH_EVENTS` at a time, each chunk with a single statement that inserts all of
+    /// its events or none of them.
+    ///
     /// # Arguments
     ///
-    /// * `events`: A vector of `DbInsert` objects representing the events to be inserted
+    /// * `events`: The `DbInsert` records to be inserted, in order
     ///
     /// # Returns
     ///
     /// * `Ok(())`: If the events were inserted successfully
-    /// * `Err(DockManagerError)`: If there's an error during the insertion process
-    pub async fn insert_dock_door_events(&self, events: Vec<DbInsert>) -> DockManagerResult<()> {
+    /// * `Err(DockManagerError)`: If there's an error during the insertion process; the chunks before the one
+    ///   that failed were inserted
+    pub async fn insert_dock_door_events(&self, events: &[DbInsert]) -> DockManagerResult<()> {
         let repo = DoorEventRepository::new(self.local_client.clone());
-        for event in events {
-            repo.insert(&event).await?;
+        for chunk in events.chunks(MAX_BATCH_EVENTS) {
+            repo.insert_batch(chunk).await?;
         }
         Ok(())
     }
 
+    /// Inserts a single dock door event into the local database
+    ///
+    /// # Arguments
+    ///
+    /// * `event`: The `DbInsert` representing the event to be inserted
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())`: If the event was inserted successfully
+    /// * `Err(DockManagerError)`: If there's an error during the insertion process
+    pub async fn insert_dock_door_event(&self, event: &DbInsert) -> DockManagerResult<()> {
+        DoorEventRepository::new(self.local_client.clone()).insert(event).await
+    }
+
     /// Inserts a consolidated dock event into the database.
     ///
     /// # Arguments
@@ -141,6 +161,32 @@ impl DatabaseService {
         Ok(())
     }
 
+    /// Inserts a completed trailer visit into the local database
+    ///
+    /// # Arguments
+    /// * `visit`: The `TrailerVisit` to be inserted
+    ///
+    /// # Returns
+    /// * `Ok(())` if the insertion was successful
+    /// * `Err(DockManagerError)` if there was an error during the database operation
+    pub async fn insert_trailer_visit(&self, visit: &TrailerVisit) -> DockManagerResult<()> {
+        TrailerVisitRepository::new(self.local_client.clone()).insert(visit).await
+    }
+
+    /// Fetches the trailer visits of a door from the local database
+    ///
+    /// # Arguments
+    /// * `plant_id`: The plant of the door
+    /// * `door_name`: The name of the door
+    /// * `since`: The earliest arrival time to return
+    ///
+    /// # Returns
+    /// * `Ok(Vec<TrailerVisit>)`: The visits that arrived at or after `since`, oldest first
+    /// * `Err(DockManagerError)` if there was an error during the database operation
+    pub async fn fetch_trailer_visits(&self, plant_id: &str, door_name: &str, since: NaiveDateTime) -> DockManagerResult<Vec<TrailerVisit>> {
+        TrailerVisitRepository::new(self.local_client.clone()).fetch_for_door(plant_id, door_name, since).await
+    }
+
     /// Fetches WMS data (door statuses) for the specified plant
     ///
     /// # Arguments:
@@ -0,0 +1,670 @@
+//! # Database Outbox
+
+//! This module provides the `DbOutbox`, which writes records such as `DbInsert`s to the local database and holds on
+//! to the ones that could not be written, so that an unreachable SQL Server delays the records instead of losing them.
+
+use std::collections::VecDeque;
+use std::fmt::Debug;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Duration;
+use async_trait::async_trait;
+use log::{error, info, warn};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tokio::fs::OpenOptions;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+use sqlx_oldapi::Error as SqlxError;
+use crate::config::OutboxSettings;
+use crate::errors::{DockManagerError, DockManagerResult};
+use crate::models::{DbInsert, TrailerVisit};
+use crate::repositories::MAX_BATCH_EVENTS;
+use crate::services::db::DatabaseService;
+use crate::utils::shutdown::ShutdownSignal;
+
+/// A kind of record written to the database through a `DbOutbox`
+#[async_trait]
+pub trait OutboxRecord: Serialize + DeserializeOwned + Clone + Debug + Send + Sync + 'static {
+    /// The service the records are inserted with
+    type Service: Send + Sync + 'static;
+    /// The table the records are inserted into, which they are logged by
+    const TABLE: &'static str;
+    /// The largest number of records inserted by one statement
+    const MAX_BATCH: usize;
+
+    /// Returns the file the pending records are kept in and the file dead-lettered records are appended to
+    fn files(settings: &OutboxSettings) -> (Option<PathBuf>, Option<PathBuf>);
+
+    /// Returns the name of the door the record is about
+    fn door_name(&self) -> &str;
+
+    /// Inserts up to `MAX_BATCH` records with a single statement, so either all or none of them are inserted
+    async fn insert_batch(db_service: &Self::Service, records: &[Self]) -> DockManagerResult<()>;
+}
+
+#[async_trait]
+impl OutboxRecord for DbInsert {
+    type Service = DatabaseService;
+    const TABLE: &'static str = "DOCK_DOOR_EVENTS";
+    const MAX_BATCH: usize = MAX_BATCH_EVENTS;
+
+    fn files(settings: &OutboxSettings) -> (Option<PathBuf>, Option<PathBuf>) {
+        (settings.path.clone(), settings.dead_letter_path.clone())
+    }
+
+    fn door_name(&self) -> &str {
+        &self.DOOR_NAME
+    }
+
+    async fn insert_batch(db_service: &DatabaseService, records: &[Self]) -> DockManagerResult<()> {
+        db_service.insert_dock_door_events(records).await
+    }
+}
+
+#[async_trait]
+impl OutboxRecord for TrailerVisit {
+    type Service = DatabaseService;
+    const TABLE: &'static str = "TRAILER_VISITS";
+    const MAX_BATCH: usize = 1;
+
+    fn files(settings: &OutboxSettings) -> (Option<PathBuf>, Option<PathBuf>) {
+        (settings.visit_path.clone(), settings.visit_dead_letter_path.clone())
+    }
+
+    fn door_name(&self) -> &str {
+        &self.dock_name
+    }
+
+    async fn insert_batch(db_service: &DatabaseService, records: &[Self]) -> DockManagerResult<()> {
+        for visit in records {
+            db_service.insert_trailer_visit(visit).await?;
+        }
+        Ok(())
+    }
+}
+
+/// A durable queue in front of the inserts into a table, `DOCK_DOOR_EVENTS` by default
+///
+/// Records are written straight to the database while it is reachable, `OutboxRecord::MAX_BATCH` to a statement.
+/// Once a batch fails, its records and every record after them go to the outbox, which is kept in memory and, if
+/// `outbox.path` (`outbox.visit_path` for trailer visits) is set, in a JSON lines file that survives a restart. The outbox is retried in order with an
+/// exponential backoff, a batch at a time, and new records keep going to the outbox until it is empty so that they
+/// are never written ahead of older ones. Only one insert writes straight to the database at a time; records
+/// inserted meanwhile are queued behind it, and the records it could not write are put back ahead of them.
+///
+/// Only failures to reach the database hold the records back. When the database answers a batch from the outbox
+/// with an error, such as a constraint violation, its records are written one at a time. A record that is rejected
+/// on its own is tried `outbox.reject_attempts` times, backing off between the attempts, and then dead-lettered: it
+/// is logged and, if `outbox.dead_letter_path` (`outbox.visit_dead_letter_path`) is set, appended to that file, and
+/// the records after it carry on. The outbox keeps at most `outbox.max_pending` records and dead-letters the oldest
+/// ones beyond that.
+///
+/// A crash while the outbox is being retried may write its oldest records a second time.
+pub struct DbOutbox<T: OutboxRecord = DbInsert> {
+    /// The database service the records are written with
+    db_service: Arc<T::Service>,
+    /// The file the pending records are kept in, if any
+    path: Option<PathBuf>,
+    /// The delay before the first retry after a failure
+    retry_initial: Duration,
+    /// The longest delay between retries
+    retry_max: Duration,
+    /// The number of times a record the database rejects is tried before it is dead-lettered
+    reject_attempts: u32,
+    /// The largest number of records kept in the outbox
+    max_pending: usize,
+    /// The file dead-lettered records are appended to, if any
+    dead_letter_path: Option<PathBuf>,
+    /// The records waiting to be written; never held while the database is written to
+    pending: Mutex<Pending<T>>,
+    /// Set while an insert writes new records straight to the database; the records queued meanwhile are not
+    /// retried until it is done, so that they cannot overtake it
+    writing: AtomicBool,
+    /// Held while the outbox is retried, so that only one retry writes its records at a time
+    retrying: Mutex<()>,
+    /// The number of records waiting to be written
+    depth: AtomicUsize,
+}
+
+/// The records waiting in the outbox
+struct Pending<T> {
+    /// The records, oldest first
+    records: VecDeque<T>,
+    /// The number of oldest records a retry is writing, which are not dead-lettered when the outbox is full
+    in_flight: usize,
+}
+
+impl<T: OutboxRecord> DbOutbox<T> {
+    /// Opens the outbox, loading the records left in its file by a previous run
+    ///
+    /// # Arguments
+    ///
+    /// * `db_service`: The `DatabaseService` to write the records with
+    /// * `settings`: The outbox settings
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Self)`: The opened `DbOutbox`
+    /// * `Err(DockManagerError)`: If the outbox file exists but cannot be read
+    pub async fn open(db_service: Arc<T::Service>, settings: &OutboxSettings) -> DockManagerResult<Self> {
+        let (path, dead_letter_path) = T::files(settings);
+        let pending = match &path {
+            Some(path) => Self::read_pending(path).await?,
+            None => VecDeque::new(),
+        };
+        if !pending.is_empty() {
+            warn!("Database outbox for {} holds {} records from a previous run", T::TABLE, pending.len());
+        }
+
+        Ok(Self {
+            db_service,
+            path,
+            retry_initial: Duration::from_secs(settings.retry_initial_secs.max(1)),
+            retry_max: Duration::from_secs(settings.retry_max_secs.max(settings.retry_initial_secs).max(1)),
+            reject_attempts: settings.reject_attempts.max(1),
+            max_pending: settings.max_pending.max(1),
+            dead_letter_path,
+            depth: AtomicUsize::new(pending.len()),
+            pending: Mutex::new(Pending { records: pending, in_flight: 0 }),
+            writing: AtomicBool::new(false),
+            retrying: Mutex::new(()),
+        })
+    }
+
+    /// Writes records to the database, putting them in the outbox if it is not empty, another insert is writing,
+    /// or a batch fails
+    ///
+    /// The outbox is not held while the records are written, so other inserts queue their records in the
+    /// meantime; the records that could not be written are put back ahead of them. A batch the database rejects
+    /// goes to the outbox as well, whose retry writes its records one at a time and dead-letters the ones that are
+    /// rejected.
+    ///
+    /// # Arguments
+    ///
+    /// * `events`: The records to write, in order
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())`: If every record was either written or put in the outbox
+    /// * `Err(DockManagerError)`: If records were put in the outbox but its file could not be written; they are
+    ///   still retried from memory
+    pub async fn insert(&self, mut events: Vec<T>) -> DockManagerResult<()> {
+        if events.is_empty() {
+            return Ok(());
+        }
+        let writing = {
+            let mut pending = self.pending.lock().await;
+            if !pending.records.is_empty() || self.writing.load(Ordering::Acquire) {
+                return self.enqueue(&mut pending, events).await;
+            }
+            WritingGuard::set(&self.writing)
+        };
+
+        let mut written = 0;
+        for batch in events.chunks(T::MAX_BATCH) {
+            if let Err(e) = T::insert_batch(&self.db_service, batch).await {
+                warn!("Database insert into {} failed, keeping {} records in the outbox: {}", T::TABLE, events.len() - written, e);
+                break;
+            }
+            written += batch.len();
+        }
+        events = events.split_off(written);
+
+        let mut pending = self.pending.lock().await;
+        let result = if events.is_empty() { Ok(()) } else { self.requeue(&mut pending, events).await };
+        drop(writing);
+        result
+    }
+
+    /// Puts records in the outbox to be written by its retry, without waiting for the database
+    ///
+    /// # Arguments
+    ///
+    /// * `events`: The records to write, in order
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())`: If the records were put in the outbox
+    /// * `Err(DockManagerError)`: If the outbox file could not be written; the records are still retried from memory
+    pub async fn push(&self, events: Vec<T>) -> DockManagerResult<()> {
+        if events.is_empty() {
+            return Ok(());
+        }
+        let mut pending = self.pending.lock().await;
+        self.enqueue(&mut pending, events).await
+    }
+
+    /// Writes a batch of records from the outbox to the database, one at a time if the database rejects the batch
+    async fn write_batch(&self, batch: &[T]) -> BatchOutcome {
+        if let [event] = batch {
+            return match self.write(event).await {
+                WriteOutcome::Written => BatchOutcome { removed: 1, written: 1, error: None },
+                WriteOutcome::Rejected(e) => {
+                    self.dead_letter(batch, &e.to_string()).await;
+                    BatchOutcome { removed: 1, written: 0, error: None }
+                },
+                WriteOutcome::Unreachable(e) => BatchOutcome { removed: 0, written: 0, error: Some(e) },
+            };
+        }
+        match T::insert_batch(&self.db_service, batch).await {
+            Ok(()) => return BatchOutcome { removed: batch.len(), written: batch.len(), error: None },
+            Err(e) if !is_rejection(&e) => return BatchOutcome { removed: 0, written: 0, error: Some(e) },
+            Err(e) => warn!("Database rejected a batch of {} records for {}, writing them one at a time: {}", batch.len(), T::TABLE, e),
+        }
+
+        let mut written = 0;
+        for (index, event) in batch.iter().enumerate() {
+            match self.write(event).await {
+                WriteOutcome::Written => written += 1,
+                WriteOutcome::Rejected(e) => self.dead_letter(std::slice::from_ref(event), &e.to_string()).await,
+                WriteOutcome::Unreachable(e) => return BatchOutcome { removed: index, written, error: Some(e) },
+            }
+        }
+        BatchOutcome { removed: batch.len(), written, error: None }
+    }
+
+    /// Writes a record to the database, trying it again after the retry backoff if the database rejects it
+    async fn write(&self, event: &T) -> WriteOutcome {
+        let mut attempt = 1;
+        let mut delay = self.retry_initial;
+        loop {
+            match T::insert_batch(&self.db_service, std::slice::from_ref(event)).await {
+                Ok(()) => return WriteOutcome::Written,
+                Err(e) if !is_rejection(&e) => return WriteOutcome::Unreachable(e),
+                Err(e) if attempt >= self.reject_attempts => return WriteOutcome::Rejected(e),
+                Err(e) => {
+                    warn!("Database rejected the {} record for door {} (attempt {} of {}), trying again in {:?}: {}",
+                        T::TABLE, event.door_name(), attempt, self.reject_attempts, delay, e);
+                    tokio::time::sleep(delay).await;
+                    delay = (delay * 2).min(self.retry_max);
+                    attempt += 1;
+                },
+            }
+        }
+    }
+
+    /// Adds records to the end of the outbox, dead-lettering the oldest records beyond `max_pending`
+    ///
+    /// The records a retry is writing are kept, as the retry removes them once they are written.
+    async fn enqueue(&self, pending: &mut Pending<T>, events: Vec<T>) -> DockManagerResult<()> {
+        let overflow = (pending.records.len() + events.len()).saturating_sub(self.max_pending);
+        if overflow == 0 {
+            let result = match &self.path {
+                Some(path) => Self::append_to_file(path, &events).await,
+                None => Ok(()),
+            };
+            pending.records.extend(events);
+            self.depth.store(pending.records.len(), Ordering::Relaxed);
+            return result;
+        }
+
+        pending.records.extend(events);
+        self.trim(pending, overflow).await
+    }
+
+    /// Puts records an insert could not write back at the front of the outbox, ahead of the records queued while
+    /// it was writing, dead-lettering the oldest records beyond `max_pending`
+    async fn requeue(&self, pending: &mut Pending<T>, events: Vec<T>) -> DockManagerResult<()> {
+        if pending.records.is_empty() {
+            return self.enqueue(pending, events).await;
+        }
+
+        for event in events.into_iter().rev() {
+            pending.records.push_front(event);
+        }
+        let overflow = pending.records.len().saturating_sub(self.max_pending);
+        if overflow == 0 {
+            self.depth.store(pending.records.len(), Ordering::Relaxed);
+            return match &self.path {
+                Some(path) => Self::rewrite_file(path, &pending.records).await,
+                None => Ok(()),
+            };
+        }
+        self.trim(pending, overflow).await
+    }
+
+    /// Dead-letters the `overflow` oldest records of the outbox, except the ones a retry is writing, and rewrites
+    /// the outbox file
+    async fn trim(&self, pending: &mut Pending<T>, overflow: usize) -> DockManagerResult<()> {
+        let start = pending.in_flight.min(pending.records.len());
+        let end = (start + overflow).min(pending.records.len());
+        let dropped: Vec<T> = pending.records.drain(start..end).collect();
+        self.depth.store(pending.records.len(), Ordering::Relaxed);
+        self.dead_letter(&dropped, &format!("the outbox is full ({} records)", self.max_pending)).await;
+        match &self.path {
+            Some(path) => Self::rewrite_file(path, &pending.records).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Logs records that are given up on and appends them to the dead-letter file, if any
+    async fn dead_letter(&self, events: &[T], reason: &str) {
+        error!("Dead-lettering {} {} records, {}", events.len(), T::TABLE, reason);
+        match &self.dead_letter_path {
+            Some(path) => {
+                if let Err(e) = Self::append_to_file(path, events).await {
+                    error!("Failed to write dead-lettered records to {}: {}; records: {:?}", path.display(), e, events);
+                }
+            },
+            None => error!("Dead-lettered records: {:?}", events),
+        }
+    }
+
+    /// Writes the records in the outbox to the database, oldest first, stopping once the database is unreachable
+    ///
+    /// The records are written a batch at a time, and the outbox is not held while a batch is written, so new
+    /// records are queued behind it in the meantime. Records the database rejects are dead-lettered and removed
+    /// from the outbox. While an insert is writing new records straight to the database the retry stops early,
+    /// leaving the outbox to the next retry.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(usize)`: The number of records written; the outbox is empty unless an insert was writing
+    /// * `Err(DockManagerError)`: If the database could not be reached; the records before the one that failed
+    ///   have been written or dead-lettered and removed from the outbox
+    pub async fn retry(&self) -> DockManagerResult<usize> {
+        let _retrying = self.retrying.lock().await;
+        let mut written = 0;
+        let mut removed = 0;
+        let mut result = Ok(());
+        loop {
+            let batch: Vec<T> = {
+                let mut pending = self.pending.lock().await;
+                if self.writing.load(Ordering::Acquire) {
+                    // The records were queued behind an insert that is still writing
+                    break;
+                }
+                let batch: Vec<T> = pending.records.iter().take(T::MAX_BATCH).cloned().collect();
+                pending.in_flight = batch.len();
+                batch
+            };
+            if batch.is_empty() {
+                break;
+            }
+
+            let outcome = self.write_batch(&batch).await;
+            {
+                let mut pending = self.pending.lock().await;
+                pending.records.drain(..outcome.removed);
+                pending.in_flight = 0;
+                self.depth.store(pending.records.len(), Ordering::Relaxed);
+            }
+            written += outcome.written;
+            removed += outcome.removed;
+            if let Some(e) = outcome.error {
+                result = Err(e);
+                break;
+            }
+        }
+
+        if removed > 0 {
+            if let Some(path) = &self.path {
+                let pending = self.pending.lock().await;
+                if let Err(e) = Self::rewrite_file(path, &pending.records).await {
+                    error!("Failed to rewrite the database outbox file {}: {}", path.display(), e);
+                }
+            }
+        }
+        result.map(|_| written)
+    }
+
+    /// Returns the database service the records are written with
+    pub fn db_service(&self) -> &Arc<T::Service> {
+        &self.db_service
+    }
+
+    /// Returns the number of records waiting in the outbox
+    pub fn depth(&self) -> usize {
+        self.depth.load(Ordering::Relaxed)
+    }
+
+    /// Retries the outbox until shutdown, backing off while the database stays unreachable
+    ///
+    /// # Arguments
+    ///
+    /// * `shutdown`: The signal that stops the retries
+    pub async fn run(&self, mut shutdown: ShutdownSignal) {
+        let mut delay = self.retry_initial;
+        while shutdown.sleep(delay).await {
+            if self.depth() == 0 {
+                delay = self.retry_initial;
+                continue;
+            }
+            match self.retry().await {
+                Ok(written) => {
+                    if written > 0 {
+                        info!("Database outbox for {} emptied, {} records written", T::TABLE, written);
+                    }
+                    delay = self.retry_initial;
+                },
+                Err(e) => {
+                    delay = (delay * 2).min(self.retry_max);
+                    warn!("Database outbox for {} still holds {} records, retrying in {:?}: {}", T::TABLE, self.depth(), delay, e);
+                },
+            }
+        }
+    }
+
+    /// Reads the records left in an outbox file
+    ///
+    /// Lines that cannot be parsed, such as one cut short by a crash, are skipped with a warning.
+    async fn read_pending(path: &Path) -> DockManagerResult<VecDeque<T>> {
+        let content = match tokio::fs::read_to_string(path).await {
+            Ok(content) => content,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(VecDeque::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut pending = VecDeque::new();
+        for (index, line) in content.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+            match serde_json::from_str::<T>(line) {
+                Ok(event) => pending.push_back(event),
+                Err(e) => warn!("Skipping unreadable line {} of database outbox {}: {}", index + 1, path.display(), e),
+            }
+        }
+        Ok(pending)
+    }
+
+    /// Appends records to an outbox file
+    async fn append_to_file(path: &Path, events: &[T]) -> DockManagerResult<()> {
+        let content = to_json_lines(events.iter())?;
+        let mut file = OpenOptions::new().create(true).append(true).open(path).await?;
+        file.write_all(&content).await?;
+        file.flush().await?;
+        Ok(())
+    }
+
+    /// Replaces the content of an outbox file with the given records, removing it when there are none
+    ///
+    /// The records are written to a temporary file next to `path` which then replaces it, so a crash
+    /// while writing never leaves a truncated outbox behind.
+    async fn rewrite_file(path: &Path, pending: &VecDeque<T>) -> DockManagerResult<()> {
+        if pending.is_empty() {
+            return match tokio::fs::remove_file(path).await {
+                Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+                _ => Ok(()),
+            };
+        }
+        let mut temp_path = path.to_path_buf();
+        temp_path.set_extension("tmp");
+        tokio::fs::write(&temp_path, to_json_lines(pending.iter())?).await?;
+        tokio::fs::rename(&temp_path, path).await?;
+        Ok(())
+    }
+}
+
+/// Clears the flag of an insert writing straight to the database once the insert is done, or dropped
+struct WritingGuard<'a>(&'a AtomicBool);
+
+impl<'a> WritingGuard<'a> {
+    /// Sets the flag, which the guard clears when dropped
+    fn set(flag: &'a AtomicBool) -> Self {
+        flag.store(true, Ordering::Release);
+        Self(flag)
+    }
+}
+
+impl Drop for WritingGuard<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::Release);
+    }
+}
+
+/// The outcome of writing a batch of records from the outbox to the database
+struct BatchOutcome {
+    /// The number of records at the front of the batch that were written or dead-lettered
+    removed: usize,
+    /// The number of records that were written
+    written: usize,
+    /// The error that stopped the batch, if the database could not be reached
+    error: Option<DockManagerError>,
+}
+
+/// The outcome of writing a record to the database
+enum WriteOutcome {
+    /// The record was written
+    Written,
+    /// The database answered every attempt with an error about the record itself
+    Rejected(DockManagerError),
+    /// The database could not be reached
+    Unreachable(DockManagerError),
+}
+
+/// Returns `true` if the database answered and refused the statement, rather than not being reachable
+fn is_rejection(error: &DockManagerError) -> bool {
+    matches!(error, DockManagerError::DatabaseError(SqlxError::Database(_)))
+}
+
+/// Serializes records as JSON lines
+fn to_json_lines<'a, T: Serialize + 'a>(events: impl Iterator<Item = &'a T>) -> DockManagerResult<Vec<u8>> {
+    let mut content = Vec::new();
+    for event in events {
+        content.extend(serde_json::to_vec(event)?);
+        content.push(b'\n');
+    }
+    Ok(content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use tokio::sync::Notify;
+
+    /// A database that records the inserted records, and fails or holds the inserts when told to
+    #[derive(Default)]
+    struct FakeDatabase {
+        /// Makes the inserts fail as if the database could not be reached
+        unreachable: AtomicBool,
+        /// Held by a test to keep the inserts waiting
+        hold: Mutex<()>,
+        /// Notified when an insert starts
+        started: Notify,
+        /// The records inserted, in order
+        written: std::sync::Mutex<Vec<u32>>,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    struct TestRecord(u32);
+
+    #[async_trait]
+    impl OutboxRecord for TestRecord {
+        type Service = FakeDatabase;
+        const TABLE: &'static str = "TEST_RECORDS";
+        const MAX_BATCH: usize = 2;
+
+        fn files(_settings: &OutboxSettings) -> (Option<PathBuf>, Option<PathBuf>) {
+            (None, None)
+        }
+
+        fn door_name(&self) -> &str {
+            "DOOR_1"
+        }
+
+        async fn insert_batch(database: &FakeDatabase, records: &[Self]) -> DockManagerResult<()> {
+            database.started.notify_one();
+            let _hold = database.hold.lock().await;
+            if database.unreachable.load(Ordering::SeqCst) {
+                return Err(DockManagerError::DatabaseError(SqlxError::PoolTimedOut));
+            }
+            database.written.lock().unwrap().extend(records.iter().map(|record| record.0));
+            Ok(())
+        }
+    }
+
+    async fn outbox() -> (Arc<FakeDatabase>, Arc<DbOutbox<TestRecord>>) {
+        let database = Arc::new(FakeDatabase::default());
+        let outbox = DbOutbox::open(Arc::clone(&database), &OutboxSettings::default()).await.unwrap();
+        (database, Arc::new(outbox))
+    }
+
+    fn records(ids: &[u32]) -> Vec<TestRecord> {
+        ids.iter().copied().map(TestRecord).collect()
+    }
+
+    fn written(database: &FakeDatabase) -> Vec<u32> {
+        database.written.lock().unwrap().clone()
+    }
+
+    #[tokio::test]
+    async fn records_wait_in_the_outbox_while_the_database_is_unreachable() {
+        let (database, outbox) = outbox().await;
+        database.unreachable.store(true, Ordering::SeqCst);
+
+        outbox.insert(records(&[1, 2, 3])).await.unwrap();
+        outbox.insert(records(&[4])).await.unwrap();
+        assert_eq!(outbox.depth(), 4);
+        assert!(outbox.retry().await.is_err());
+        assert_eq!(outbox.depth(), 4);
+
+        database.unreachable.store(false, Ordering::SeqCst);
+        assert_eq!(outbox.retry().await.unwrap(), 4);
+        assert_eq!(outbox.depth(), 0);
+        assert_eq!(written(&database), vec![1, 2, 3, 4]);
+    }
+
+    #[tokio::test]
+    async fn inserts_queue_behind_a_write_without_waiting_for_the_database() {
+        let (database, outbox) = outbox().await;
+        let hold = database.hold.lock().await;
+        let first = tokio::spawn({
+            let outbox = Arc::clone(&outbox);
+            async move { outbox.insert(records(&[1, 2])).await }
+        });
+        database.started.notified().await;
+
+        tokio::time::timeout(Duration::from_secs(1), outbox.insert(records(&[3])))
+            .await
+            .expect("the insert should not wait for the write in progress")
+            .unwrap();
+        assert_eq!(outbox.retry().await.unwrap(), 0, "the queued record must not overtake the write in progress");
+
+        drop(hold);
+        first.await.unwrap().unwrap();
+        assert_eq!(outbox.retry().await.unwrap(), 1);
+        assert_eq!(written(&database), vec![1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn records_of_a_failed_write_go_ahead_of_the_ones_queued_meanwhile() {
+        let (database, outbox) = outbox().await;
+        database.unreachable.store(true, Ordering::SeqCst);
+        let hold = database.hold.lock().await;
+        let first = tokio::spawn({
+            let outbox = Arc::clone(&outbox);
+            async move { outbox.insert(records(&[1, 2])).await }
+        });
+        database.started.notified().await;
+
+        outbox.insert(records(&[3])).await.unwrap();
+        drop(hold);
+        first.await.unwrap().unwrap();
+        assert_eq!(outbox.depth(), 3);
+
+        database.unreachable.store(false, Ordering::SeqCst);
+        assert_eq!(outbox.retry().await.unwrap(), 3);
+        assert_eq!(written(&database), vec![1, 2, 3]);
+    }
+}
@@ -1,5 +1,6 @@
 pub mod plc;
 pub mod db;
+pub mod db_outbox;
 pub mod dbc;
 
 pub use plc::*;
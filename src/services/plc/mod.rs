@@ -1,5 +1,14 @@
+pub mod plc_address;
 pub mod plc_tag_factory;
 pub mod plc_reader;
+pub mod plc_tag_cache;
+pub mod modbus;
 pub mod plcs;
+pub mod sensor_source;
+pub mod simulator;
+pub mod replay;
 
-pub use plcs::*;
\ No newline at end of file
+pub use plcs::*;
+pub use sensor_source::SensorSource;
+pub use simulator::SimulatedSensorSource;
+pub use replay::{ReplaySensorSource, RecordingSensorSource};
@@ -0,0 +1,387 @@
+//! # Modbus TCP
+//!
+//! This module reads dock door sensors from controllers that speak Modbus TCP rather than EtherNet/IP.
+//! The tag addresses are parsed by `crate::models::ModbusAddress`.
+
+use std::sync::Arc;
+use std::time::Duration;
+use dashmap::DashMap;
+use log::{debug, info};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::Mutex;
+use crate::config::{DockDoorConfig, PlcElementType};
+use crate::errors::{DockManagerError, DockManagerResult};
+use crate::models::{ModbusAddress, ModbusTable, SensorValue, DEFAULT_MODBUS_PORT, DEFAULT_MODBUS_UNIT_ID};
+
+/// # ModbusConnection
+///
+/// Describes how to reach the Modbus TCP controller of a single dock door. Built from the door's
+/// `DockDoorConfig`, with the port and unit identifier defaulting to 502 and 1.
+#[derive(Debug, Clone)]
+pub struct ModbusConnection {
+    /// The IP address (or host name) of the controller
+    pub host: String,
+    /// The TCP port of the controller
+    pub port: u16,
+    /// The unit identifier sent with every request
+    pub unit_id: u8,
+    /// The element type of the door's register tags, if configured
+    pub element_type: Option<PlcElementType>,
+}
+
+impl ModbusConnection {
+    /// Builds the connection description for a dock door
+    pub fn from_door(door: &DockDoorConfig) -> Self {
+        Self {
+            host: door.dock_ip.clone(),
+            port: door.modbus_port.unwrap_or(DEFAULT_MODBUS_PORT),
+            unit_id: door.modbus_unit_id.unwrap_or(DEFAULT_MODBUS_UNIT_ID),
+            element_type: door.element_type,
+        }
+    }
+
+    /// The key identifying the connection in the `ModbusClientCache`
+    fn key(&self) -> String {
+        format!("{}:{}/{}", self.host, self.port, self.unit_id)
+    }
+}
+
+/// # ModbusClient
+///
+/// A minimal Modbus TCP client supporting the read functions needed to poll dock door sensors.
+/// Requests are sent one at a time and each response is checked against its request's transaction identifier.
+/// The client is generic over its stream, so it can talk to any `AsyncRead + AsyncWrite` stand-in for a controller.
+pub struct ModbusClient<S = TcpStream> {
+    /// The stream connected to the controller
+    stream: S,
+    /// The unit identifier sent with every request
+    unit_id: u8,
+    /// The identifier of the last request sent
+    transaction_id: u16,
+    /// The time allowed for each request to complete
+    timeout: Duration,
+}
+
+impl ModbusClient<TcpStream> {
+    /// Opens a TCP connection to a Modbus controller
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The `ModbusConnection` of the door's controller
+    /// * `timeout`: The time allowed for connecting and for each request
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(ModbusClient)`: The connected client
+    /// * `Err(DockManagerError::ConnectionError)`: If the controller cannot be reached in time
+    pub async fn connect(connection: &ModbusConnection, timeout: Duration) -> DockManagerResult<Self> {
+        let address = format!("{}:{}", connection.host, connection.port);
+        let stream = tokio::time::timeout(timeout, TcpStream::connect(&address)).await
+            .map_err(|_| DockManagerError::ConnectionError(format!("Timed out connecting to Modbus controller {}", address)))?
+            .map_err(|e| DockManagerError::ConnectionError(format!("Failed to connect to Modbus controller {}: {}", address, e)))?;
+        stream.set_nodelay(true)?;
+        debug!("Connected to Modbus controller {}", address);
+        Ok(Self::new(stream, connection.unit_id, timeout))
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin + Send> ModbusClient<S> {
+    /// The maximum number of bits a single request may read
+    const MAX_BITS: u16 = 2000;
+    /// The maximum number of registers a single request may read
+    const MAX_REGISTERS: u16 = 125;
+
+    /// Creates a client over an already connected stream
+    pub fn new(stream: S, unit_id: u8, timeout: Duration) -> Self {
+        Self { stream, unit_id, transaction_id: 0, timeout }
+    }
+
+    /// Reads consecutive coils or discrete inputs
+    ///
+    /// # Arguments
+    ///
+    /// * `table`: `ModbusTable::Coil` or `ModbusTable::DiscreteInput`
+    /// * `offset`: The zero-based offset of the first bit
+    /// * `count`: The number of bits to read (1 to 2000)
+    pub async fn read_bits(&mut self, table: ModbusTable, offset: u16, count: u16) -> DockManagerResult<Vec<bool>> {
+        if !table.is_bit_table() || count == 0 || count > Self::MAX_BITS {
+            return Err(DockManagerError::PlcError(format!("Invalid Modbus bit read of {} from {:?}", count, table)));
+        }
+        let data = self.request(table.function_code(), offset, count).await?;
+        if data.len() < usize::from(count).div_ceil(8) {
+            return Err(DockManagerError::PlcError(format!("Modbus response too short for {} bits", count)));
+        }
+        Ok((0..usize::from(count)).map(|i| data[i / 8] >> (i % 8) & 1 == 1).collect())
+    }
+
+    /// Reads consecutive input or holding registers
+    ///
+    /// # Arguments
+    ///
+    /// * `table`: `ModbusTable::InputRegister` or `ModbusTable::HoldingRegister`
+    /// * `offset`: The zero-based offset of the first register
+    /// * `count`: The number of registers to read (1 to 125)
+    pub async fn read_registers(&mut self, table: ModbusTable, offset: u16, count: u16) -> DockManagerResult<Vec<u16>> {
+        if table.is_bit_table() || count == 0 || count > Self::MAX_REGISTERS {
+            return Err(DockManagerError::PlcError(format!("Invalid Modbus register read of {} from {:?}", count, table)));
+        }
+        let data = self.request(table.function_code(), offset, count).await?;
+        if data.len() < usize::from(count) * 2 {
+            return Err(DockManagerError::PlcError(format!("Modbus response too short for {} registers", count)));
+        }
+        Ok(data.chunks_exact(2).take(usize::from(count)).map(|word| u16::from_be_bytes([word[0], word[1]])).collect())
+    }
+
+    /// Reads the value of a single tag
+    ///
+    /// Coils and discrete inputs, and addresses selecting a register bit, are reported as `Bool` values.
+    /// Registers are decoded according to the element type: `bool` reports whether the register is non-zero,
+    /// `sint` its low byte, `int` a signed 16-bit value, and `dint` and `real` span two registers, high word first.
+    /// Without an element type the register is reported as an unsigned 16-bit integer.
+    ///
+    /// # Arguments
+    ///
+    /// * `address`: The parsed address of the tag
+    /// * `element_type`: The data type of the tag, if configured
+    pub async fn read_value(&mut self, address: &ModbusAddress, element_type: Option<PlcElementType>) -> DockManagerResult<SensorValue> {
+        if address.table.is_bit_table() {
+            let bits = self.read_bits(address.table, address.offset, 1).await?;
+            return Ok(SensorValue::Bool(bits[0]));
+        }
+
+        let count = match element_type {
+            Some(PlcElementType::Dint | PlcElementType::Real) if address.bit.is_none() => 2,
+            _ => 1,
+        };
+        let registers = self.read_registers(address.table, address.offset, count).await?;
+        let register = registers[0];
+
+        let value = match (address.bit, element_type) {
+            (Some(bit), _) => SensorValue::Bool(register >> bit & 1 == 1),
+            (None, Some(PlcElementType::Bool)) => SensorValue::Bool(register != 0),
+            (None, Some(PlcElementType::Sint)) => SensorValue::Int(i64::from(register as u8 as i8)),
+            (None, Some(PlcElementType::Int)) => SensorValue::Int(i64::from(register as i16)),
+            (None, Some(PlcElementType::Dint)) => {
+                SensorValue::Int(i64::from(((u32::from(register) << 16) | u32::from(registers[1])) as i32))
+            }
+            (None, Some(PlcElementType::Real)) => {
+                SensorValue::Float(f64::from(f32::from_bits((u32::from(register) << 16) | u32::from(registers[1]))))
+            }
+            (None, None) => SensorValue::Int(i64::from(register)),
+        };
+        Ok(value)
+    }
+
+    /// Sends a read request and returns the data bytes of the response
+    async fn request(&mut self, function: u8, offset: u16, count: u16) -> DockManagerResult<Vec<u8>> {
+        self.transaction_id = self.transaction_id.wrapping_add(1);
+        let transaction_id = self.transaction_id;
+
+        let mut frame = Vec::with_capacity(12);
+        frame.extend_from_slice(&transaction_id.to_be_bytes());
+        frame.extend_from_slice(&0u16.to_be_bytes());
+        frame.extend_from_slice(&6u16.to_be_bytes());
+        frame.push(self.unit_id);
+        frame.push(function);
+        frame.extend_from_slice(&offset.to_be_bytes());
+        frame.extend_from_slice(&count.to_be_bytes());
+
+        let exchange = async {
+            self.stream.write_all(&frame).await?;
+
+            let mut header = [0u8; 7];
+            self.stream.read_exact(&mut header).await?;
+            let length = usize::from(u16::from_be_bytes([header[4], header[5]]));
+            if length < 2 {
+                return Err(DockManagerError::PlcError(format!("Invalid Modbus response length {}", length)));
+            }
+            let mut pdu = vec![0u8; length - 1];
+            self.stream.read_exact(&mut pdu).await?;
+            Ok::<_, DockManagerError>((header, pdu))
+        };
+        let (header, pdu) = tokio::time::timeout(self.timeout, exchange).await
+            .map_err(|_| DockManagerError::PlcError(format!("Modbus request timed out after {:?}", self.timeout)))??;
+
+        let response_id = u16::from_be_bytes([header[0], header[1]]);
+        if response_id != transaction_id || header[2..4] != [0, 0] {
+            return Err(DockManagerError::PlcError(format!(
+                "Unexpected Modbus response (transaction {}, expected {})", response_id, transaction_id
+            )));
+        }
+        if pdu[0] == function | 0x80 {
+            return Err(DockManagerError::PlcError(format!(
+                "Modbus exception {:#04x} for function {:#04x}", pdu.get(1).copied().unwrap_or(0), function
+            )));
+        }
+        if pdu[0] != function || pdu.len() < 2 || pdu.len() - 2 != usize::from(pdu[1]) {
+            return Err(DockManagerError::PlcError(format!("Malformed Modbus response for function {:#04x}", function)));
+        }
+
+        Ok(pdu[2..].to_vec())
+    }
+}
+
+/// # ModbusClientCache
+///
+/// Keeps one Modbus TCP connection per controller open across polling cycles. Requests to the same controller
+/// are serialized through the connection's lock. A connection whose request fails should be dropped with
+/// `reset`, so the next read reconnects.
+#[derive(Default)]
+pub struct ModbusClientCache {
+    /// The open connections, keyed by host, port and unit identifier
+    clients: DashMap<String, Arc<Mutex<Option<ModbusClient>>>>,
+}
+
+impl ModbusClientCache {
+    /// Creates a new, empty `ModbusClientCache`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads a tag from a controller, connecting to it first if necessary
+    ///
+    /// A failed read drops the connection, so the next read opens a new one.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The `ModbusConnection` of the door's controller
+    /// * `address`: The parsed address of the tag
+    /// * `element_type`: The data type of the tag, if configured
+    /// * `timeout`: The time allowed for connecting and for the request
+    pub async fn read_value(
+        &self,
+        connection: &ModbusConnection,
+        address: &ModbusAddress,
+        element_type: Option<PlcElementType>,
+        timeout: Duration,
+    ) -> DockManagerResult<SensorValue> {
+        let slot = Arc::clone(self.clients.entry(connection.key()).or_default().value());
+        let mut client = slot.lock().await;
+
+        if client.is_none() {
+            *client = Some(ModbusClient::connect(connection, timeout).await?);
+        }
+        let result = match client.as_mut() {
+            Some(client) => client.read_value(address, element_type).await,
+            None => Err(DockManagerError::ConnectionError(format!("No Modbus connection to {}", connection.key()))),
+        };
+        if result.is_err() {
+            *client = None;
+        }
+        result
+    }
+
+    /// Closes every open connection
+    pub fn close_all(&self) {
+        let count = self.clients.len();
+        self.clients.clear();
+        info!("Closed {} Modbus connections", count);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{duplex, DuplexStream};
+
+    /// Creates a client talking to a stand-in controller over an in-memory stream
+    fn stand_in() -> (ModbusClient<DuplexStream>, DuplexStream) {
+        let (client, server) = duplex(256);
+        (ModbusClient::new(client, DEFAULT_MODBUS_UNIT_ID, Duration::from_secs(1)), server)
+    }
+
+    /// Reads a request on the controller's side and answers it with the given PDU, returning the request frame
+    async fn respond(server: &mut DuplexStream, transaction_id: u16, pdu: &[u8]) -> [u8; 12] {
+        let mut request = [0u8; 12];
+        server.read_exact(&mut request).await.unwrap();
+        let mut frame = Vec::new();
+        frame.extend_from_slice(&transaction_id.to_be_bytes());
+        frame.extend_from_slice(&0u16.to_be_bytes());
+        frame.extend_from_slice(&(pdu.len() as u16 + 1).to_be_bytes());
+        frame.push(request[6]);
+        frame.extend_from_slice(pdu);
+        server.write_all(&frame).await.unwrap();
+        request
+    }
+
+    #[tokio::test]
+    async fn read_bits_sends_request_and_decodes_bits_lsb_first() {
+        let (mut client, mut server) = stand_in();
+        let (bits, request) = tokio::join!(
+            client.read_bits(ModbusTable::Coil, 10, 10),
+            respond(&mut server, 1, &[0x01, 2, 0b1010_0101, 0b0000_0010]),
+        );
+
+        assert_eq!(request, [0, 1, 0, 0, 0, 6, 1, 0x01, 0, 10, 0, 10]);
+        assert_eq!(bits.unwrap(), vec![true, false, true, false, false, true, false, true, false, true]);
+    }
+
+    #[tokio::test]
+    async fn read_registers_decodes_big_endian_words() {
+        let (mut client, mut server) = stand_in();
+        let (registers, request) = tokio::join!(
+            client.read_registers(ModbusTable::HoldingRegister, 100, 2),
+            respond(&mut server, 1, &[0x03, 4, 0x12, 0x34, 0xff, 0xfe]),
+        );
+
+        assert_eq!(request, [0, 1, 0, 0, 0, 6, 1, 0x03, 0, 100, 0, 2]);
+        assert_eq!(registers.unwrap(), vec![0x1234, 0xfffe]);
+    }
+
+    #[tokio::test]
+    async fn read_value_decodes_dint_and_real_high_word_first() {
+        let (mut client, mut server) = stand_in();
+        let address: ModbusAddress = "hr:0".parse().unwrap();
+
+        let (dint, request) = tokio::join!(
+            client.read_value(&address, Some(PlcElementType::Dint)),
+            respond(&mut server, 1, &[0x03, 4, 0xff, 0xff, 0xff, 0xfe]),
+        );
+        assert_eq!(request[10..], [0, 2]);
+        assert_eq!(dint.unwrap(), SensorValue::Int(-2));
+
+        let (real, _) = tokio::join!(
+            client.read_value(&address, Some(PlcElementType::Real)),
+            respond(&mut server, 2, &[0x03, 4, 0x3f, 0xc0, 0x00, 0x00]),
+        );
+        assert_eq!(real.unwrap(), SensorValue::Float(1.5));
+    }
+
+    #[tokio::test]
+    async fn read_value_selects_a_register_bit() {
+        let (mut client, mut server) = stand_in();
+        let address: ModbusAddress = "hr:100/4".parse().unwrap();
+        let (value, _) = tokio::join!(
+            client.read_value(&address, Some(PlcElementType::Dint)),
+            respond(&mut server, 1, &[0x03, 2, 0x00, 0x10]),
+        );
+        assert_eq!(value.unwrap(), SensorValue::Bool(true));
+    }
+
+    #[tokio::test]
+    async fn exception_response_is_an_error() {
+        let (mut client, mut server) = stand_in();
+        let (result, _) = tokio::join!(
+            client.read_registers(ModbusTable::HoldingRegister, 0, 1),
+            respond(&mut server, 1, &[0x83, 0x02]),
+        );
+        match result {
+            Err(DockManagerError::PlcError(message)) => assert!(message.contains("exception 0x02"), "{}", message),
+            other => panic!("expected a Modbus exception, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn mismatched_transaction_id_is_an_error() {
+        let (mut client, mut server) = stand_in();
+        let (result, _) = tokio::join!(
+            client.read_registers(ModbusTable::InputRegister, 0, 1),
+            respond(&mut server, 7, &[0x04, 2, 0x00, 0x01]),
+        );
+        match result {
+            Err(DockManagerError::PlcError(message)) => assert!(message.contains("transaction 7, expected 1"), "{}", message),
+            other => panic!("expected a transaction mismatch, got {:?}", other),
+        }
+    }
+}
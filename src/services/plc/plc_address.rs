@@ -0,0 +1,135 @@
+//! # PLC Block Reads
+//!
+//! This module groups the PCCC bit addresses of a door by data file so that each file can be fetched with a
+//! single multi-element read. The addresses themselves are parsed by `crate::models::PlcAddress`.
+
+use std::collections::BTreeMap;
+use crate::config::{DockPlcTag, PlcElementType};
+use crate::models::PlcAddress;
+
+/// A single multi-element read covering every configured bit of one data file
+#[derive(Debug, Clone)]
+pub struct BlockRead {
+    /// The data file being read, e.g. `B9`
+    pub data_file: String,
+    /// The first element (word) read, i.e. the lowest element of the block's tags
+    pub start_element: u16,
+    /// The number of 16-bit words to read, starting at `start_element`
+    pub element_count: u16,
+    /// The tags decoded from the block, along with their parsed addresses
+    pub tags: Vec<(DockPlcTag, PlcAddress)>,
+}
+
+impl BlockRead {
+    /// The libplctag name of the block, i.e. the first element read
+    pub fn tag_name(&self) -> String {
+        format!("{}:{}", self.data_file, self.start_element)
+    }
+
+    /// Decodes the value of one of the block's bits from the words read from the PLC
+    ///
+    /// # Returns
+    ///
+    /// The bit value (0 or 1), or `None` if the element lies outside the words read
+    pub fn decode_bit(&self, words: &[u16], address: &PlcAddress) -> Option<u8> {
+        let offset = address.element.checked_sub(self.start_element)?;
+        let word = words.get(offset as usize)?;
+        let bit = address.bit.unwrap_or(0);
+        Some(((word >> bit) & 1) as u8)
+    }
+}
+
+/// Splits a door's tags into block reads (one per data file) and the tags that must be read on their own
+///
+/// Only bit addresses in 16-bit word files are grouped, and only for tags without a data type or declared as `bool`;
+/// any other tag, including ones whose address cannot be parsed as a PCCC address, is returned as an individual read.
+/// Each block covers the elements from the lowest to the highest one used in its file; the tags of a file whose
+/// span does not fit in a single read are read individually.
+///
+/// # Arguments
+///
+/// * `tags`: The PLC tags configured for the door
+///
+/// # Returns
+///
+/// A tuple of the block reads and the remaining individual tags
+pub fn plan_block_reads(tags: &[DockPlcTag]) -> (Vec<BlockRead>, Vec<DockPlcTag>) {
+    let mut files: BTreeMap<String, Vec<(DockPlcTag, PlcAddress)>> = BTreeMap::new();
+    let mut singles = Vec::new();
+
+    for tag in tags {
+        match tag.address.parse::<PlcAddress>() {
+            Ok(address) if address.is_block_readable_bit() && matches!(tag.data_type, None | Some(PlcElementType::Bool)) => {
+                files.entry(address.data_file()).or_default().push((tag.clone(), address));
+            }
+            _ => singles.push(tag.clone()),
+        }
+    }
+
+    let mut blocks = Vec::new();
+    for (data_file, tags) in files {
+        let start_element = tags.iter().map(|(_, address)| address.element).min().unwrap_or(0);
+        let end_element = tags.iter().map(|(_, address)| address.element).max().unwrap_or(0);
+        match (end_element - start_element).checked_add(1) {
+            Some(element_count) => blocks.push(BlockRead { data_file, start_element, element_count, tags }),
+            None => singles.extend(tags.into_iter().map(|(tag, _)| tag)),
+        }
+    }
+
+    (blocks, singles)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tag(tag_name: &str, address: &str) -> DockPlcTag {
+        serde_json::from_value(serde_json::json!({ "tag_name": tag_name, "address": address })).unwrap()
+    }
+
+    fn address(address: &str) -> PlcAddress {
+        address.parse().unwrap()
+    }
+
+    #[test]
+    fn bits_are_grouped_by_file_from_their_lowest_element() {
+        let tags = vec![
+            tag("RH_DOOR_OPEN", "B9:6/2"),
+            tag("TRAILER_AT_DOOR", "B9:4/1"),
+            tag("RH_DOCK_READY", "N7:1/0"),
+            tag("RH_ESTOP", "B3/35"),
+            tag("TRAILER_ANGLE", "F8:0"),
+            tag("RH_MANUAL_MODE", "Manual_Mode"),
+        ];
+
+        let (blocks, singles) = plan_block_reads(&tags);
+
+        let spans: Vec<_> = blocks.iter()
+            .map(|block| (block.tag_name(), block.element_count, block.tags.len()))
+            .collect();
+        assert_eq!(spans, vec![("B3:2".to_string(), 1, 1), ("B9:4".to_string(), 3, 2), ("N7:1".to_string(), 1, 1)]);
+        let singles: Vec<_> = singles.iter().map(|tag| tag.tag_name.as_str()).collect();
+        assert_eq!(singles, vec!["TRAILER_ANGLE", "RH_MANUAL_MODE"]);
+    }
+
+    #[test]
+    fn bits_are_decoded_relative_to_the_start_of_the_block() {
+        let (blocks, _) = plan_block_reads(&[tag("TRAILER_AT_DOOR", "B9:4/1"), tag("RH_DOOR_OPEN", "B9:6/2")]);
+        let block = &blocks[0];
+        let words = [0b10, 0, 0b100];
+
+        assert_eq!(block.decode_bit(&words, &address("B9:4/1")), Some(1));
+        assert_eq!(block.decode_bit(&words, &address("B9:4/0")), Some(0));
+        assert_eq!(block.decode_bit(&words, &address("B9:6/2")), Some(1));
+        assert_eq!(block.decode_bit(&words, &address("B9:3/0")), None);
+        assert_eq!(block.decode_bit(&words, &address("B9:7/0")), None);
+    }
+
+    #[test]
+    fn files_spanning_more_than_one_read_are_read_per_tag() {
+        let (blocks, singles) = plan_block_reads(&[tag("TRAILER_AT_DOOR", "B9:0/0"), tag("RH_DOOR_OPEN", "B9:65535/0")]);
+
+        assert!(blocks.is_empty());
+        assert_eq!(singles.len(), 2);
+    }
+}
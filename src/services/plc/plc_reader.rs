@@ -1,6 +1,9 @@
+use std::sync::Arc;
 use tokio::task;
 use std::time::Duration;
+use crate::config::PlcElementType;
 use crate::errors::DockManagerError;
+use crate::models::SensorValue;
 
 /// # PlcReader
 ///
@@ -20,8 +23,8 @@ use crate::errors::DockManagerError;
 ///
 /// ```rust
 /// let reader = PlcReader::new(5000);
-/// let tag = PlcTagFactory::create_tag("192.168.1.100", "Tag1", 5000)?;
-/// let value = reader.read_tag(tag).await?;
+/// let tag = Arc::new(PlcTagFactory::create_tag(&connection, "Tag1", None, 5000)?);
+/// let value = reader.read_tag(tag, None).await?;
 /// ```
 pub struct PlcReader {
     pub timeout_ms: u64,
@@ -49,24 +52,28 @@ impl PlcReader {
 
     /// Reads a value from a PLC tag.
     ///
-    /// This method attempts to read an 8-bit unsigned integer value from the given PLC tag.
+    /// This method reads the value of the given PLC tag using the getter matching its element type:
+    /// `Bool` tags are decoded from their first bit, `Sint`, `Int` and `Dint` tags as signed integers and `Real` tags
+    /// as floats. Tags without an element type are read as an 8-bit unsigned integer, reported as an integer value.
     /// It uses Tokio's `spawn_blocking` to perform the blocking PLC read operation in a separate thread,
     /// and implements a timeout to prevent indefinite blocking.
     ///
     /// # Arguments
     ///
-    /// * `tag`: A `RawTag` instance representing the PLC tag to read from.
+    /// * `tag`: A shared `RawTag` handle representing the PLC tag to read from. Handles are usually
+    ///   obtained from the `PlcTagCache` so they can be reused across polling cycles.
+    /// * `element_type`: The element type of the tag, if configured for the door.
     ///
     /// # Returns
     ///
-    /// Returns a `Result<u8, DockManagerError>`:
-    /// - `Ok(u8)`: The successfully read 8-bit unsigned integer value.
+    /// Returns a `Result<SensorValue, DockManagerError>`:
+    /// - `Ok(SensorValue)`: The successfully read, typed value.
     /// - `Err(DockManagerError)`: An error if the read operation fails or times out.
     ///
     /// # Errors
     ///
     /// This function will return an error if:
-    /// - The PLC read operation fails.
+    /// - The PLC read operation fails or reports an error status.
     /// - The spawned task panics.
     /// - The operation times out.
     ///
@@ -75,28 +82,66 @@ impl PlcReader {
     /// This method is typically called in the `read_sensor` method of `PlcService`:
     ///
     /// ```rust
-    /// let value = reader.read_tag(tag).await?;
+    /// let value = reader.read_tag(tag, None).await?;
     /// ```
     ///
     /// # Example
     ///
     /// ```rust
     /// let reader = PlcReader::new(5000);
-    /// let tag = PlcTagFactory::create_tag("192.168.1.100", "Tag1", 5000)?;
-    /// let value = reader.read_tag(tag).await?;
+    /// let tag = Arc::new(PlcTagFactory::create_tag(&connection, "Tag1", None, 5000)?);
+    /// let value = reader.read_tag(tag, None).await?;
     /// println!("Read value: {}", value);
     /// ```
-    pub async fn read_tag(&self, tag: plctag::RawTag) -> Result<u8, DockManagerError> {
+    pub async fn read_tag(&self, tag: Arc<plctag::RawTag>, element_type: Option<PlcElementType>) -> Result<SensorValue, DockManagerError> {
         let timeout = Duration::from_millis(self.timeout_ms);
         let timeout_ms = self.timeout_ms;
 
         tokio::time::timeout(timeout, task::spawn_blocking(move || {
-            tag.read(timeout_ms as u32);
-            tag.get_u8(0)
+            tag.read(timeout_ms as u32).into_result()?;
+            match element_type {
+                Some(PlcElementType::Bool) => tag.get_bit(0).map(SensorValue::Bool),
+                Some(PlcElementType::Sint) => tag.get_i8(0).map(|value| SensorValue::Int(i64::from(value))),
+                Some(PlcElementType::Int) => tag.get_i16(0).map(|value| SensorValue::Int(i64::from(value))),
+                Some(PlcElementType::Dint) => tag.get_i32(0).map(|value| SensorValue::Int(i64::from(value))),
+                Some(PlcElementType::Real) => tag.get_f32(0).map(|value| SensorValue::Float(f64::from(value))),
+                None => tag.get_u8(0).map(SensorValue::from),
+            }
         }))
             .await
             .map_err(|_| DockManagerError::PlcError("PLC read operation timed out".to_string()))?
             .map_err(|e| DockManagerError::PlcError(format!("Task join error: {}", e)))?
             .map_err(|e| DockManagerError::PlcError(format!("Failed to read tag value: {}", e)))
     }
-}
\ No newline at end of file
+
+    /// Reads a block of consecutive 16-bit words from a PLC tag.
+    ///
+    /// The tag is expected to have been created with `PlcTagFactory::create_block_tag`, so that a single
+    /// read returns every element of interest in a data file. The individual bits are decoded by the caller.
+    ///
+    /// # Arguments
+    ///
+    /// * `tag`: A shared `RawTag` handle covering the block.
+    /// * `element_count`: The number of 16-bit words to return.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result<Vec<u16>, DockManagerError>`:
+    /// - `Ok(Vec<u16>)`: The words read, in element order.
+    /// - `Err(DockManagerError)`: An error if the read operation fails or times out.
+    pub async fn read_words(&self, tag: Arc<plctag::RawTag>, element_count: u16) -> Result<Vec<u16>, DockManagerError> {
+        let timeout = Duration::from_millis(self.timeout_ms);
+        let timeout_ms = self.timeout_ms;
+
+        tokio::time::timeout(timeout, task::spawn_blocking(move || {
+            tag.read(timeout_ms as u32).into_result()?;
+            (0..element_count as u32)
+                .map(|element| tag.get_u16(element * 2))
+                .collect::<Result<Vec<u16>, _>>()
+        }))
+            .await
+            .map_err(|_| DockManagerError::PlcError("PLC block read operation timed out".to_string()))?
+            .map_err(|e| DockManagerError::PlcError(format!("Task join error: {}", e)))?
+            .map_err(|e| DockManagerError::PlcError(format!("Failed to read block values: {}", e)))
+    }
+}
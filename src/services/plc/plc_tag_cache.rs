@@ -0,0 +1,128 @@
+use std::sync::Arc;
+use dashmap::DashMap;
+use log::{debug, info};
+use plctag::RawTag;
+use crate::config::PlcElementType;
+use crate::errors::DockManagerResult;
+use crate::services::plc::plc_address::BlockRead;
+use crate::services::plc::plc_tag_factory::{PlcConnection, PlcTagFactory};
+
+/// # PlcTagCache
+///
+/// Keeps PLC tag handles alive across polling cycles so that each door/address pair only opens its
+/// EtherNet/IP session once, instead of building a new `RawTag` for every read.
+///
+/// Handles are keyed by the door's IP address and the tag address. A handle that fails to read should be
+/// evicted with `evict`, so the next read recreates it. Dropping a handle destroys the underlying libplctag tag;
+/// `close_all` does this for every cached handle and is called when the application shuts down.
+///
+/// Handles are shared as `Arc<RawTag>`, so a handle evicted while a read is still in flight is only
+/// destroyed once that read completes.
+#[derive(Default)]
+pub struct PlcTagCache {
+    /// The cached tag handles, keyed by (door IP, tag address)
+    tags: DashMap<(String, String), Arc<RawTag>>,
+}
+
+impl PlcTagCache {
+    /// Creates a new, empty `PlcTagCache`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the cached handle for a door/address pair, creating it if necessary
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The `PlcConnection` of the door's PLC
+    /// * `address`: The address of the tag in the PLC
+    /// * `element_type`: The data type of the tag, used if the tag has to be created
+    /// * `timeout_ms`: The timeout used if the tag has to be created, in milliseconds
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Arc<RawTag>)`: The cached or newly created handle
+    /// * `Err(DockManagerError)`: If a new tag could not be created
+    pub fn get_or_create(
+        &self,
+        connection: &PlcConnection,
+        address: &str,
+        element_type: Option<PlcElementType>,
+        timeout_ms: u64,
+    ) -> DockManagerResult<Arc<RawTag>> {
+        self.get_or_create_with(&connection.gateway, address, || {
+            PlcTagFactory::create_tag(connection, address, element_type, timeout_ms)
+        })
+    }
+
+    /// Returns the cached handle for a block read, creating it if necessary
+    ///
+    /// Block handles are cached under the block's tag name suffixed with its element count (e.g. `B9:0[2]`),
+    /// so they never collide with single-element handles for the same address.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The `PlcConnection` of the door's PLC
+    /// * `block`: The block read the handle is for
+    /// * `timeout_ms`: The timeout used if the tag has to be created, in milliseconds
+    pub fn get_or_create_block(&self, connection: &PlcConnection, block: &BlockRead, timeout_ms: u64) -> DockManagerResult<Arc<RawTag>> {
+        let tag_name = block.tag_name();
+        self.get_or_create_with(&connection.gateway, &Self::block_key(block), || {
+            PlcTagFactory::create_block_tag(connection, &tag_name, block.element_count, timeout_ms)
+        })
+    }
+
+    /// Removes the handle for a block read so that the next read recreates it
+    pub fn evict_block(&self, door_ip: &str, block: &BlockRead) {
+        self.evict(door_ip, &Self::block_key(block));
+    }
+
+    /// The cache key used for a block read
+    fn block_key(block: &BlockRead) -> String {
+        format!("{}[{}]", block.tag_name(), block.element_count)
+    }
+
+    /// Returns the handle cached under a key, creating it with `create` if necessary
+    fn get_or_create_with<F>(&self, door_ip: &str, key_address: &str, create: F) -> DockManagerResult<Arc<RawTag>>
+    where
+        F: FnOnce() -> DockManagerResult<RawTag>,
+    {
+        let key = (door_ip.to_string(), key_address.to_string());
+        if let Some(tag) = self.tags.get(&key) {
+            return Ok(Arc::clone(tag.value()));
+        }
+
+        let tag = Arc::new(create()?);
+        debug!("Created PLC tag handle for {} on {}", key_address, door_ip);
+        Ok(Arc::clone(self.tags.entry(key).or_insert(tag).value()))
+    }
+
+    /// Removes the handle for a door/address pair so that the next read recreates it
+    ///
+    /// # Arguments
+    ///
+    /// * `door_ip`: The IP address of the PLC
+    /// * `address`: The address of the tag in the PLC
+    pub fn evict(&self, door_ip: &str, address: &str) {
+        if self.tags.remove(&(door_ip.to_string(), address.to_string())).is_some() {
+            debug!("Evicted PLC tag handle for {} on {}", address, door_ip);
+        }
+    }
+
+    /// Destroys every cached handle
+    pub fn close_all(&self) {
+        let count = self.tags.len();
+        self.tags.clear();
+        info!("Closed {} cached PLC tag handles", count);
+    }
+
+    /// Returns the number of cached handles
+    pub fn len(&self) -> usize {
+        self.tags.len()
+    }
+
+    /// Returns `true` if no handles are cached
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty()
+    }
+}
@@ -1,7 +1,62 @@
 use plctag::builder::*;
 use plctag::RawTag;
+use crate::config::{DockDoorConfig, PlcElementType, PlcFamily};
 use crate::errors::DockManagerError;
 
+/// # PlcConnection
+///
+/// Describes how to reach the PLC of a single dock door: its address, PLC family, routing path
+/// and the element type of its tags. Built from the door's `DockDoorConfig`, falling back to the
+/// MicroLogix defaults the dock doors have historically used.
+#[derive(Debug, Clone)]
+pub struct PlcConnection {
+    /// The IP address (or host name) of the PLC
+    pub gateway: String,
+    /// The PLC family
+    pub family: PlcFamily,
+    /// The routing path to the PLC, if the family needs one
+    pub path: Option<String>,
+    /// The element type of the door's tags, if configured
+    pub element_type: Option<PlcElementType>,
+}
+
+impl PlcConnection {
+    /// Builds the connection description for a dock door
+    ///
+    /// When no path is configured, MicroLogix doors use "0" and the Logix families use "1,0"
+    /// (backplane, CPU in slot 0). Micro800 controllers never take a path.
+    pub fn from_door(door: &DockDoorConfig) -> Self {
+        let family = door.plc_kind.unwrap_or_default();
+        let path = match (family, &door.plc_path) {
+            (PlcFamily::Micro800, _) => None,
+            (_, Some(path)) => Some(path.clone()),
+            (PlcFamily::MicroLogix, None) => Some("0".to_string()),
+            (PlcFamily::ControlLogix | PlcFamily::CompactLogix | PlcFamily::LogixPccc, None) => Some("1,0".to_string()),
+            (_, None) => None,
+        };
+
+        Self {
+            gateway: door.dock_ip.clone(),
+            family,
+            path,
+            element_type: door.element_type,
+        }
+    }
+
+    /// The libplctag PLC kind for the connection's family
+    fn plc_kind(&self) -> PlcKind {
+        match self.family {
+            PlcFamily::MicroLogix => PlcKind::MicroLogix,
+            PlcFamily::Slc500 => PlcKind::SLC500,
+            PlcFamily::Plc5 => PlcKind::PLC5,
+            PlcFamily::LogixPccc => PlcKind::LogixPCCC,
+            PlcFamily::ControlLogix | PlcFamily::CompactLogix => PlcKind::ControlLogix,
+            PlcFamily::Micro800 => PlcKind::Micro800,
+            PlcFamily::Omron => PlcKind::Omron,
+        }
+    }
+}
+
 /// # PlcTagFactory
 ///
 /// A factory struct for creating PLC (Programmable Logic Controller) tags.
@@ -16,7 +71,8 @@ use crate::errors::DockManagerError;
 /// ## Example
 ///
 /// ```rust
-/// let tag = PlcTagFactory::create_tag("192.168.1.100", "Tag1", 5000)?;
+/// let connection = PlcConnection::from_door(&door_config);
+/// let tag = PlcTagFactory::create_tag(&connection, "Tag1", None, 5000)?;
 /// ```
 pub struct PlcTagFactory;
 
@@ -28,8 +84,9 @@ impl PlcTagFactory {
     ///
     /// # Arguments
     ///
-    /// * `door_ip`: A string slice containing the IP address of the PLC.
+    /// * `connection`: The `PlcConnection` describing the door's PLC.
     /// * `plc_tag_address`: A string slice representing the address of the tag in the PLC.
+    /// * `element_type`: The data type of the tag; when absent, the door's element type (or a single byte) is used.
     /// * `timeout_ms`: The timeout for PLC operations in milliseconds.
     ///
     /// # Returns
@@ -49,21 +106,20 @@ impl PlcTagFactory {
     /// This method is typically called in the `read_sensor` method of `PlcService`:
     ///
     /// ```rust
-    /// let tag = PlcTagFactory::create_tag(door_ip, plc_tag_address, reader.timeout_ms)?;
+    /// let tag = PlcTagFactory::create_tag(&connection, plc_tag_address, sensor.data_type, reader.timeout_ms)?;
     /// ```
     ///
     /// # Example
     ///
     /// ```rust
-    /// let tag = PlcTagFactory::create_tag("192.168.1.100", "Tag1", 5000)?;
+    /// let tag = PlcTagFactory::create_tag(&connection, "Tag1", Some(PlcElementType::Real), 5000)?;
     /// // Use the tag for PLC communication
     /// ```
     ///
-    /// # TODO
+    /// # Validation
     ///
-    /// - Consider adding support for different PLC types beyond MicroLogix.
-    /// - Implement a caching mechanism for frequently used tags to improve performance.
-    /// - Add validation for the `plc_tag_address` format to catch configuration errors early.
+    /// The `plc_tag_address` format is not checked here; `Settings::validate` checks every configured address
+    /// at startup.
     ///
     /// # Safety
     ///
@@ -72,22 +128,66 @@ impl PlcTagFactory {
     ///
     /// # Performance Considerations
     ///
-    /// Tag creation can be a relatively expensive operation. If the same tags are used frequently,
-    /// consider implementing a caching mechanism to reuse tag instances where possible.
+    /// Tag creation can be a relatively expensive operation, as each tag opens its own session with the PLC.
+    /// Tags that are read repeatedly should be obtained through the `PlcTagCache` instead of being created per read.
     pub fn create_tag(
-        door_ip: &str,
+        connection: &PlcConnection,
         plc_tag_address: &str,
+        element_type: Option<PlcElementType>,
         timeout_ms: u64
     ) -> Result<RawTag, DockManagerError> {
-        let path = PathBuilder::default()
+        let element_size = element_type.or(connection.element_type).map(|t| t.size()).unwrap_or(1);
+        Self::build_tag(connection, plc_tag_address, element_size, 1, timeout_ms)
+    }
+
+    /// Creates a PLC tag spanning several consecutive 16-bit elements of a data file.
+    ///
+    /// This is used to fetch every configured bit of a data file (e.g. `B9`) with a single read,
+    /// instead of one read per bit.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The `PlcConnection` describing the door's PLC, which must use data table addresses.
+    /// * `block_address`: The address of the first element to read (e.g. `B9:0`).
+    /// * `element_count`: The number of 16-bit elements to read.
+    /// * `timeout_ms`: The timeout for PLC operations in milliseconds.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result<RawTag, DockManagerError>`:
+    /// - `Ok(RawTag)`: A successfully created `RawTag` instance.
+    /// - `Err(DockManagerError)`: An error if tag creation fails.
+    pub fn create_block_tag(
+        connection: &PlcConnection,
+        block_address: &str,
+        element_count: u16,
+        timeout_ms: u64
+    ) -> Result<RawTag, DockManagerError> {
+        Self::build_tag(connection, block_address, 2, element_count as usize, timeout_ms)
+    }
+
+    /// Builds the libplctag attribute string for a tag and creates it
+    fn build_tag(
+        connection: &PlcConnection,
+        name: &str,
+        element_size: usize,
+        element_count: usize,
+        timeout_ms: u64
+    ) -> Result<RawTag, DockManagerError> {
+        let mut builder = PathBuilder::default();
+        builder
             .protocol(Protocol::EIP)
-            .gateway(door_ip)
-            .plc(PlcKind::MicroLogix)
-            .name(plc_tag_address)
-            .element_size(1)
-            .element_count(1)
-            .path("0")
-            .read_cache_ms(0)
+            .gateway(&connection.gateway)
+            .plc(connection.plc_kind())
+            .name(name)
+            .element_size(element_size)
+            .element_count(element_count)
+            .read_cache_ms(0);
+        if let Some(path) = &connection.path {
+            builder.path(path);
+        }
+
+        let path = builder
             .build()
             .map_err(|e| DockManagerError::PlcError(format!("Failed to build PLC path: {:?}", e)))?;
 
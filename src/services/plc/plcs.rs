@@ -1,13 +1,33 @@
+use std::future::Future;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
-use log::{info, error};
+use log::{debug, info, warn, error};
 use futures::future::join_all;
-use crate::models::PlcVal;
+use crate::models::{DoorReadStatus, ModbusAddress, PlcVal, SensorReadings, SensorValue};
 use crate::errors::{DockManagerError, DockManagerResult};
-use crate::config::Settings;
-use tokio::time::timeout;
-use crate::services::plc::plc_tag_factory::PlcTagFactory;
+use crate::config::{DockPlcTag, PlantSettings, PlcElementType, PlcProtocol, PlcTiming, Settings};
+use crate::services::plc::plc_address::{plan_block_reads, BlockRead};
+use crate::services::plc::modbus::{ModbusClientCache, ModbusConnection};
+use crate::services::plc::plc_tag_cache::PlcTagCache;
+use crate::services::plc::plc_tag_factory::PlcConnection;
 use crate::services::plc::plc_reader::PlcReader;
+use crate::services::plc::sensor_source::SensorSource;
+use async_trait::async_trait;
+
+/// Everything needed to read the tags of a single door during a polling cycle
+#[derive(Clone, Copy)]
+struct DoorPoll<'a> {
+    /// The `PlcReader` configured with the plant's timeout
+    reader: &'a PlcReader,
+    /// The plant's polling settings
+    timing: &'a PlcTiming,
+    /// The ID of the plant the door belongs to
+    plant_id: &'a str,
+    /// The name of the door
+    door_name: &'a str,
+    /// The `PlcConnection` of the PLC controlling the door
+    connection: &'a PlcConnection,
+}
 
 // noinspection all
 /// # PlcService
@@ -15,37 +35,38 @@ use crate::services::plc::plc_reader::PlcReader;
 /// The `PlcService` struct is responsible for managing communications with Programmable Logic Controllers (PLCs)
 /// in the dock door management system. It handles sensor polling and data retrieval from the PLCs.
 ///
+/// Timeouts, retries and the retry backoff come from `PlcSettings`, with any per-plant overrides applied
+/// (see `PlcSettings::timing_for`), so they can be tuned through configuration.
+///
 /// ## Fields
 ///
-/// * `reader`: An `Arc<PlcReader>` that provides thread-safe access to the PLC reading functionality.
-/// * `max_retries`: The maximum number of retry attempts for reading sensor data.
+/// * `tag_cache`: An `Arc<PlcTagCache>` holding the tag handles reused across polling cycles.
+/// * `modbus_clients`: An `Arc<ModbusClientCache>` holding the connections to doors read over Modbus TCP.
 ///
 /// ## Usage
 ///
 /// The `PlcService` is typically instantiated in the `main.rs` file or a central service manager.
-/// It's used by the `DockDoorController` to periodically poll sensor data from the PLCs.
+/// It's used by the `DockDoorController`, through the `SensorSource` trait, to periodically poll sensor data from the PLCs.
 ///
 /// ## Example
 ///
 /// ```rust
 /// let plc_service = PlcService::new();
-/// let sensor_data = plc_service.poll_sensors(&settings).await?;
+/// let readings = plc_service.poll_sensors(&settings).await?;
 /// ```
 #[derive(Clone)]
 pub struct PlcService {
-    /// Provides thread-safe access to the PLC reading functionality
-    reader: Arc<PlcReader>,
-    /// The maximum number of retry attempts for reading sensor data
-    max_retries: u32,
+    /// The tag handles reused across polling cycles, keyed by door IP and tag address
+    tag_cache: Arc<PlcTagCache>,
+    /// The Modbus TCP connections reused across polling cycles, keyed by controller
+    modbus_clients: Arc<ModbusClientCache>,
 }
 
 impl PlcService {
     // noinspection all
     /// Creates a new instance of `PlcService`.
     ///
-    /// This method initializes a new `PlcService` with default configurations:
-    /// - A `PlcReader` with a 5000ms timeout
-    /// - A maximum of 3 retry attempts for sensor reading
+    /// This method initializes a new `PlcService` with an empty `PlcTagCache` and `ModbusClientCache`.
     ///
     /// # Returns
     ///
@@ -58,118 +79,344 @@ impl PlcService {
     /// ```
     pub fn new() -> Self {
         Self {
-            reader: Arc::new(PlcReader::new(5000)),
-            max_retries: 3,
+            tag_cache: Arc::new(PlcTagCache::new()),
+            modbus_clients: Arc::new(ModbusClientCache::new()),
         }
     }
 
-    /// Polls sensors across all plants and collects their values
+    /// Destroys every cached PLC tag handle and Modbus connection, closing the underlying sessions
+    pub fn close_tags(&self) {
+        self.tag_cache.close_all();
+        self.modbus_clients.close_all();
+    }
+
+    /// Polls the sensors of every door of a plant and collects their values
     ///
-    /// This method iterates through all configured plants, their associated doors, and sensors
-    /// For each sensor, it attempts to read its value from the PLC using the `read_sensor` method
-    /// The collected sensor values are returned as a vector of `PlcVal`
+    /// This method reads each of the plant's doors concurrently
+    /// Doors configured with the `modbus_tcp` protocol are read over Modbus TCP using `read_modbus_door`
+    /// When `plc.block_reads` is enabled, the bit tags of a door are grouped by data file and each file is fetched
+    /// with a single multi-element read; any other tag is read on its own using the `read_sensor` method
+    /// Failed reads are retried according to the plant's `PlcTiming`
+    /// The collected sensor values are returned along with a `DoorReadStatus` per door, recording which sensors
+    /// could not be read and how long the door took to read
     ///
     /// # Arguments
-    /// * `settings`: The application settings containing plant and sensor configurations
+    /// * `settings`: The application settings containing the global PLC settings
+    /// * `plant`: The plant whose doors are polled
     ///
     /// # Returns
-    /// * `Ok(Vec<PlcVal>)`: The collected sensor values
+    /// * `Ok(SensorReadings)`: The collected sensor values and door read statuses
     /// * `Err(DockManagerError)`: If there's an error during sensor polling or task joining
-    pub async fn poll_sensors(&self, settings: &Settings) -> DockManagerResult<Vec<PlcVal>> {
-        let start = Instant::now();
-        let mut all_plc_values = Vec::new();
-        let _ = self.max_retries.clone();
-
-        for plant in &settings.plants {
-            let plant_start = Instant::now();
-            let plant_id = plant.plant_id.clone();
-
-            info!("Starting sensor polling for plant {} with {} doors", plant_id, plant.dock_doors.dock_door_config.len());
-
-            let sensor_futures: Vec<_> = plant.dock_doors.dock_door_config.iter()
-                .flat_map(|door| {
-                    plant.dock_doors.dock_plc_tags.iter().map({
-                        let plant = plant_id.clone();
-                        move |sensor| {
-                            let reader = Arc::clone(&self.reader);
-                            let plant_id = plant.clone();
-                            let door_name = door.dock_name.clone();
-                            let door_ip = door.dock_ip.clone();
-                            let sensor_name = sensor.tag_name.clone();
-                            let address = sensor.address.clone();
-
-                            tokio::spawn(async move {
-                                timeout(Duration::from_secs(1), Self::read_sensor(
-                                    reader,
-                                    plant_id,
-                                    door_name,
-                                    door_ip,
-                                    sensor_name,
-                                    address,
-                                )).await
-                            })
-                        }
-                    })
+    pub async fn poll_plant(&self, settings: &Settings, plant: &PlantSettings) -> DockManagerResult<SensorReadings> {
+        let plant_start = Instant::now();
+        let plant_id = plant.plant_id.clone();
+        let timing = settings.plc.timing_for(plant);
+        let reader = Arc::new(PlcReader::new(timing.timeout_ms));
+        let mut readings = SensorReadings::default();
+
+        info!("Starting sensor polling for plant {} with {} doors", plant_id, plant.dock_doors.dock_door_config.len());
+
+        let (blocks, singles) = if settings.plc.block_reads {
+            plan_block_reads(&plant.dock_doors.dock_plc_tags)
+        } else {
+            (Vec::new(), plant.dock_doors.dock_plc_tags.clone())
+        };
+        let blocks = Arc::new(blocks);
+        let singles = Arc::new(singles);
+        let all_tags = Arc::new(plant.dock_doors.dock_plc_tags.clone());
+
+        let door_futures: Vec<_> = plant.dock_doors.dock_door_config.iter()
+            .map(|door| {
+                let service = self.clone();
+                let reader = Arc::clone(&reader);
+                let plant_id = plant_id.clone();
+                let door_name = door.dock_name.clone();
+
+                if door.protocol.unwrap_or_default() == PlcProtocol::ModbusTcp {
+                    let connection = ModbusConnection::from_door(door);
+                    let tags = Arc::clone(&all_tags);
+                    return tokio::spawn(async move {
+                        let door_start = Instant::now();
+                        let results = service.read_modbus_door(&timing, &plant_id, &door_name, &connection, &tags).await;
+                        (door_name.clone(), results, door_start.elapsed())
+                    });
+                }
+
+                let connection = PlcConnection::from_door(door);
+                // Block reads only apply to data table addresses; symbolic tags are always read one by one
+                let (blocks, singles) = if connection.family.uses_data_table_addresses() {
+                    (Arc::clone(&blocks), Arc::clone(&singles))
+                } else {
+                    (Arc::new(Vec::new()), Arc::clone(&all_tags))
+                };
+
+                tokio::spawn(async move {
+                    let door = DoorPoll {
+                        reader: &reader,
+                        timing: &timing,
+                        plant_id: &plant_id,
+                        door_name: &door_name,
+                        connection: &connection,
+                    };
+                    let door_start = Instant::now();
+                    let results = service.read_door(&door, &blocks, &singles).await;
+                    (door_name.clone(), results, door_start.elapsed())
                 })
-                .collect();
+            })
+            .collect();
 
-            let results = join_all(sensor_futures).await;
+        let results = join_all(door_futures).await;
 
-            for result in results {
-                match result {
-                    Ok(Ok(Ok(plc_val))) => all_plc_values.push(plc_val),
-                    Ok(Ok(Err(e))) => info!("Sensor read error: {:?}", e),
-                    Ok(Err(_)) => info!("Sensor read timed out"),
-                    Err(e) => error!("Task join error: {:?}", e),
+        for result in results {
+            match result {
+                Ok((door_name, door_results, latency)) => {
+                    let mut status = DoorReadStatus {
+                        plant_id: plant_id.clone(),
+                        door_name,
+                        latency: Some(latency),
+                        ..Default::default()
+                    };
+                    for (sensor, door_result) in door_results {
+                        match door_result {
+                            Ok(plc_val) => {
+                                status.successful_reads += 1;
+                                readings.values.push(plc_val);
+                            }
+                            Err(e) => {
+                                warn!("Sensor read error: {:?}", e);
+                                status.failed_sensors.push(sensor);
+                                status.last_error = Some(e.to_string());
+                            }
+                        }
+                    }
+                    readings.doors.push(status);
                 }
+                Err(e) => error!("Task join error: {:?}", e),
             }
+        }
+
+        info!("Completed sensor polling for plant {} in {:?}", plant_id, plant_start.elapsed());
+        Ok(readings)
+    }
+
+    /// Reads every configured sensor of a single door
+    ///
+    /// Block reads are issued first, one per data file, followed by the individual reads, which run concurrently
+    ///
+    /// # Arguments
+    ///
+    /// * `door`: The door being read
+    /// * `blocks`: The block reads planned for the door's tags
+    /// * `singles`: The tags that are read individually
+    ///
+    /// # Returns
+    ///
+    /// One entry per configured tag, pairing the tag name with either the read value or the error that prevented reading it
+    async fn read_door(
+        &self,
+        door: &DoorPoll<'_>,
+        blocks: &[BlockRead],
+        singles: &[DockPlcTag],
+    ) -> Vec<(String, DockManagerResult<PlcVal>)> {
+        let mut results = Vec::new();
 
-            info!("Completed sensor polling for plant {} in {:?}", plant_id, plant_start.elapsed());
+        for block in blocks {
+            let block_results = self.read_block(door, block).await;
+            results.extend(block.tags.iter().map(|(sensor, _)| sensor.tag_name.clone()).zip(block_results));
         }
 
-        info!("Completed polling all sensors in {:?}", start.elapsed());
-        Ok(all_plc_values)
+        let single_futures = singles.iter().map(|sensor| async move {
+            (sensor.tag_name.clone(), self.read_sensor(door, sensor).await)
+        });
+        results.extend(join_all(single_futures).await);
+
+        results
+    }
+
+    /// Reads a whole data file block from a door's PLC and decodes the configured bits
+    ///
+    /// Every failed attempt evicts the block's handle from the cache, so the next attempt recreates it
+    /// If the block still cannot be read after all retries, an error is returned for every tag in it
+    ///
+    /// # Arguments
+    ///
+    /// * `door`: The door being read
+    /// * `block`: The block to read
+    ///
+    /// # Returns
+    ///
+    /// One result per tag in the block
+    async fn read_block(&self, door: &DoorPoll<'_>, block: &BlockRead) -> Vec<DockManagerResult<PlcVal>> {
+        let DoorPoll { reader, timing, plant_id, door_name, connection } = *door;
+        let description = format!("data file {} on door '{}' in plant '{}'", block.data_file, door_name, plant_id);
+
+        let words = Self::with_retries(timing, &description, || async {
+            let result = async {
+                let tag = self.tag_cache.get_or_create_block(connection, block, timing.timeout_ms)?;
+                reader.read_words(tag, block.element_count).await
+            }.await;
+            if result.is_err() {
+                self.tag_cache.evict_block(&connection.gateway, block);
+            }
+            result
+        }).await;
+
+        let words = match words {
+            Ok(words) => words,
+            Err(e) => {
+                return block.tags.iter()
+                    .map(|(sensor, _)| Err(DockManagerError::PlcError(format!(
+                        "Failed to read {} (sensor '{}'): {:?}", description, sensor.tag_name, e
+                    ))))
+                    .collect();
+            }
+        };
+
+        block.tags.iter()
+            .map(|(sensor, address)| {
+                let is_bool = sensor.data_type.or(connection.element_type) == Some(PlcElementType::Bool);
+                block.decode_bit(&words, address)
+                    .map(|bit| if is_bool { SensorValue::Bool(bit == 1) } else { SensorValue::from(bit) })
+                    .map(|value| PlcVal::new(plant_id, door_name, &connection.gateway, &sensor.tag_name, value))
+                    .ok_or_else(|| DockManagerError::PlcError(format!(
+                        "Address {} of sensor '{}' is outside the block read from door '{}' in plant '{}'",
+                        sensor.address, sensor.tag_name, door_name, plant_id
+                    )))
+            })
+            .collect()
     }
 
     /// Attempts to read a sensor value from a PLC with retries
     ///
-    /// This method creates a PLC tag using the `PlcTagFactory` and then tries to read its value using the `PlcReader`
-    /// If the read fails, it retries up to `max_retries` times with a 2-second delay between attempts
+    /// This method takes the PLC tag from the `PlcTagCache` (creating it on first use) and then tries to read its value using the `PlcReader`
+    /// A tag that fails to read is evicted from the cache, so it is recreated on the next attempt
+    /// If the read fails, it retries up to `max_retries` times, waiting `retry_backoff_ms` before the first retry and doubling the delay after each one
     /// If all attempts fail it returns an error
     ///
     /// # Arguments
     ///
-    /// * `reader`: The `PlcReader` used for communication with the PLC
-    /// * `max_retries`: The maximum number of retry attempts
-    /// * `plant_id`: The ID of the plant where the sensor is located
-    /// * `door_name`: The name of the door associated with the sensor
-    /// * `door_ip`: The IP address of the PLC controlling the door
-    /// * `sensor`: The name of the sensor
-    /// * `plc_tag_address`: The PLC address of the sensor
+    /// * `door`: The door the sensor belongs to
+    /// * `sensor`: The configured PLC tag of the sensor; its data type, or else the door's, selects how the value is decoded,
+    ///   and doors addressed with symbolic tag names read it from its `symbolic_address` when set
     ///
     /// # Returns
     ///
     /// * `Ok(PlcVal)`: The read sensor value encapsulated in a `PlcVal` struct
     /// * `Err(DockManagerError)`: If the sensor read fails after all retries
-    async fn read_sensor(
-        reader: Arc<PlcReader>,
-        plant_id: String,
-        door_name: String,
-        door_ip: String,
-        sensor: String,
-        plc_tag_address: String
-    ) -> DockManagerResult<PlcVal> {
-        let tag = PlcTagFactory::create_tag(&door_ip, &plc_tag_address, 1000)
-            .map_err(|e| DockManagerError::PlcError(format!(
-                "Failed to create PLC tag for sensor '{}' (address: {}) on door '{}' in plant '{}': {:?}",
-                sensor, plc_tag_address, door_name, plant_id, e
-            )))?;
-
-        reader.read_tag(tag).await
-            .map(|value| PlcVal::new(&plant_id, &door_name, &door_ip, &sensor, value))
-            .map_err(|e| DockManagerError::PlcError(format!(
-                "Failed to read sensor '{}' (address: {}) on door '{}' in plant '{}': {:?}",
-                sensor, plc_tag_address, door_name, plant_id, e
-            )))
+    async fn read_sensor(&self, door: &DoorPoll<'_>, sensor: &DockPlcTag) -> DockManagerResult<PlcVal> {
+        let DoorPoll { reader, timing, plant_id, door_name, connection } = *door;
+        let plc_tag_address = sensor.ethernet_ip_address(connection.family);
+        let element_type = sensor.data_type.or(connection.element_type);
+        let description = format!(
+            "sensor '{}' (address: {}) on door '{}' in plant '{}'",
+            sensor.tag_name, plc_tag_address, door_name, plant_id
+        );
+
+        Self::with_retries(timing, &description, || async {
+            let tag = self.tag_cache.get_or_create(connection, plc_tag_address, element_type, timing.timeout_ms)
+                .map_err(|e| DockManagerError::PlcError(format!("Failed to create PLC tag for {}: {:?}", description, e)))?;
+
+            reader.read_tag(tag, element_type).await
+                .map(|value| PlcVal::new(plant_id, door_name, &connection.gateway, &sensor.tag_name, value))
+                .map_err(|e| {
+                    self.tag_cache.evict(&connection.gateway, plc_tag_address);
+                    DockManagerError::PlcError(format!("Failed to read {}: {:?}", description, e))
+                })
+        }).await
     }
-}
\ No newline at end of file
+
+    /// Reads every configured sensor of a door connected over Modbus TCP
+    ///
+    /// Each tag is read from its `modbus_address`, or its `address` if none is set; the reads share the door's
+    /// connection and are retried according to the plant's `PlcTiming`
+    ///
+    /// # Arguments
+    ///
+    /// * `timing`: The plant's polling settings
+    /// * `plant_id`: The ID of the plant the door belongs to
+    /// * `door_name`: The name of the door
+    /// * `connection`: The `ModbusConnection` of the door's controller
+    /// * `tags`: The tags configured for the door
+    ///
+    /// # Returns
+    ///
+    /// One entry per configured tag, pairing the tag name with either the read value or the error that prevented reading it
+    async fn read_modbus_door(
+        &self,
+        timing: &PlcTiming,
+        plant_id: &str,
+        door_name: &str,
+        connection: &ModbusConnection,
+        tags: &[DockPlcTag],
+    ) -> Vec<(String, DockManagerResult<PlcVal>)> {
+        let timeout = Duration::from_millis(timing.timeout_ms);
+        let mut results = Vec::with_capacity(tags.len());
+
+        for sensor in tags {
+            let raw_address = sensor.modbus_address.as_deref().unwrap_or(&sensor.address);
+            let element_type = sensor.data_type.or(connection.element_type);
+            let description = format!(
+                "sensor '{}' (Modbus address: {}) on door '{}' in plant '{}'",
+                sensor.tag_name, raw_address, door_name, plant_id
+            );
+
+            let result = match raw_address.parse::<ModbusAddress>() {
+                Ok(address) => Self::with_retries(timing, &description, || async {
+                    self.modbus_clients.read_value(connection, &address, element_type, timeout).await
+                        .map(|value| PlcVal::new(plant_id, door_name, &connection.host, &sensor.tag_name, value))
+                        .map_err(|e| DockManagerError::PlcError(format!("Failed to read {}: {}", description, e)))
+                }).await,
+                Err(e) => Err(e),
+            };
+            results.push((sensor.tag_name.clone(), result));
+        }
+
+        results
+    }
+
+    /// Runs a PLC operation, retrying it with exponential backoff when it fails
+    ///
+    /// # Arguments
+    ///
+    /// * `timing`: The polling settings providing `max_retries` and `retry_backoff_ms`
+    /// * `description`: A description of the operation, used in log messages
+    /// * `operation`: Produces a new attempt of the operation each time it is called
+    ///
+    /// # Returns
+    ///
+    /// The result of the first successful attempt, or the error of the last attempt
+    async fn with_retries<T, F, Fut>(timing: &PlcTiming, description: &str, mut operation: F) -> DockManagerResult<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = DockManagerResult<T>>,
+    {
+        let mut retry = 0;
+        loop {
+            match operation().await {
+                Ok(value) => return Ok(value),
+                Err(e) if retry < timing.max_retries => {
+                    let delay = timing.retry_backoff_ms.saturating_mul(1 << retry.min(16));
+                    debug!("Retrying {} in {}ms (retry {} of {}): {:?}", description, delay, retry + 1, timing.max_retries, e);
+                    tokio::time::sleep(Duration::from_millis(delay)).await;
+                    retry += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl SensorSource for PlcService {
+    async fn poll_plant(&self, settings: &Settings, plant: &PlantSettings) -> DockManagerResult<SensorReadings> {
+        PlcService::poll_plant(self, settings, plant).await
+    }
+
+    async fn shutdown(&self) {
+        self.close_tags();
+    }
+
+    fn name(&self) -> &'static str {
+        "plctag"
+    }
+}
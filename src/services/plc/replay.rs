@@ -0,0 +1,257 @@
+//! # Replay Sensor Source
+//!
+//! This module provides a `SensorSource` that plays back `PlcVal` records previously written to a file,
+//! along with a `RecordingSensorSource` that wraps another source and records everything it produces.
+//!
+//! Recordings are stored as JSON lines, one `PlcVal` per line. During playback the records of each plant are
+//! split into polling cycles: a new cycle starts as soon as a sensor of a door shows up a second time.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use async_trait::async_trait;
+use chrono::Local;
+use log::{error, info};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+use crate::config::{PlantSettings, Settings};
+use crate::errors::{DockManagerError, DockManagerResult};
+use crate::models::{PlcVal, SensorReadings};
+use crate::services::plc::sensor_source::SensorSource;
+
+/// A `SensorSource` that replays a recorded stream of `PlcVal` records
+pub struct ReplaySensorSource {
+    /// The recorded polling cycles of each plant, in the order they were recorded
+    cycles: HashMap<String, Vec<Vec<PlcVal>>>,
+    /// The index of the next cycle to play, per plant
+    positions: Mutex<HashMap<String, usize>>,
+    /// Whether playback starts over once the end of the recording is reached
+    loop_playback: bool,
+}
+
+impl ReplaySensorSource {
+    /// Creates a new `ReplaySensorSource` from already loaded records
+    ///
+    /// # Arguments
+    ///
+    /// * `records`: The recorded sensor values, in recording order
+    /// * `loop_playback`: Whether playback starts over after the last cycle
+    pub fn new(records: Vec<PlcVal>, loop_playback: bool) -> Self {
+        let mut by_plant: HashMap<String, Vec<PlcVal>> = HashMap::new();
+        for record in records {
+            by_plant.entry(record.plant_id.clone()).or_default().push(record);
+        }
+
+        Self {
+            cycles: by_plant.into_iter()
+                .map(|(plant_id, records)| (plant_id, Self::split_cycles(records)))
+                .collect(),
+            positions: Mutex::new(HashMap::new()),
+            loop_playback,
+        }
+    }
+
+    /// Loads a recording from a JSON lines file
+    ///
+    /// # Arguments
+    ///
+    /// * `path`: The path of the recording
+    /// * `loop_playback`: Whether playback starts over after the last cycle
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(ReplaySensorSource)`: The source, ready to play the recording
+    /// * `Err(DockManagerError)`: If the file cannot be read or a line is not a valid `PlcVal`
+    pub fn from_file(path: &Path, loop_playback: bool) -> DockManagerResult<Self> {
+        let content = std::fs::read_to_string(path)?;
+        let records = content.lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(number, line)| serde_json::from_str::<PlcVal>(line).map_err(|e| DockManagerError::ConfigError(
+                format!("Invalid record on line {} of {}: {}", number + 1, path.display(), e)
+            )))
+            .collect::<DockManagerResult<Vec<_>>>()?;
+
+        let source = Self::new(records, loop_playback);
+        for (plant_id, cycles) in &source.cycles {
+            info!("Loaded {} recorded polling cycles for plant {} from {}", cycles.len(), plant_id, path.display());
+        }
+        Ok(source)
+    }
+
+    /// Splits a flat list of records into polling cycles
+    ///
+    /// A cycle ends as soon as a (door, sensor) combination that is already part of it shows up again.
+    fn split_cycles(records: Vec<PlcVal>) -> Vec<Vec<PlcVal>> {
+        let mut cycles = Vec::new();
+        let mut current: Vec<PlcVal> = Vec::new();
+        let mut seen = HashSet::new();
+
+        for record in records {
+            let key = (record.door_name.clone(), record.sensor_name.clone());
+            if !seen.insert(key.clone()) {
+                cycles.push(std::mem::take(&mut current));
+                seen.clear();
+                seen.insert(key);
+            }
+            current.push(record);
+        }
+        if !current.is_empty() {
+            cycles.push(current);
+        }
+        cycles
+    }
+
+    /// Plays the plant's next recorded cycle, stamped with the current time
+    ///
+    /// Doors missing from the recorded cycle are reported as failed reads, as they were when the cycle was recorded.
+    /// Once the plant's recording is exhausted an empty cycle, without any door statuses, is returned unless
+    /// `loop_playback` is set.
+    ///
+    /// # Arguments
+    ///
+    /// * `plant`: The plant whose next cycle is played
+    pub async fn next_cycle(&self, plant: &PlantSettings) -> SensorReadings {
+        let cycles = match self.cycles.get(&plant.plant_id) {
+            Some(cycles) if !cycles.is_empty() => cycles,
+            _ => return SensorReadings::default(),
+        };
+
+        let mut positions = self.positions.lock().await;
+        let position = positions.entry(plant.plant_id.clone()).or_insert(0);
+        if *position >= cycles.len() {
+            if !self.loop_playback {
+                return SensorReadings::default();
+            }
+            *position = 0;
+        }
+
+        let now = Local::now().naive_local();
+        let cycle = cycles[*position].iter()
+            .cloned()
+            .map(|mut value| {
+                value.timestamp = now;
+                value
+            })
+            .collect();
+        *position += 1;
+        SensorReadings::from_values(plant, cycle)
+    }
+}
+
+#[async_trait]
+impl SensorSource for ReplaySensorSource {
+    /// Returns the plant's next recorded cycle, see `ReplaySensorSource::next_cycle`
+    async fn poll_plant(&self, _settings: &Settings, plant: &PlantSettings) -> DockManagerResult<SensorReadings> {
+        Ok(self.next_cycle(plant).await)
+    }
+
+    fn name(&self) -> &'static str {
+        "replay"
+    }
+}
+
+/// A `SensorSource` decorator that appends every value produced by the wrapped source to a recording file
+pub struct RecordingSensorSource {
+    /// The source whose values are recorded
+    inner: Arc<dyn SensorSource>,
+    /// The recording file, in the format read by `ReplaySensorSource`
+    path: PathBuf,
+    /// Serializes writes to the recording file
+    write_lock: Mutex<()>,
+}
+
+impl RecordingSensorSource {
+    /// Creates a new `RecordingSensorSource`
+    ///
+    /// # Arguments
+    ///
+    /// * `inner`: The source whose values are recorded
+    /// * `path`: The file the values are appended to
+    pub fn new(inner: Arc<dyn SensorSource>, path: PathBuf) -> Self {
+        Self { inner, path, write_lock: Mutex::new(()) }
+    }
+
+    /// Appends a polling cycle to the recording file
+    async fn record(&self, values: &[PlcVal]) -> DockManagerResult<()> {
+        let mut lines = String::new();
+        for value in values {
+            lines.push_str(&serde_json::to_string(value)?);
+            lines.push('\n');
+        }
+
+        let _guard = self.write_lock.lock().await;
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await?;
+        file.write_all(lines.as_bytes()).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl SensorSource for RecordingSensorSource {
+    async fn poll_plant(&self, settings: &Settings, plant: &PlantSettings) -> DockManagerResult<SensorReadings> {
+        let readings = self.inner.poll_plant(settings, plant).await?;
+        if let Err(e) = self.record(&readings.values).await {
+            error!("Failed to record sensor values to {}: {:?}", self.path.display(), e);
+        }
+        Ok(readings)
+    }
+
+    async fn shutdown(&self) {
+        self.inner.shutdown().await;
+    }
+
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::test_support::{self, DOOR_NAME, PLANT_ID};
+    use crate::models::{DockDoorEvent, DoorState, SensorValue, TrailerState};
+    use crate::state_management::door_state_repository::DoorStateRepository;
+    use crate::state_management::sensor_data_processor::SensorDataProcessor;
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name)
+    }
+
+    #[tokio::test]
+    async fn recorded_trailer_arrival_replays_into_door_events() {
+        let plant = test_support::plant();
+        let repository = Arc::new(DoorStateRepository::new());
+        repository.update_door(PLANT_ID, test_support::door(&plant)).await.unwrap();
+        let processor = SensorDataProcessor::new(Arc::clone(&repository));
+        let source = ReplaySensorSource::from_file(&fixture("replay_trailer_arrival.jsonl"), false).unwrap();
+
+        let mut cycles = Vec::new();
+        loop {
+            let readings = source.next_cycle(&plant).await;
+            if readings.doors.is_empty() {
+                break;
+            }
+            assert!(readings.doors.iter().all(|door| door.failed_sensors.is_empty()), "{:?}", readings.doors);
+            cycles.push(processor.process_sensor_updates(readings.values).await.unwrap());
+        }
+
+        let cycles: Vec<&[DockDoorEvent]> = cycles.iter().map(Vec::as_slice).collect();
+        match cycles.as_slice() {
+            [first, [DockDoorEvent::SensorStateChanged(sensor), DockDoorEvent::TrailerStateChanged(trailer), DockDoorEvent::DoorStateChanged(door)], last] => {
+                assert!(first.is_empty(), "the first cycle only sets the initial values: {:?}", first);
+                assert!(last.is_empty(), "an unchanged cycle raises no events: {:?}", last);
+                assert_eq!(sensor.sensor_name, "TRAILER_AT_DOOR");
+                assert_eq!((sensor.old_value, sensor.new_value), (Some(SensorValue::Int(0)), Some(SensorValue::Int(1))));
+                assert_eq!((trailer.old_state, trailer.new_state), (TrailerState::Undocked, TrailerState::Docked));
+                assert_eq!((door.old_state, door.new_state), (DoorState::Unassigned, DoorState::TrailerDocked));
+            }
+            other => panic!("unexpected events: {:?}", other),
+        }
+        assert_eq!(repository.get_door_state(PLANT_ID, DOOR_NAME).await.unwrap().door_state, DoorState::TrailerDocked);
+    }
+}
@@ -0,0 +1,54 @@
+//! # Sensor Sources
+//!
+//! This module defines the `SensorSource` trait, the abstraction the polling cycle uses to obtain sensor values.
+//! The production implementation is `PlcService`, which talks to the dock door PLCs through libplctag.
+//! The `SimulatedSensorSource` and `ReplaySensorSource` implementations allow the rest of the pipeline
+//! (state management, rules, alerting) to run without any PLC on the network.
+
+use async_trait::async_trait;
+use crate::config::{PlantSettings, Settings};
+use crate::errors::DockManagerResult;
+use crate::models::SensorReadings;
+
+/// Defines a source of sensor values for the dock doors configured in the settings
+#[async_trait]
+pub trait SensorSource: Send + Sync {
+    /// Polls the sensors of every door of a single plant
+    ///
+    /// # Arguments
+    ///
+    /// * `settings`: The application settings, including the global PLC settings
+    /// * `plant`: The plant whose doors are polled
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(SensorReadings)`: The sensor values produced during this polling cycle, along with the read status of each door
+    /// * `Err(DockManagerError)`: If the source is unable to produce values
+    async fn poll_plant(&self, settings: &Settings, plant: &PlantSettings) -> DockManagerResult<SensorReadings>;
+
+    /// Polls the sensors of every configured plant and door
+    ///
+    /// # Arguments
+    ///
+    /// * `settings`: The application settings containing plant, door and tag configurations
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(SensorReadings)`: The sensor values and door read statuses produced during this polling cycle
+    /// * `Err(DockManagerError)`: If the source is unable to produce values
+    async fn poll_sensors(&self, settings: &Settings) -> DockManagerResult<SensorReadings> {
+        let mut readings = SensorReadings::default();
+        for plant in &settings.plants {
+            readings.extend(self.poll_plant(settings, plant).await?);
+        }
+        Ok(readings)
+    }
+
+    /// Releases any resources held by the source, such as open PLC sessions
+    ///
+    /// Called once when the application shuts down. The default implementation does nothing.
+    async fn shutdown(&self) {}
+
+    /// A short, human-readable name for the source, used in log messages
+    fn name(&self) -> &'static str;
+}
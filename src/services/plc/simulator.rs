@@ -0,0 +1,135 @@
+//! # Simulated Sensor Source
+//!
+//! This module provides a `SensorSource` that produces sensor values from scripted, per-door scenarios
+//! instead of reading them from a PLC. Each scenario is a list of steps, and each step sets a number of tag
+//! values for a given number of polling cycles. Doors without a scenario report the configured default values.
+//! A step can also mark the door's PLC as offline, in which case every read of the door fails for the step's duration.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use async_trait::async_trait;
+use log::debug;
+use crate::config::{PlantSettings, Settings, SimulationScenario, SimulationSettings};
+use crate::errors::{DockManagerError, DockManagerResult};
+use crate::models::{PlcVal, SensorReadings, SensorValue};
+use crate::services::plc::sensor_source::SensorSource;
+
+/// A `SensorSource` that plays the scenarios configured under `plc.simulation`
+pub struct SimulatedSensorSource {
+    /// The simulation settings, including the default tag values and per-door scenarios
+    simulation: SimulationSettings,
+    /// The number of polling cycles played so far, keyed by plant ID and door name
+    cycles: Mutex<HashMap<(String, String), u64>>,
+}
+
+impl SimulatedSensorSource {
+    /// Creates a new `SimulatedSensorSource` for the given simulation settings
+    ///
+    /// # Arguments
+    ///
+    /// * `simulation`: The default tag values and the scenarios to play
+    pub fn new(simulation: SimulationSettings) -> Self {
+        Self {
+            simulation,
+            cycles: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Finds the scenario configured for a door, if any
+    fn scenario_for(&self, plant_id: &str, door_name: &str) -> Option<&SimulationScenario> {
+        self.simulation.scenarios.iter()
+            .find(|s| s.plant_id == plant_id && s.door_name == door_name)
+    }
+
+    /// Computes the tag values a scenario reports at a given cycle
+    ///
+    /// Values set by a step carry over into the following steps until they are overwritten.
+    /// Once the last step has been played, the scenario either starts over (when `repeat` is set)
+    /// or keeps reporting the values of its final step.
+    ///
+    /// # Arguments
+    ///
+    /// * `scenario`: The scenario being played
+    /// * `cycle`: The zero-based polling cycle number for the door
+    ///
+    /// # Returns
+    ///
+    /// The tag values set by the scenario up to and including the active step
+    fn scenario_values(scenario: &SimulationScenario, cycle: u64) -> HashMap<String, SensorValue> {
+        let position = Self::scenario_position(scenario, cycle);
+
+        let mut values = HashMap::new();
+        let mut elapsed = 0;
+        for step in &scenario.steps {
+            values.extend(step.values.iter().map(|(tag, value)| (tag.clone(), *value)));
+            elapsed += u64::from(step.cycles);
+            if position < elapsed {
+                break;
+            }
+        }
+        values
+    }
+
+    /// Returns `true` if the step active at a given cycle marks the door's PLC as offline
+    fn scenario_offline(scenario: &SimulationScenario, cycle: u64) -> bool {
+        let position = Self::scenario_position(scenario, cycle);
+        let mut elapsed = 0;
+        for step in &scenario.steps {
+            elapsed += u64::from(step.cycles);
+            if position < elapsed {
+                return step.offline;
+            }
+        }
+        scenario.steps.last().map(|step| step.offline).unwrap_or(false)
+    }
+
+    /// Maps a door's cycle number onto the scenario's timeline, wrapping around when the scenario repeats
+    fn scenario_position(scenario: &SimulationScenario, cycle: u64) -> u64 {
+        let total: u64 = scenario.steps.iter().map(|step| u64::from(step.cycles)).sum();
+        if scenario.repeat && total > 0 { cycle % total } else { cycle }
+    }
+
+    /// Advances the cycle counter for a door and returns the cycle to play
+    fn next_cycle(&self, plant_id: &str, door_name: &str) -> DockManagerResult<u64> {
+        let mut cycles = self.cycles.lock()
+            .map_err(|e| DockManagerError::StateError(format!("Simulation state poisoned: {}", e)))?;
+        let counter = cycles.entry((plant_id.to_string(), door_name.to_string())).or_insert(0);
+        let cycle = *counter;
+        *counter += 1;
+        Ok(cycle)
+    }
+}
+
+#[async_trait]
+impl SensorSource for SimulatedSensorSource {
+    async fn poll_plant(&self, _settings: &Settings, plant: &PlantSettings) -> DockManagerResult<SensorReadings> {
+        let mut plc_values = Vec::new();
+
+        for door in &plant.dock_doors.dock_door_config {
+            let cycle = self.next_cycle(&plant.plant_id, &door.dock_name)?;
+            let scenario = self.scenario_for(&plant.plant_id, &door.dock_name);
+            if scenario.is_some_and(|scenario| Self::scenario_offline(scenario, cycle)) {
+                debug!("Simulated PLC outage at cycle {} for door {} in plant {}", cycle, door.dock_name, plant.plant_id);
+                continue;
+            }
+            let scripted = scenario
+                .map(|scenario| Self::scenario_values(scenario, cycle))
+                .unwrap_or_default();
+
+            for tag in &plant.dock_doors.dock_plc_tags {
+                let value = scripted.get(&tag.tag_name)
+                    .or_else(|| self.simulation.default_values.get(&tag.tag_name))
+                    .copied()
+                    .unwrap_or(SensorValue::Int(0));
+                plc_values.push(PlcVal::new(&plant.plant_id, &door.dock_name, &door.dock_ip, &tag.tag_name, value));
+            }
+            debug!("Simulated cycle {} for door {} in plant {}", cycle, door.dock_name, plant.plant_id);
+        }
+
+        Ok(SensorReadings::from_values(plant, plc_values))
+    }
+
+    fn name(&self) -> &'static str {
+        "simulated"
+    }
+}
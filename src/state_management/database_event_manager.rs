@@ -1,7 +1,7 @@
 use tokio::sync::RwLock;
 use crate::models::DbInsert;
 use crate::errors::DockManagerError;
-use crate::services::db::DatabaseService;
+use crate::services::db_outbox::DbOutbox;
 use std::sync::Arc;
 use log::{info, error};
 
@@ -11,8 +11,8 @@ pub struct DatabaseEventManager {
     db_events: RwLock<Vec<DbInsert>>,
     /// The maximum number of events to accumulate before automatically flushing to the database.
     batch_size: usize,
-    /// The database service used for inserting events.
-    db_service: Arc<DatabaseService>,
+    /// The outbox the events are written to the database through.
+    db_outbox: Arc<DbOutbox>,
 }
 
 impl DatabaseEventManager {
@@ -21,16 +21,16 @@ impl DatabaseEventManager {
     /// # Arguments
     ///
     /// * `batch_size` - The maximum number of events to accumulate before automatically flushing.
-    /// * `db_service` - A reference to the `DatabaseService` for database operations.
+    /// * `db_outbox` - A reference to the `DbOutbox` the events are written through.
     ///
     /// # Returns
     ///
     /// A new instance of `DatabaseEventManager`.
-    pub fn new(batch_size: usize, db_service: Arc<DatabaseService>) -> Self {
+    pub fn new(batch_size: usize, db_outbox: Arc<DbOutbox>) -> Self {
         Self {
             db_events: RwLock::new(Vec::new()),
             batch_size,
-            db_service,
+            db_outbox,
         }
     }
 
@@ -61,7 +61,8 @@ impl DatabaseEventManager {
     /// Flushes all queued events to the database.
     ///
     /// This method is called automatically when the batch size is reached,
-    /// or it can be called manually to force a flush operation.
+    /// or it can be called manually to force a flush operation. Events that
+    /// cannot be written while the database is unreachable are kept in the outbox.
     ///
     /// # Returns
     ///
@@ -76,14 +77,13 @@ impl DatabaseEventManager {
         drop(events); // Release the write lock before database operation
 
         info!("Flushing {} database events", events_to_flush.len());
-        match self.db_service.insert_dock_door_events(events_to_flush).await {
+        match self.db_outbox.insert(events_to_flush).await {
             Ok(_) => {
                 info!("Successfully flushed database events");
                 Ok(())
             },
             Err(e) => {
                 error!("Failed to flush database events: {:?}", e);
-                // In case of failure, we might want to re-queue the events or implement a retry mechanism
                 Err(e)
             }
         }
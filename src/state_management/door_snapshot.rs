@@ -0,0 +1,82 @@
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use chrono::{Local, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use crate::errors::DockManagerResult;
+use crate::models::DockDoor;
+
+/// A point-in-time copy of every dock door, written to disk so the door states survive a restart.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DoorSnapshot {
+    /// When the snapshot was taken.
+    pub taken_at: NaiveDateTime,
+    /// The sequence number of the last journaled event the doors reflect, or 0 if none.
+    ///
+    /// Only the events journaled after it are replayed on top of the snapshot.
+    pub journal_sequence: u64,
+    /// The state of every door at that time.
+    pub doors: Vec<DockDoor>,
+}
+
+impl DoorSnapshot {
+    /// Creates a new `DoorSnapshot` of the given doors, taken now.
+    ///
+    /// # Arguments
+    ///
+    /// * `doors` - The doors to include in the snapshot.
+    /// * `journal_sequence` - The sequence number of the last journaled event applied to the doors.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `DoorSnapshot`.
+    pub fn new(doors: Vec<DockDoor>, journal_sequence: u64) -> Self {
+        Self {
+            taken_at: Local::now().naive_local(),
+            journal_sequence,
+            doors,
+        }
+    }
+
+    /// Returns the age of the snapshot in seconds.
+    pub fn age_secs(&self) -> i64 {
+        Local::now().naive_local().signed_duration_since(self.taken_at).num_seconds()
+    }
+
+    /// Writes the snapshot to a file.
+    ///
+    /// The snapshot is written to a temporary file next to `path` which then replaces it, so a crash
+    /// while writing never leaves a truncated snapshot behind.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The file to write the snapshot to.
+    ///
+    /// # Returns
+    ///
+    /// A `DockManagerResult` indicating success or failure.
+    pub async fn write_to(&self, path: &Path) -> DockManagerResult<()> {
+        let content = serde_json::to_vec(self)?;
+        let mut temp_path = PathBuf::from(path);
+        temp_path.set_extension("tmp");
+        tokio::fs::write(&temp_path, content).await?;
+        tokio::fs::rename(&temp_path, path).await?;
+        Ok(())
+    }
+
+    /// Reads a snapshot from a file.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The file to read the snapshot from.
+    ///
+    /// # Returns
+    ///
+    /// A `DockManagerResult` containing the snapshot, or `None` if the file does not exist.
+    pub async fn read_from(path: &Path) -> DockManagerResult<Option<Self>> {
+        match tokio::fs::read(path).await {
+            Ok(content) => Ok(Some(serde_json::from_slice(&content)?)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
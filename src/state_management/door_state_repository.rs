@@ -1,19 +1,24 @@
 use std::collections::HashMap;
+use std::path::Path;
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use crate::models::{DbInsert, DockDoor};
+use crate::models::{DbInsert, DockDoor, PlantOccupancy};
 use crate::errors::DockManagerError;
 use crate::config::Settings;
-use tracing::info;
+use crate::state_management::door_snapshot::DoorSnapshot;
+use tracing::{info, warn};
 
 pub struct DoorStateRepository {
     plants: Arc<RwLock<HashMap<String, HashMap<String, DockDoor>>>>,
+    /// The door counts of every plant, updated with the doors; only locked while `plants` is held for writing
+    occupancy: Arc<RwLock<HashMap<String, PlantOccupancy>>>,
 }
 
 impl DoorStateRepository {
     pub fn new() -> Self {
         Self {
             plants: Arc::new(RwLock::new(HashMap::new())),
+            occupancy: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 
@@ -25,13 +30,43 @@ impl DoorStateRepository {
 
     pub async fn update_door(&self, plant_id: &str, door: DockDoor) -> Result<(), DockManagerError> {
         let mut plants = self.plants.write().await;
-        plants
+        let mut occupancy = self.occupancy.write().await;
+        let plant_occupancy = occupancy.entry(plant_id.to_string()).or_insert_with(|| PlantOccupancy::new(plant_id));
+        plant_occupancy.add(&door);
+        let replaced = plants
             .entry(plant_id.to_string())
             .or_insert_with(HashMap::new)
             .insert(door.dock_name.clone(), door);
+        if let Some(replaced) = replaced {
+            plant_occupancy.remove(&replaced);
+        }
         Ok(())
     }
 
+    /// Changes a door in place, holding the repository lock so no other update is lost in between
+    ///
+    /// Returns what `change` returned, or `None` if the door is unknown
+    pub async fn modify_door<R>(&self, plant_id: &str, door_name: &str, change: impl FnOnce(&mut DockDoor) -> R) -> Option<R> {
+        let mut plants = self.plants.write().await;
+        let door = plants.get_mut(plant_id).and_then(|plant_doors| plant_doors.get_mut(door_name))?;
+        let mut occupancy = self.occupancy.write().await;
+        let plant_occupancy = occupancy.entry(plant_id.to_string()).or_insert_with(|| PlantOccupancy::new(plant_id));
+        plant_occupancy.remove(door);
+        let result = change(door);
+        plant_occupancy.add(door);
+        Some(result)
+    }
+
+    /// Returns the door counts of a plant, or `None` if it has no doors
+    pub async fn plant_occupancy(&self, plant_id: &str) -> Option<PlantOccupancy> {
+        self.occupancy.read().await.get(plant_id).cloned()
+    }
+
+    /// Returns the door counts of every plant
+    pub async fn all_occupancy(&self) -> Vec<PlantOccupancy> {
+        self.occupancy.read().await.values().cloned().collect()
+    }
+
     pub async fn get_all_doors(&self) -> Vec<DockDoor> {
         let plants = self.plants.read().await;
         plants.values()
@@ -41,9 +76,11 @@ impl DoorStateRepository {
 
     pub async fn initialize_from_settings(&self, settings: &Settings) -> Result<(), DockManagerError> {
         let mut plants = self.plants.write().await;
+        let mut occupancy = self.occupancy.write().await;
         for plant in &settings.plants {
             let plant_id = &plant.plant_id;
             let mut plant_doors = HashMap::new();
+            let mut plant_occupancy = PlantOccupancy::new(plant_id);
 
             for dock in &plant.dock_doors.dock_door_config {
                 let door = DockDoor::new(
@@ -52,14 +89,46 @@ impl DoorStateRepository {
                     dock.dock_ip.clone(),
                     plant,
                 );
+                plant_occupancy.add(&door);
                 plant_doors.insert(dock.dock_name.clone(), door);
             }
 
             plants.insert(plant_id.clone(), plant_doors);
+            occupancy.insert(plant_id.clone(), plant_occupancy);
         }
         Ok(())
     }
 
+    pub async fn snapshot(&self, journal_sequence: u64) -> DoorSnapshot {
+        DoorSnapshot::new(self.get_all_doors().await, journal_sequence)
+    }
+
+    pub async fn save_snapshot(&self, path: &Path, journal_sequence: u64) -> Result<(), DockManagerError> {
+        let snapshot = self.snapshot(journal_sequence).await;
+        snapshot.write_to(path).await?;
+        info!("Saved snapshot of {} doors to {}", snapshot.doors.len(), path.display());
+        Ok(())
+    }
+
+    pub async fn restore_snapshot(&self, snapshot: DoorSnapshot) -> usize {
+        let mut plants = self.plants.write().await;
+        let mut occupancy = self.occupancy.write().await;
+        let mut restored = 0;
+        for saved in snapshot.doors {
+            match plants.get_mut(&saved.plant_id).and_then(|plant_doors| plant_doors.get_mut(&saved.dock_name)) {
+                Some(door) => {
+                    let plant_occupancy = occupancy.entry(saved.plant_id.clone()).or_insert_with(|| PlantOccupancy::new(&saved.plant_id));
+                    plant_occupancy.remove(door);
+                    door.restore_from(saved);
+                    plant_occupancy.add(door);
+                    restored += 1;
+                },
+                None => warn!("Skipping snapshot of door {} in plant {}, it is no longer configured", saved.dock_name, saved.plant_id),
+            }
+        }
+        restored
+    }
+
     pub async fn insert_db_event(&self, plant_id: &str, event: DbInsert) -> Result<(), DockManagerError> {
         info!("{:?} - {:?}", plant_id, event);
         Ok(())
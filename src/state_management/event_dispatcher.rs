@@ -1,12 +1,16 @@
-use tokio::sync::mpsc;
+use std::sync::Arc;
 use crate::errors::{DockManagerError, DockManagerResult};
+use crate::event_handling::EventBus;
 use crate::models::DockDoorEvent;
+use crate::state_management::visit_tracker::TrailerVisitTracker;
 use log::{info, error};
 
 /// Dispatches events to the appropriate handlers in the dock monitoring system.
 pub struct EventDispatcher {
-    /// The sender end of a channel for dispatching events.
-    event_sender: mpsc::Sender<DockDoorEvent>,
+    /// The bus the events are published to.
+    event_bus: Arc<EventBus>,
+    /// The tracker recording every event in the trailer visit of its door.
+    visit_tracker: Arc<TrailerVisitTracker>,
 }
 
 impl EventDispatcher {
@@ -14,18 +18,20 @@ impl EventDispatcher {
     ///
     /// # Arguments
     ///
-    /// * `event_sender` - The sender end of a channel for dispatching events.
+    /// * `event_bus` - The bus the events are published to.
+    /// * `visit_tracker` - The tracker to record every event in the trailer visit of its door.
     ///
     /// # Returns
     ///
     /// A new instance of `EventDispatcher`.
-    pub fn new(event_sender: mpsc::Sender<DockDoorEvent>) -> Self {
-        Self { event_sender }
+    pub fn new(event_bus: Arc<EventBus>, visit_tracker: Arc<TrailerVisitTracker>) -> Self {
+        Self { event_bus, visit_tracker }
     }
 
     /// Dispatches an event to the appropriate handler.
     ///
-    /// This method sends the event through the channel to be processed by the event handler.
+    /// This method records the event in the trailer visit of its door and publishes it on the event bus,
+    /// to be processed by the event handler and any other subscriber.
     ///
     /// # Arguments
     ///
@@ -36,16 +42,18 @@ impl EventDispatcher {
     /// A `DockManagerResult` indicating success or failure of the dispatch operation.
     pub async fn dispatch_event(&self, event: DockDoorEvent) -> DockManagerResult<()> {
         info!("Dispatching event: {:?}", event);
-        self.event_sender.send(event).await
-            .map_err(|e| {
-                error!("Failed to dispatch event: {:?}", e);
-                DockManagerError::EventProcessingError(format!("Failed to dispatch event: {}", e))
-            })
+        if self.event_bus.is_closed() {
+            error!("Failed to dispatch event, the event bus is closed");
+            return Err(DockManagerError::EventProcessingError("Failed to dispatch event: the event bus is closed".to_string()));
+        }
+        self.visit_tracker.track(&event).await;
+        self.event_bus.publish(event).await;
+        Ok(())
     }
 
     /// Dispatches multiple events to the appropriate handlers.
     ///
-    /// This method sends multiple events through the channel to be processed by the event handler.
+    /// This method publishes multiple events on the event bus.
     ///
     /// # Arguments
     ///
@@ -65,12 +73,12 @@ impl EventDispatcher {
         Ok(())
     }
 
-    /// Checks if the event channel is still open and able to send events.
+    /// Checks if the event bus is still open and able to publish events.
     ///
     /// # Returns
     ///
-    /// `true` if the channel is open, `false` otherwise.
+    /// `true` if the bus is open, `false` otherwise.
     pub fn is_channel_open(&self) -> bool {
-        !self.event_sender.is_closed()
+        !self.event_bus.is_closed()
     }
 }
\ No newline at end of file
@@ -0,0 +1,350 @@
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use chrono::{Local, NaiveDateTime};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use tokio::fs::{File, OpenOptions};
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tokio::sync::Mutex;
+use crate::config::{JournalSettings, ShipmentRegistrySettings};
+use crate::errors::DockManagerResult;
+use crate::models::DockDoorEvent;
+use crate::state_management::door_state_repository::DoorStateRepository;
+use crate::state_management::sensor_data_processor::SensorDataProcessor;
+use crate::state_management::wms_data_processor::WmsDataProcessor;
+use crate::state_management::shipment_registry::ShipmentRegistry;
+
+/// A single event recorded in the journal.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    /// The position of the event in the journal, increasing by one for every event and kept across restarts.
+    pub sequence: u64,
+    /// When the event was recorded.
+    pub recorded_at: NaiveDateTime,
+    /// The recorded event.
+    pub event: DockDoorEvent,
+}
+
+impl JournalEntry {
+    /// Returns `true` if the entry's event concerns the given door.
+    ///
+    /// # Arguments
+    ///
+    /// * `plant_id` - The ID of the plant of the door.
+    /// * `door_name` - The name of the door.
+    pub fn concerns(&self, plant_id: &str, door_name: &str) -> bool {
+        self.event.get_plant_id() == plant_id && self.event.get_dock_name() == door_name
+    }
+}
+
+/// The open journal file and the position of the next event.
+struct JournalWriter {
+    /// The current journal file, opened on the first append.
+    file: Option<File>,
+    /// The size of the current journal file in bytes.
+    size: u64,
+    /// The sequence number given to the next event.
+    next_sequence: u64,
+}
+
+/// An append-only journal of dock door events, written as JSON lines.
+///
+/// The current journal is written to the configured path. Once it grows beyond `max_file_bytes` it is
+/// rotated to `<path>.1`, older files moving up to `<path>.2` and so on, and files beyond `max_files` are removed.
+pub struct EventJournal {
+    /// The path of the current journal file.
+    path: PathBuf,
+    /// The size in bytes after which the journal file is rotated.
+    max_file_bytes: u64,
+    /// The number of rotated journal files kept.
+    max_files: usize,
+    /// The writer, serializing appends.
+    writer: Mutex<JournalWriter>,
+    /// The sequence number of the last event whose effects on the doors are complete.
+    applied_sequence: AtomicU64,
+}
+
+impl EventJournal {
+    /// Opens the journal at the given path, continuing the sequence numbers of the events already in it.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the current journal file.
+    /// * `settings` - The journal settings.
+    ///
+    /// # Returns
+    ///
+    /// A `DockManagerResult` containing the opened `EventJournal`.
+    pub async fn open(path: &Path, settings: &JournalSettings) -> DockManagerResult<Self> {
+        let size = match tokio::fs::metadata(path).await {
+            Ok(metadata) => metadata.len(),
+            Err(e) if e.kind() == ErrorKind::NotFound => 0,
+            Err(e) => return Err(e.into()),
+        };
+        let next_sequence = Self::read_last_entry(path, settings.max_files).await?
+            .map_or(1, |entry| entry.sequence + 1);
+        info!("Opened event journal {} at sequence {}", path.display(), next_sequence);
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            max_file_bytes: settings.max_file_bytes,
+            max_files: settings.max_files,
+            writer: Mutex::new(JournalWriter { file: None, size, next_sequence }),
+            applied_sequence: AtomicU64::new(next_sequence - 1),
+        })
+    }
+
+    /// Records that an event has been applied to its door, once every change it causes has been made.
+    ///
+    /// # Arguments
+    ///
+    /// * `sequence` - The sequence number the event was given by `append`.
+    pub fn mark_applied(&self, sequence: u64) {
+        self.applied_sequence.fetch_max(sequence, Ordering::SeqCst);
+    }
+
+    /// Returns the sequence number of the last event applied to its door, or 0 if none was.
+    ///
+    /// A snapshot records this number before copying the doors, so that the events journaled up to it are
+    /// not replayed on top of it.
+    pub fn applied_sequence(&self) -> u64 {
+        self.applied_sequence.load(Ordering::SeqCst)
+    }
+
+    /// Appends an event to the journal, rotating the journal file first if it is full.
+    ///
+    /// # Arguments
+    ///
+    /// * `event` - The `DockDoorEvent` to record.
+    ///
+    /// # Returns
+    ///
+    /// A `DockManagerResult` containing the sequence number given to the event.
+    pub async fn append(&self, event: &DockDoorEvent) -> DockManagerResult<u64> {
+        let mut writer = self.writer.lock().await;
+        let entry = JournalEntry {
+            sequence: writer.next_sequence,
+            recorded_at: Local::now().naive_local(),
+            event: event.clone(),
+        };
+        let mut line = serde_json::to_vec(&entry)?;
+        line.push(b'\n');
+
+        if writer.size > 0 && writer.size + line.len() as u64 > self.max_file_bytes {
+            writer.file = None;
+            self.rotate().await?;
+            writer.size = 0;
+        }
+        if writer.file.is_none() {
+            writer.file = Some(OpenOptions::new().create(true).append(true).open(&self.path).await?);
+        }
+        if let Some(file) = writer.file.as_mut() {
+            file.write_all(&line).await?;
+            file.flush().await?;
+        }
+
+        writer.size += line.len() as u64;
+        writer.next_sequence += 1;
+        Ok(entry.sequence)
+    }
+
+    /// Moves the current journal file to `<path>.1`, shifting the older files up and removing the oldest.
+    async fn rotate(&self) -> DockManagerResult<()> {
+        if self.max_files == 0 {
+            remove_if_exists(&self.path).await?;
+            return Ok(());
+        }
+        remove_if_exists(&rotated_path(&self.path, self.max_files)).await?;
+        for index in (1..self.max_files).rev() {
+            rename_if_exists(&rotated_path(&self.path, index), &rotated_path(&self.path, index + 1)).await?;
+        }
+        rename_if_exists(&self.path, &rotated_path(&self.path, 1)).await?;
+        info!("Rotated event journal {}", self.path.display());
+        Ok(())
+    }
+
+    /// Reads every entry of a journal, oldest first, including the rotated files.
+    ///
+    /// Lines that cannot be parsed, such as one cut short by a crash, are skipped with a warning.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the current journal file.
+    /// * `max_files` - The number of rotated journal files kept next to it.
+    ///
+    /// # Returns
+    ///
+    /// A `DockManagerResult` containing the journal's entries in sequence order.
+    pub async fn read_entries(path: &Path, max_files: usize) -> DockManagerResult<Vec<JournalEntry>> {
+        let mut files: Vec<PathBuf> = (1..=max_files).rev().map(|index| rotated_path(path, index)).collect();
+        files.push(path.to_path_buf());
+
+        let mut entries = Vec::new();
+        for file in files {
+            let content = match tokio::fs::read_to_string(&file).await {
+                Ok(content) => content,
+                Err(e) if e.kind() == ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            };
+            for (index, line) in content.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+                match serde_json::from_str::<JournalEntry>(line) {
+                    Ok(entry) => entries.push(entry),
+                    Err(e) => warn!("Skipping unreadable line {} of event journal {}: {}", index + 1, file.display(), e),
+                }
+            }
+        }
+        entries.sort_by_key(|entry| entry.sequence);
+        Ok(entries)
+    }
+
+    /// Reads the most recent entry of a journal, looking only at the end of the newest file that has one.
+    ///
+    /// A last line that cannot be parsed, such as one cut short by a crash, is skipped with a warning.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the current journal file.
+    /// * `max_files` - The number of rotated journal files kept next to it.
+    ///
+    /// # Returns
+    ///
+    /// A `DockManagerResult` containing the most recent entry, or `None` if the journal is empty.
+    async fn read_last_entry(path: &Path, max_files: usize) -> DockManagerResult<Option<JournalEntry>> {
+        let files = std::iter::once(path.to_path_buf()).chain((1..=max_files).map(|index| rotated_path(path, index)));
+        for file in files {
+            if let Some(entry) = read_last_file_entry(&file).await? {
+                return Ok(Some(entry));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// The number of bytes read from the end of a journal file at a time when looking for its last entry.
+const TAIL_CHUNK_BYTES: u64 = 64 * 1024;
+
+/// Reads the last readable entry of a single journal file, reading it backwards from its end.
+async fn read_last_file_entry(file: &Path) -> DockManagerResult<Option<JournalEntry>> {
+    let mut handle = match File::open(file).await {
+        Ok(handle) => handle,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let len = handle.metadata().await?.len();
+    let mut tail_len = TAIL_CHUNK_BYTES.min(len);
+    loop {
+        let start = len - tail_len;
+        handle.seek(std::io::SeekFrom::Start(start)).await?;
+        let mut tail = Vec::with_capacity(tail_len as usize);
+        (&mut handle).take(tail_len).read_to_end(&mut tail).await?;
+
+        // Unless the tail starts the file, its first line may be cut and is left for a longer tail
+        let mut lines: Vec<&[u8]> = tail.split(|byte| *byte == b'\n').collect();
+        if start > 0 {
+            lines.remove(0);
+        }
+        for line in lines.into_iter().rev().filter(|line| !line.trim_ascii().is_empty()) {
+            match serde_json::from_slice::<JournalEntry>(line) {
+                Ok(entry) => return Ok(Some(entry)),
+                Err(e) => warn!("Skipping unreadable line at the end of event journal {}: {}", file.display(), e),
+            }
+        }
+        if start == 0 {
+            return Ok(None);
+        }
+        tail_len = (tail_len * 2).min(len);
+    }
+}
+
+/// Rebuilds door states by feeding journaled events back through the door model and the sensor and WMS processors.
+///
+/// Replaying a journal into doors freshly created from the settings shows how each door reached its state, and
+/// replaying the events recorded after a snapshot recovers the state lost in a crash.
+pub struct JournalReplayer {
+    /// Repository holding the doors being rebuilt.
+    door_repository: Arc<DoorStateRepository>,
+    /// Processor replaying sensor changes.
+    sensor_processor: SensorDataProcessor,
+    /// Processor replaying WMS updates.
+    wms_processor: WmsDataProcessor,
+}
+
+impl JournalReplayer {
+    /// Creates a new `JournalReplayer`.
+    ///
+    /// # Arguments
+    ///
+    /// * `door_repository` - The repository holding the doors to rebuild.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `JournalReplayer`.
+    pub fn new(door_repository: Arc<DoorStateRepository>) -> Self {
+        Self {
+            sensor_processor: SensorDataProcessor::new(Arc::clone(&door_repository)),
+            // Replay only rebuilds door states, so the shipments it sees are not kept
+            wms_processor: WmsDataProcessor::new(Arc::clone(&door_repository), Arc::new(ShipmentRegistry::new(&ShipmentRegistrySettings::default()))),
+            door_repository,
+        }
+    }
+
+    /// Replays journal entries in order.
+    ///
+    /// Each event is applied to its door through the WMS processor, the sensor processor and finally
+    /// `DockDoor::handle_event`. Events of doors that are no longer configured are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `entries` - The journal entries to replay, in sequence order.
+    /// * `until_sequence` - The last sequence number to replay, or `None` to replay every entry.
+    ///
+    /// # Returns
+    ///
+    /// A `DockManagerResult` containing the number of replayed events.
+    pub async fn replay(&self, entries: &[JournalEntry], until_sequence: Option<u64>) -> DockManagerResult<usize> {
+        let mut replayed = 0;
+        for entry in entries.iter().take_while(|entry| until_sequence.is_none_or(|until| entry.sequence <= until)) {
+            let event = &entry.event;
+            let Some(mut door) = self.door_repository.get_door_state(event.get_plant_id(), event.get_dock_name()).await else {
+                warn!("Skipping journal entry {} of unknown door {} in plant {}", entry.sequence, event.get_dock_name(), event.get_plant_id());
+                continue;
+            };
+
+            self.wms_processor.replay_event(&mut door, event);
+            if let DockDoorEvent::SensorStateChanged(e) = event {
+                self.sensor_processor.replay_sensor_change(&mut door, e)?;
+            }
+            door.handle_event(event)?;
+            door.track_visit(event);
+
+            self.door_repository.update_door(&door.plant_id.clone(), door).await?;
+            replayed += 1;
+        }
+        Ok(replayed)
+    }
+}
+
+/// Returns the path of the rotated journal file with the given index.
+fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    let mut rotated = path.as_os_str().to_owned();
+    rotated.push(format!(".{}", index));
+    PathBuf::from(rotated)
+}
+
+/// Removes a file, ignoring a file that does not exist.
+async fn remove_if_exists(path: &Path) -> DockManagerResult<()> {
+    match tokio::fs::remove_file(path).await {
+        Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+/// Renames a file, ignoring a file that does not exist.
+async fn rename_if_exists(from: &Path, to: &Path) -> DockManagerResult<()> {
+    match tokio::fs::rename(from, to).await {
+        Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
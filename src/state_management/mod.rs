@@ -1,10 +1,15 @@
 pub mod state_manager;
 pub mod door_state_repository;
+pub mod door_snapshot;
+pub mod event_journal;
 pub mod command_processor;
 pub mod sensor_data_processor;
 pub mod event_dispatcher;
 pub mod database_event_manager;
 pub mod state_manager_lifecycle;
 pub mod wms_data_processor;
+pub mod reconciler;
+pub mod visit_tracker;
+pub mod shipment_registry;
 
 pub use state_manager::DockDoorStateManager;
\ No newline at end of file
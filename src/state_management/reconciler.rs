@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use chrono::{Duration, NaiveDateTime};
+use log::{info, warn};
+use tokio::sync::Mutex;
+use crate::config::{ContradictionPattern, ReconcilerSettings};
+use crate::models::{DockDoor, DockDoorEvent, WmsPlcDiscrepancyEvent};
+use crate::state_management::door_state_repository::DoorStateRepository;
+
+/// A contradiction currently seen at a door.
+struct OpenDiscrepancy {
+    /// When the contradiction was first seen.
+    since: NaiveDateTime,
+    /// Whether a discrepancy event has been raised for it.
+    reported: bool,
+}
+
+/// Cross-checks what the WMS reports for each door against the state derived from its sensors.
+///
+/// A door matching one of the configured contradiction patterns for longer than the pattern's grace period
+/// raises a `WmsPlcDiscrepancy` event, and a second one with `resolved` set once it stops matching. Doors whose
+/// PLC is offline or whose sensor values are not all known are skipped, as their sensor state cannot be trusted.
+pub struct WmsPlcReconciler {
+    /// Repository holding the doors to check.
+    door_repository: Arc<DoorStateRepository>,
+    /// The contradiction patterns checked for every door.
+    patterns: Vec<ContradictionPattern>,
+    /// The contradictions currently seen, keyed by plant, door and pattern name.
+    open: Mutex<HashMap<(String, String, String), OpenDiscrepancy>>,
+}
+
+impl WmsPlcReconciler {
+    /// Creates a new `WmsPlcReconciler`.
+    ///
+    /// # Arguments
+    ///
+    /// * `door_repository` - The repository holding the doors to check.
+    /// * `settings` - The reconciler settings.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `WmsPlcReconciler`.
+    pub fn new(door_repository: Arc<DoorStateRepository>, settings: &ReconcilerSettings) -> Self {
+        Self {
+            door_repository,
+            patterns: settings.patterns.clone(),
+            open: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Checks every door against the contradiction patterns.
+    ///
+    /// # Arguments
+    ///
+    /// * `now` - The time of the check.
+    ///
+    /// # Returns
+    ///
+    /// The `WmsPlcDiscrepancy` events for the contradictions that outlasted their grace period
+    /// and for the reported ones that are gone.
+    pub async fn reconcile(&self, now: NaiveDateTime) -> Vec<DockDoorEvent> {
+        let doors = self.door_repository.get_all_doors().await;
+        let mut open = self.open.lock().await;
+        let mut events = Vec::new();
+
+        for door in doors.iter().filter(|door| door.plc_health.online && door.all_sensors_known()) {
+            for pattern in &self.patterns {
+                let key = (door.plant_id.clone(), door.dock_name.clone(), pattern.name.clone());
+                if !matches(pattern, door) {
+                    if let Some(discrepancy) = open.remove(&key).filter(|discrepancy| discrepancy.reported) {
+                        info!("Door {} no longer matches contradiction pattern {}", door.dock_name, pattern.name);
+                        events.push(discrepancy_event(door, pattern, discrepancy.since, true, now));
+                    }
+                    continue;
+                }
+
+                let discrepancy = open.entry(key).or_insert(OpenDiscrepancy { since: now, reported: false });
+                let grace_period = Duration::seconds(pattern.grace_period_secs as i64);
+                if !discrepancy.reported && now.signed_duration_since(discrepancy.since) >= grace_period {
+                    warn!("Door {} matches contradiction pattern {} since {}: {}",
+                        door.dock_name, pattern.name, discrepancy.since, describe(door));
+                    discrepancy.reported = true;
+                    events.push(discrepancy_event(door, pattern, discrepancy.since, false, now));
+                }
+            }
+        }
+
+        events
+    }
+}
+
+/// Returns `true` if every condition the pattern sets holds for the door.
+fn matches(pattern: &ContradictionPattern, door: &DockDoor) -> bool {
+    (pattern.loading_statuses.is_empty() || pattern.loading_statuses.contains(&door.loading_status.loading_status))
+        && pattern.shipment_assigned.is_none_or(|assigned| door.assigned_shipment.current_shipment.is_some() == assigned)
+        && pattern.trailer_state.is_none_or(|state| door.trailer_state == state)
+        && pattern.dock_lock_state.is_none_or(|state| door.dock_lock_state == state)
+        && pattern.door_position.is_none_or(|position| door.door_position == position)
+}
+
+/// Describes the WMS and sensor state of a door.
+fn describe(door: &DockDoor) -> String {
+    format!(
+        "WMS reports {:?} with shipment {}, sensors report trailer {:?}, dock lock {:?}, door {:?}",
+        door.loading_status.loading_status,
+        door.assigned_shipment.current_shipment.as_deref().unwrap_or("none"),
+        door.trailer_state,
+        door.dock_lock_state,
+        door.door_position
+    )
+}
+
+/// Creates the discrepancy event of a door and pattern.
+fn discrepancy_event(door: &DockDoor, pattern: &ContradictionPattern, since: NaiveDateTime, resolved: bool, now: NaiveDateTime) -> DockDoorEvent {
+    DockDoorEvent::WmsPlcDiscrepancy(WmsPlcDiscrepancyEvent {
+        plant_id: door.plant_id.clone(),
+        dock_name: door.dock_name.clone(),
+        shipment_id: door.assigned_shipment.current_shipment.clone(),
+        pattern: pattern.name.clone(),
+        details: describe(door),
+        since,
+        resolved,
+        timestamp: now,
+    })
+}
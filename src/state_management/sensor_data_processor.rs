@@ -1,11 +1,11 @@
 use std::sync::Arc;
-use chrono::Local;
+use chrono::{Local, NaiveDateTime};
 use log::{info, debug};
 use crate::errors::{DockManagerError, DockManagerResult};
 use crate::models::{
-    DockDoor, DockDoorEvent, DockLockState, DoorPosition, DoorState, DoorStateChangedEvent,
+    DockDoor, DockDoorEvent, DockLockState, DoorPosition, DoorReadStatus, DoorState,
     FaultState, LevelerPosition, ManualMode, PlcVal, RestraintState, SensorStateChangedEvent,
-    TrailerPositionState, TrailerState, TrailerStateChangedEvent
+    TrailerPositionState, TrailerState, TrailerStateChangedEvent, TransitionCause
 };
 use crate::state_management::door_state_repository::DoorStateRepository;
 
@@ -33,6 +33,9 @@ impl SensorDataProcessor {
 
     /// Processes a batch of sensor updates and generates corresponding events.
     ///
+    /// Each update is applied to the door in place through `DoorStateRepository::modify_door`, so changes made
+    /// to the door elsewhere in the meantime, such as its open trailer visit, are never overwritten.
+    ///
     /// # Arguments
     ///
     /// * `sensor_values` - A vector of `PlcVal` representing the sensor updates.
@@ -47,13 +50,31 @@ impl SensorDataProcessor {
             let plant_id = &sensor_value.plant_id;
             let door_name = &sensor_value.door_name;
 
-            let mut door = self.door_repository.get_door_state(plant_id, door_name).await
-                .ok_or_else(|| DockManagerError::DoorNotFound(format!("Plant: {}, Door: {}", plant_id, door_name)))?;
-
-            let new_events = self.process_single_sensor_update(&mut door, &sensor_value).await?;
+            let new_events = self.door_repository.modify_door(plant_id, door_name, |door| self.process_single_sensor_update(door, &sensor_value)).await
+                .ok_or_else(|| DockManagerError::DoorNotFound(format!("Plant: {}, Door: {}", plant_id, door_name)))??;
             events.extend(new_events);
+        }
+
+        Ok(events)
+    }
 
-            self.door_repository.update_door(plant_id, door).await?;
+    /// Processes the per-door read statuses of a polling cycle, updating each door's PLC health.
+    ///
+    /// # Arguments
+    ///
+    /// * `statuses` - The read status of every door that was polled.
+    /// * `offline_after_failures` - The number of failed cycles in a row after which a door is reported offline.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the `DoorOffline` and `DoorOnline` events raised by the update,
+    /// or a `DockManagerError` if a door cannot be found.
+    pub async fn process_health_updates(&self, statuses: Vec<DoorReadStatus>, offline_after_failures: u32) -> DockManagerResult<Vec<DockDoorEvent>> {
+        let mut events = Vec::new();
+        for status in statuses {
+            let event = self.door_repository.modify_door(&status.plant_id, &status.door_name, |door| door.update_plc_health(&status, offline_after_failures)).await
+                .ok_or_else(|| DockManagerError::DoorNotFound(format!("Plant: {}, Door: {}", status.plant_id, status.door_name)))?;
+            events.extend(event);
         }
 
         Ok(events)
@@ -61,6 +82,9 @@ impl SensorDataProcessor {
 
     /// Processes a single sensor update for a specific door.
     ///
+    /// The door state is derived from the value as translated by the sensor's `ValueMapping`,
+    /// so active-low and remapped tags are handled like any other.
+    ///
     /// # Arguments
     ///
     /// * `door` - A mutable reference to the `DockDoor` being updated.
@@ -70,15 +94,47 @@ impl SensorDataProcessor {
     ///
     /// A Result containing a vector of `DockDoorEvent`s generated from the sensor update,
     /// or a `DockManagerError` if processing fails.
-    async fn process_single_sensor_update(&self, door: &mut DockDoor, sensor_value: &PlcVal) -> Result<Vec<DockDoorEvent>, DockManagerError> {
+    fn process_single_sensor_update(&self, door: &mut DockDoor, sensor_value: &PlcVal) -> Result<Vec<DockDoorEvent>, DockManagerError> {
         let mut events = Vec::new();
 
+        // A sensor whose value was lost while the PLC was offline keeps its last known value as the previous value
+        let last_known_value = door.sensors.get(&sensor_value.sensor_name)
+            .map(|sensor| sensor.get_sensor_data())
+            .and_then(|data| data.current_value.or(data.previous_value));
         let sensor_evaluation = door.update_sensor(&sensor_value.sensor_name, Some(sensor_value.value))?;
+        let changed_while_unknown = match (last_known_value, sensor_evaluation.new_value) {
+            (Some(last_known), Some(new_value)) => !door.sensors.get(&sensor_value.sensor_name)
+                .is_some_and(|sensor| sensor.get_sensor_data().same_value(last_known, new_value)),
+            _ => false,
+        };
+        let sensor_value = &PlcVal {
+            value: sensor_evaluation.new_value.unwrap_or(sensor_value.value),
+            ..sensor_value.clone()
+        };
 
         if sensor_evaluation.changed {
-            if sensor_evaluation.old_value.is_none() {
+            if sensor_evaluation.old_value.is_none() && door.awaiting_reconciliation {
+                // The door was restored from a snapshot or its PLC was offline, so the first reads report what
+                // changed while the sensor values were unknown
+                debug!("Reconciling state for door: {}, sensor: {}", door.dock_name, sensor_value.sensor_name);
+                if changed_while_unknown {
+                    events.push(DockDoorEvent::SensorStateChanged(SensorStateChangedEvent {
+                        plant_id: door.plant_id.clone(),
+                        dock_name: door.dock_name.clone(),
+                        sensor_name: sensor_value.sensor_name.clone(),
+                        old_value: last_known_value,
+                        new_value: sensor_evaluation.new_value,
+                        timestamp: chrono::Local::now().naive_local(),
+                    }));
+                }
+                self.update_door_state(door, sensor_value, Local::now().naive_local(), &mut events)?;
+                if door.all_sensors_known() {
+                    info!("Door {} reconciled with its PLC", door.dock_name);
+                    door.awaiting_reconciliation = false;
+                }
+            } else if sensor_evaluation.old_value.is_none() {
                 debug!("Skipping initial sensor update for door: {}, sensor: {}", door.dock_name, sensor_value.sensor_name);
-                self.update_door_state(door, sensor_value, &mut Vec::new())?;
+                self.update_door_state(door, sensor_value, Local::now().naive_local(), &mut Vec::new())?;
                 return Ok(Vec::new())
             } else {
                 events.push(DockDoorEvent::SensorStateChanged(SensorStateChangedEvent {
@@ -89,45 +145,74 @@ impl SensorDataProcessor {
                     new_value: sensor_evaluation.new_value,
                     timestamp: chrono::Local::now().naive_local(),
                 }));
-                self.update_door_state(door, sensor_value, &mut events)?;
+                self.update_door_state(door, sensor_value, Local::now().naive_local(), &mut events)?;
             }
         }
 
         Ok(events)
     }
 
+    /// Replays a journaled sensor change, updating the door's derived state as the original change did.
+    ///
+    /// The journaled value has already been translated and debounced, so it is applied as is. The events
+    /// the change derives are not returned, since they were journaled after it themselves.
+    ///
+    /// # Arguments
+    ///
+    /// * `door` - A mutable reference to the `DockDoor` being rebuilt.
+    /// * `event` - The journaled `SensorStateChangedEvent`.
+    ///
+    /// # Returns
+    ///
+    /// A Result indicating success or a `DockManagerError` if processing fails.
+    pub fn replay_sensor_change(&self, door: &mut DockDoor, event: &SensorStateChangedEvent) -> Result<(), DockManagerError> {
+        let Some(value) = event.new_value else {
+            return Ok(());
+        };
+        let sensor_value = PlcVal {
+            plant_id: event.plant_id.clone(),
+            door_name: event.dock_name.clone(),
+            door_ip: door.dock_ip.clone(),
+            sensor_name: event.sensor_name.clone(),
+            value,
+            timestamp: event.timestamp,
+        };
+        self.update_door_state(door, &sensor_value, event.timestamp, &mut Vec::new())
+    }
+
     /// Updates the door state based on the sensor value.
     ///
     /// # Arguments
     ///
     /// * `door` - A mutable reference to the `DockDoor` being updated.
     /// * `sensor_value` - The `PlcVal` containing the sensor update.
+    /// * `timestamp` - When the sensor changed.
     /// * `events` - A mutable reference to the vector of events being generated.
     ///
     /// # Returns
     ///
     /// A Result indicating success or a `DockManagerError` if processing fails.
-    fn update_door_state(&self, door: &mut DockDoor, sensor_value: &PlcVal, events: &mut Vec<DockDoorEvent>) -> Result<(), DockManagerError> {
+    fn update_door_state(&self, door: &mut DockDoor, sensor_value: &PlcVal, timestamp: NaiveDateTime, events: &mut Vec<DockDoorEvent>) -> Result<(), DockManagerError> {
         match sensor_value.sensor_name.as_str() {
             "AUTO_DISENGAGING" => {
-                door.restraint_state = if sensor_value.value == 1 { RestraintState::Unlocking } else { RestraintState::Unlocked };
+                door.restraint_state = if sensor_value.value.is_on() { RestraintState::Unlocking } else { RestraintState::Unlocked };
             },
             "AUTO_ENGAGING" => {
-                door.restraint_state = if sensor_value.value == 1 { RestraintState::Locking } else { RestraintState::Locked };
+                door.restraint_state = if sensor_value.value.is_on() { RestraintState::Locking } else { RestraintState::Locked };
             },
             "FAULT_PRESENCE" => {
-                door.fault_state = if sensor_value.value == 1 { FaultState::FaultPresent } else { FaultState::NoFault };
+                door.fault_state = if sensor_value.value.is_on() { FaultState::FaultPresent } else { FaultState::NoFault };
             },
             "FAULT_TRAILER_DOORS" => {
-                door.trailer_door_fault = sensor_value.value == 1;
+                door.trailer_door_fault = sensor_value.value.is_on();
             },
             "RH_DOCK_READY" => {
-                if sensor_value.value == 1 &&
+                if sensor_value.value.is_on() &&
                     (door.door_state == DoorState::TrailerDocked || door.door_state == DoorState::Unassigned) {
                     if let Some(old_value) = door.sensors.get("RH_DOCK_READY").and_then(|s| s.get_sensor_data().current_value) {
-                        if old_value == 0 {
-                            door.consolidated.last_dock_ready_time = Some(Local::now().naive_local());
-                            self.change_door_state(door, DoorState::DoorReady, events)?;
+                        if !old_value.is_on() {
+                            door.consolidated.last_dock_ready_time = Some(timestamp);
+                            self.change_door_state(door, DoorState::DoorReady, &sensor_value.sensor_name, timestamp, events)?;
                         }
                     }
                 } else {
@@ -135,38 +220,46 @@ impl SensorDataProcessor {
                 }
             },
             "RH_DOKLOCK_FAULT" => {
-                door.dock_lock_fault = sensor_value.value == 1;
+                door.dock_lock_fault = sensor_value.value.is_on();
             },
             "RH_DOOR_FAULT" => {
-                door.door_fault = sensor_value.value == 1;
+                door.door_fault = sensor_value.value.is_on();
             },
             "RH_DOOR_OPEN" => {
-                door.door_position = if sensor_value.value == 1 { DoorPosition::Open } else { DoorPosition::Closed };
+                door.door_position = if sensor_value.value.is_on() { DoorPosition::Open } else { DoorPosition::Closed };
             },
             "RH_ESTOP" => {
-                door.emergency_stop = sensor_value.value == 1;
+                door.emergency_stop = sensor_value.value.is_on();
                 if door.emergency_stop {
                     door.manual_mode = ManualMode::Enabled;
                 }
             },
             "RH_LEVELER_FAULT" => {
-                door.leveler_fault = sensor_value.value == 1;
+                door.leveler_fault = sensor_value.value.is_on();
             },
             "RH_LEVELR_READY" => {
-                door.leveler_position = if sensor_value.value == 1 { LevelerPosition::Extended } else { LevelerPosition::Stored };
+                door.leveler_position = if sensor_value.value.is_on() { LevelerPosition::Extended } else { LevelerPosition::Stored };
             },
             "RH_MANUAL_MODE" => {
-                door.manual_mode = if sensor_value.value == 1 { ManualMode::Enabled } else { ManualMode::Disabled };
+                door.manual_mode = if sensor_value.value.is_on() { ManualMode::Enabled } else { ManualMode::Disabled };
             },
             "RH_RESTRAINT_ENGAGED" => {
-                door.dock_lock_state = if sensor_value.value == 1 { DockLockState::Engaged } else { DockLockState::Disengaged };
+                door.dock_lock_state = if sensor_value.value.is_on() { DockLockState::Engaged } else { DockLockState::Disengaged };
             },
             "TRAILER_ANGLE" | "TRAILER_CENTERING" | "TRAILER_DISTANCE" => {
-                door.trailer_position_state = if sensor_value.value == 0 { TrailerPositionState::Proper } else { TrailerPositionState::Improper };
+                // Analog measurements are judged by threshold rules rather than treated as a fault bit
+                let is_analog = door.sensors.get(&sensor_value.sensor_name)
+                    .is_some_and(|sensor| sensor.get_sensor_data().is_analog());
+                if !is_analog {
+                    door.trailer_position_state = if sensor_value.value.is_on() { TrailerPositionState::Improper } else { TrailerPositionState::Proper };
+                }
             },
             "TRAILER_AT_DOOR" => {
-                let new_trailer_state = if sensor_value.value == 1 {
-                    door.set_docking_time();
+                let new_trailer_state = if sensor_value.value.is_on() {
+                    // A docking time restored from a snapshot is kept
+                    if door.consolidated.docking_time.is_none() {
+                        door.set_docking_time(timestamp);
+                    }
                     TrailerState::Docked
                 } else {
                     door.clear_docking_time();
@@ -179,12 +272,12 @@ impl SensorDataProcessor {
                         dock_name: door.dock_name.clone(),
                         old_state: door.trailer_state,
                         new_state: new_trailer_state,
-                        timestamp: chrono::Local::now().naive_local(),
+                        timestamp,
                     }));
                     door.trailer_state = new_trailer_state;
                     if new_trailer_state == TrailerState::Docked {
                         info!("Changing door state to TrailerDocked");
-                        self.change_door_state(door, DoorState::TrailerDocked, events)?;
+                        self.change_door_state(door, DoorState::TrailerDocked, &sensor_value.sensor_name, timestamp, events)?;
                     }
                 }
             },
@@ -196,30 +289,107 @@ impl SensorDataProcessor {
     }
 
 
-    /// Changes the door state and generates a DoorStateChanged event.
+    /// Changes the door state and generates a DoorStateChanged event, or a DoorStateAnomaly event if the
+    /// transition is not allowed.
     ///
     /// # Arguments
     ///
     /// * `door` - A mutable reference to the `DockDoor` being updated.
     /// * `new_state` - The new `DoorState` to set.
+    /// * `sensor_name` - The name of the sensor whose change caused the transition.
+    /// * `timestamp` - When the sensor changed.
     /// * `events` - A mutable reference to the vector of events being generated.
     ///
     /// # Returns
     ///
     /// A Result indicating success or a `DockManagerError` if processing fails.
-    fn change_door_state(&self, door: &mut DockDoor, new_state: DoorState, events: &mut Vec<DockDoorEvent>) -> Result<(), DockManagerError> {
-        if door.door_state != new_state {
-            let old_state = door.door_state;
-            door.door_state = new_state;
-            events.push(DockDoorEvent::DoorStateChanged(DoorStateChangedEvent {
-                plant_id: door.plant_id.clone(),
-                dock_name: door.dock_name.clone(),
-                old_state,
-                new_state,
-                timestamp: chrono::Local::now().naive_local(),
-            }));
+    fn change_door_state(&self, door: &mut DockDoor, new_state: DoorState, sensor_name: &str, timestamp: NaiveDateTime, events: &mut Vec<DockDoorEvent>) -> Result<(), DockManagerError> {
+        let old_state = door.door_state;
+        let cause = TransitionCause::Sensor { sensor_name: sensor_name.to_string() };
+        if let Some(event) = door.transition_door_state(new_state, cause, timestamp) {
             info!("Door state changed for {}: {:?} -> {:?}", door.dock_name, old_state, new_state);
+            events.push(event);
         }
         Ok(())
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::test_support::{self, DOOR_IP, DOOR_NAME, PLANT_ID};
+    use crate::models::SensorValue;
+
+    /// Creates a processor over a repository holding the test door
+    async fn processor() -> (SensorDataProcessor, Arc<DoorStateRepository>) {
+        let repository = Arc::new(DoorStateRepository::new());
+        repository.update_door(PLANT_ID, test_support::door(&test_support::plant())).await.unwrap();
+        (SensorDataProcessor::new(repository.clone()), repository)
+    }
+
+    fn reading(sensor_name: &str, value: i64) -> PlcVal {
+        PlcVal::new(PLANT_ID, DOOR_NAME, DOOR_IP, sensor_name, SensorValue::Int(value))
+    }
+
+    fn status(reachable: bool) -> DoorReadStatus {
+        DoorReadStatus {
+            plant_id: PLANT_ID.to_string(),
+            door_name: DOOR_NAME.to_string(),
+            successful_reads: if reachable { 2 } else { 0 },
+            failed_sensors: if reachable { Vec::new() } else { vec!["TRAILER_AT_DOOR".to_string(), "RH_DOCK_READY".to_string()] },
+            latency: None,
+            last_error: (!reachable).then(|| "timeout".to_string()),
+        }
+    }
+
+    #[tokio::test]
+    async fn initial_reads_raise_no_events() {
+        let (processor, _) = processor().await;
+
+        let events = processor.process_sensor_updates(vec![reading("TRAILER_AT_DOOR", 1), reading("RH_DOCK_READY", 0)]).await.unwrap();
+
+        assert!(events.is_empty());
+    }
+
+    #[tokio::test]
+    async fn change_while_offline_is_reported_once_the_plc_is_back() {
+        let (processor, repository) = processor().await;
+        processor.process_sensor_updates(vec![reading("TRAILER_AT_DOOR", 0), reading("RH_DOCK_READY", 0)]).await.unwrap();
+
+        let offline = processor.process_health_updates(vec![status(false)], 1).await.unwrap();
+        assert!(matches!(offline.as_slice(), [DockDoorEvent::DoorOffline(_)]));
+        assert!(repository.get_door_state(PLANT_ID, DOOR_NAME).await.unwrap().awaiting_reconciliation);
+
+        let online = processor.process_health_updates(vec![status(true)], 1).await.unwrap();
+        assert!(matches!(online.as_slice(), [DockDoorEvent::DoorOnline(_)]));
+
+        // The trailer arrived while the PLC could not be read
+        let events = processor.process_sensor_updates(vec![reading("TRAILER_AT_DOOR", 1), reading("RH_DOCK_READY", 0)]).await.unwrap();
+        match events.as_slice() {
+            [DockDoorEvent::SensorStateChanged(sensor), DockDoorEvent::TrailerStateChanged(trailer), DockDoorEvent::DoorStateChanged(door)] => {
+                assert_eq!(sensor.sensor_name, "TRAILER_AT_DOOR");
+                assert_eq!(sensor.old_value, Some(SensorValue::Int(0)));
+                assert_eq!(sensor.new_value, Some(SensorValue::Int(1)));
+                assert_eq!((trailer.old_state, trailer.new_state), (TrailerState::Undocked, TrailerState::Docked));
+                assert_eq!((door.old_state, door.new_state), (DoorState::Unassigned, DoorState::TrailerDocked));
+            }
+            other => panic!("unexpected events: {:?}", other),
+        }
+
+        let door = repository.get_door_state(PLANT_ID, DOOR_NAME).await.unwrap();
+        assert!(!door.awaiting_reconciliation);
+        assert_eq!(door.door_state, DoorState::TrailerDocked);
+    }
+
+    #[tokio::test]
+    async fn unchanged_value_after_going_offline_raises_no_events() {
+        let (processor, repository) = processor().await;
+        processor.process_sensor_updates(vec![reading("TRAILER_AT_DOOR", 0), reading("RH_DOCK_READY", 0)]).await.unwrap();
+        processor.process_health_updates(vec![status(false)], 1).await.unwrap();
+        processor.process_health_updates(vec![status(true)], 1).await.unwrap();
+
+        let events = processor.process_sensor_updates(vec![reading("TRAILER_AT_DOOR", 0), reading("RH_DOCK_READY", 0)]).await.unwrap();
+
+        assert!(events.is_empty());
+        assert!(!repository.get_door_state(PLANT_ID, DOOR_NAME).await.unwrap().awaiting_reconciliation);
+    }
+}
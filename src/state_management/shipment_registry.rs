@@ -0,0 +1,130 @@
+use std::collections::HashMap;
+use chrono::{Duration, NaiveDateTime};
+use log::{debug, info};
+use tokio::sync::RwLock;
+use crate::config::ShipmentRegistrySettings;
+use crate::models::{ShipmentRecord, WmsEvent};
+
+/// Follows every shipment of the plants across door reassignments, keyed by plant and shipment ID.
+///
+/// The registry is fed by the WMS door statuses, which assign and unassign shipments, and by the WMS events,
+/// which are aggregated per shipment and move it through its lifecycle. Shipments are forgotten once they have
+/// not been assigned or received an event for longer than the retention period.
+pub struct ShipmentRegistry {
+    /// The records of the known shipments, keyed by plant and shipment ID.
+    shipments: RwLock<HashMap<(String, String), ShipmentRecord>>,
+    /// How long a shipment is kept after its last update.
+    retention: Duration,
+}
+
+impl ShipmentRegistry {
+    /// Creates a new, empty `ShipmentRegistry`.
+    ///
+    /// # Arguments
+    ///
+    /// * `settings` - The shipment registry settings.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `ShipmentRegistry`.
+    pub fn new(settings: &ShipmentRegistrySettings) -> Self {
+        Self {
+            shipments: RwLock::new(HashMap::new()),
+            retention: Duration::seconds(settings.retention_secs as i64),
+        }
+    }
+
+    /// Records a shipment being assigned to a door.
+    ///
+    /// Assigning the shipment to the door it is already at changes nothing, so the current assignment
+    /// of every door can be reported on each WMS poll.
+    ///
+    /// # Arguments
+    ///
+    /// * `plant_id` - The plant of the door.
+    /// * `shipment_id` - The ID of the shipment.
+    /// * `dock_name` - The name of the door.
+    /// * `at` - When the assignment was seen.
+    pub async fn assign(&self, plant_id: &str, shipment_id: &str, dock_name: &str, at: NaiveDateTime) {
+        let mut shipments = self.shipments.write().await;
+        let record = shipments.entry((plant_id.to_string(), shipment_id.to_string()))
+            .or_insert_with(|| ShipmentRecord::new(plant_id, shipment_id, at));
+        match record.assign(dock_name, at) {
+            Some(moved_from) => info!("Shipment {} moved from door {} to door {}", shipment_id, moved_from, dock_name),
+            None => debug!("Shipment {} is assigned to door {}", shipment_id, dock_name),
+        }
+    }
+
+    /// Records a shipment being unassigned from a door.
+    ///
+    /// Nothing changes if the shipment has since been assigned to another door.
+    ///
+    /// # Arguments
+    ///
+    /// * `plant_id` - The plant of the door.
+    /// * `shipment_id` - The ID of the shipment.
+    /// * `dock_name` - The name of the door.
+    /// * `at` - When the shipment was unassigned.
+    pub async fn unassign(&self, plant_id: &str, shipment_id: &str, dock_name: &str, at: NaiveDateTime) {
+        let mut shipments = self.shipments.write().await;
+        if let Some(record) = shipments.get_mut(&(plant_id.to_string(), shipment_id.to_string())) {
+            if record.current_door() == Some(dock_name) {
+                record.unassign(at);
+            }
+        }
+    }
+
+    /// Adds a WMS event to the record of its shipment.
+    ///
+    /// # Arguments
+    ///
+    /// * `event` - The `WmsEvent` to add.
+    /// * `at` - When the event occurred.
+    pub async fn record_wms_event(&self, event: &WmsEvent, at: NaiveDateTime) {
+        if event.shipment_id.is_empty() {
+            return;
+        }
+        let mut shipments = self.shipments.write().await;
+        shipments.entry((event.plant.clone(), event.shipment_id.clone()))
+            .or_insert_with(|| ShipmentRecord::new(&event.plant, &event.shipment_id, at))
+            .add_event(event, at);
+    }
+
+    /// Returns the record of a shipment, if it is known.
+    ///
+    /// # Arguments
+    ///
+    /// * `plant_id` - The plant of the shipment.
+    /// * `shipment_id` - The ID of the shipment.
+    pub async fn get(&self, plant_id: &str, shipment_id: &str) -> Option<ShipmentRecord> {
+        self.shipments.read().await.get(&(plant_id.to_string(), shipment_id.to_string())).cloned()
+    }
+
+    /// Returns the records of every shipment currently assigned to a door of a plant.
+    ///
+    /// # Arguments
+    ///
+    /// * `plant_id` - The plant of the shipments.
+    pub async fn assigned_shipments(&self, plant_id: &str) -> Vec<ShipmentRecord> {
+        self.shipments.read().await.values()
+            .filter(|record| record.plant_id == plant_id && record.current_door().is_some())
+            .cloned()
+            .collect()
+    }
+
+    /// Forgets the shipments that are no longer assigned to a door and were last updated before the retention period.
+    ///
+    /// # Arguments
+    ///
+    /// * `now` - The current time.
+    ///
+    /// # Returns
+    ///
+    /// The number of shipments forgotten.
+    pub async fn prune(&self, now: NaiveDateTime) -> usize {
+        let mut shipments = self.shipments.write().await;
+        let before = shipments.len();
+        shipments.retain(|_, record| record.current_door().is_some() || now.signed_duration_since(record.last_updated) < self.retention);
+        before - shipments.len()
+    }
+}
@@ -1,18 +1,25 @@
 use std::sync::Arc;
 use tokio::sync::{mpsc, Mutex};
-use tokio::sync::mpsc::Receiver;
-use log::{info, error};
-use crate::config::Settings;
+use std::path::Path;
+use chrono::Local;
+use log::{info, error, warn};
+use crate::config::{OverflowPolicy, Settings, SubscriberSettings};
+use crate::event_handling::{EventBus, EventSubscription};
 use crate::errors::{DockManagerError, DockManagerResult};
-use crate::models::{DockDoorEvent, PlcVal, WmsDoorStatus, DbInsert, WmsEvent, DockDoor};
+use crate::models::{DockDoorEvent, DoorReadStatus, PlcVal, WmsDoorStatus, DbInsert, WmsEvent, DockDoor, PlantOccupancy, ShipmentRecord, TrailerVisit};
 use crate::state_management::door_state_repository::DoorStateRepository;
+use crate::state_management::door_snapshot::DoorSnapshot;
+use crate::state_management::event_journal::{EventJournal, JournalEntry, JournalReplayer};
 use crate::state_management::command_processor::CommandProcessor;
 use crate::state_management::sensor_data_processor::SensorDataProcessor;
 use crate::state_management::wms_data_processor::WmsDataProcessor;
 use crate::state_management::database_event_manager::DatabaseEventManager;
 use crate::state_management::event_dispatcher::EventDispatcher;
+use crate::state_management::reconciler::WmsPlcReconciler;
+use crate::state_management::visit_tracker::TrailerVisitTracker;
+use crate::state_management::shipment_registry::ShipmentRegistry;
 use crate::state_management::state_manager_lifecycle::StateManagerLifecycle;
-use crate::services::db::DatabaseService;
+use crate::services::db_outbox::DbOutbox;
 
 /// Manages the overall state of the dock door monitoring system.
 #[derive(Clone)]
@@ -23,36 +30,60 @@ pub struct DockDoorStateManager {
     wms_processor: Arc<WmsDataProcessor>,
     db_event_manager: Arc<DatabaseEventManager>,
     event_dispatcher: Arc<EventDispatcher>,
+    event_bus: Arc<EventBus>,
+    reconciler: Arc<WmsPlcReconciler>,
+    shipment_registry: Arc<ShipmentRegistry>,
     lifecycle: Arc<StateManagerLifecycle>,
+    journal: Option<Arc<EventJournal>>,
 }
 
 impl DockDoorStateManager {
     /// Creates a new `DockDoorStateManager`.
     ///
+    /// If a snapshot path is configured, the door states saved there are restored, unless the snapshot
+    /// is older than the configured maximum age. If the journal is to be replayed on startup, the events
+    /// journaled after the last one the snapshot reflects, or all of them without one, are then replayed.
+    ///
     /// # Arguments
     ///
     /// * `settings` - The application settings.
-    /// * `db_service` - A reference to the database service.
+    /// * `db_outbox` - A reference to the outbox database events are written through.
+    /// * `visit_outbox` - A reference to the outbox completed trailer visits are stored through.
+    /// * `journal` - The journal the processed events are recorded in, if enabled, whose position is saved in snapshots.
     ///
     /// # Returns
     ///
-    /// A new instance of `DockDoorStateManager`.
-    pub async fn new(settings: &Settings, db_service: Arc<DatabaseService>) -> (Self, Receiver<DockDoorEvent>) {
+    /// A new instance of `DockDoorStateManager`, and the event handler's subscription to its event bus.
+    pub async fn new(settings: &Settings, db_outbox: Arc<DbOutbox>, visit_outbox: Arc<DbOutbox<TrailerVisit>>, journal: Option<Arc<EventJournal>>) -> (Self, EventSubscription) {
         let door_repository = Arc::new(DoorStateRepository::new());
         door_repository.initialize_from_settings(settings)
             .await
             .expect("Failed to initialize doors from settings");
+        let snapshot_sequence = match &settings.snapshot.path {
+            Some(path) => Self::restore_snapshot(&door_repository, path, settings.snapshot.max_age_secs).await,
+            None => None,
+        };
+        if let (Some(path), true) = (&settings.journal.path, settings.journal.replay_on_startup) {
+            Self::replay_journal(&door_repository, path, settings.journal.max_files, snapshot_sequence).await;
+        }
         let (_command_sender, command_receiver) = mpsc::channel(100);
-        let (event_sender, event_receiver) = mpsc::channel(1000);
+        let event_bus = Arc::new(EventBus::new(settings.event_bus.clone()));
+        let event_subscription = event_bus.subscribe("event_handler", SubscriberSettings {
+            capacity: 1000,
+            overflow: OverflowPolicy::Block,
+        });
 
         let db_event_manager = Arc::new(DatabaseEventManager::new(settings.batch_size,
-            Arc::clone(&db_service)
+            Arc::clone(&db_outbox)
         ));
 
-        let event_dispatcher = Arc::new(EventDispatcher::new(event_sender));
+        let visit_tracker = Arc::new(TrailerVisitTracker::new(Arc::clone(&door_repository), visit_outbox));
+        let event_dispatcher = Arc::new(EventDispatcher::new(Arc::clone(&event_bus), visit_tracker));
 
         let sensor_processor = Arc::new(SensorDataProcessor::new(Arc::clone(&door_repository)));
-        let wms_processor = Arc::new(WmsDataProcessor::new(Arc::clone(&door_repository)));
+        let shipment_registry = Arc::new(ShipmentRegistry::new(&settings.shipment_registry));
+        let wms_processor = Arc::new(WmsDataProcessor::new(Arc::clone(&door_repository), Arc::clone(&shipment_registry)));
+        let reconciler = Arc::new(WmsPlcReconciler::new(Arc::clone(&door_repository), &settings.reconciler));
 
         let command_processor = Arc::new(Mutex::new(CommandProcessor::new(
             command_receiver,
@@ -72,8 +103,72 @@ impl DockDoorStateManager {
             wms_processor,
             db_event_manager,
             event_dispatcher,
+            event_bus,
+            reconciler,
+            shipment_registry,
             lifecycle,
-        }, event_receiver)
+            journal,
+        }, event_subscription)
+    }
+
+    /// Restores the door states from a snapshot file, if it exists and is recent enough.
+    ///
+    /// # Arguments
+    ///
+    /// * `door_repository` - The repository to restore the door states into.
+    /// * `path` - The snapshot file.
+    /// * `max_age_secs` - The age in seconds beyond which the snapshot is ignored.
+    ///
+    /// # Returns
+    ///
+    /// The sequence number of the last journaled event the restored snapshot reflects, or `None` if no snapshot was restored.
+    async fn restore_snapshot(door_repository: &DoorStateRepository, path: &Path, max_age_secs: u64) -> Option<u64> {
+        match DoorSnapshot::read_from(path).await {
+            Ok(Some(snapshot)) if snapshot.age_secs() > max_age_secs as i64 => {
+                warn!("Ignoring door snapshot taken at {}, it is older than {} seconds", snapshot.taken_at, max_age_secs);
+                None
+            },
+            Ok(Some(snapshot)) => {
+                let (taken_at, journal_sequence) = (snapshot.taken_at, snapshot.journal_sequence);
+                let restored = door_repository.restore_snapshot(snapshot).await;
+                info!("Restored {} doors from the snapshot taken at {}, at journal sequence {}", restored, taken_at, journal_sequence);
+                Some(journal_sequence)
+            },
+            Ok(None) => {
+                info!("No door snapshot found at {}, starting with fresh door states", path.display());
+                None
+            },
+            Err(e) => {
+                error!("Failed to read door snapshot from {}: {:?}", path.display(), e);
+                None
+            },
+        }
+    }
+
+    /// Replays the event journal into the door states.
+    ///
+    /// # Arguments
+    ///
+    /// * `door_repository` - The repository holding the door states to rebuild.
+    /// * `path` - The journal file.
+    /// * `max_files` - The number of rotated journal files kept next to it.
+    /// * `after_sequence` - Only events with a greater sequence number are replayed, if set.
+    async fn replay_journal(door_repository: &Arc<DoorStateRepository>, path: &Path, max_files: usize, after_sequence: Option<u64>) {
+        let entries = match EventJournal::read_entries(path, max_files).await {
+            Ok(entries) => entries,
+            Err(e) => {
+                error!("Failed to read event journal from {}: {:?}", path.display(), e);
+                return;
+            },
+        };
+        let entries: Vec<JournalEntry> = entries.into_iter()
+            .filter(|entry| after_sequence.is_none_or(|after| entry.sequence > after))
+            .collect();
+
+        match JournalReplayer::new(Arc::clone(door_repository)).replay(&entries, None).await {
+            Ok(replayed) => info!("Replayed {} events from the event journal", replayed),
+            Err(e) => error!("Failed to replay event journal from {}: {:?}", path.display(), e),
+        }
     }
 
     /// Runs the main loop of the state manager.
@@ -109,8 +204,13 @@ impl DockDoorStateManager {
     }
 
     /// Handles the shutdown process for the state manager.
-    async fn shutdown(&self) {
+    ///
+    /// The event bus is closed, so that its subscribers stop once they have received the events
+    /// already published, and the pending database events are flushed.
+    pub async fn shutdown(&self) {
         info!("Initiating shutdown process");
+        self.lifecycle.trigger_shutdown();
+        self.event_bus.close();
 
         // Perform cleanup
         if let Err(e) = self.lifecycle.cleanup().await {
@@ -133,6 +233,20 @@ impl DockDoorStateManager {
         self.sensor_processor.process_sensor_updates(sensor_values).await
     }
 
+    /// Updates the PLC health of the polled doors and generates connectivity events.
+    ///
+    /// # Arguments
+    ///
+    /// * `statuses` - The read status of every door that was polled.
+    /// * `offline_after_failures` - The number of failed cycles in a row after which a door is reported offline.
+    ///
+    /// # Returns
+    ///
+    /// A `DockManagerResult` containing the generated `DoorOffline` and `DoorOnline` events.
+    pub async fn update_plc_health(&self, statuses: Vec<DoorReadStatus>, offline_after_failures: u32) -> DockManagerResult<Vec<DockDoorEvent>> {
+        self.sensor_processor.process_health_updates(statuses, offline_after_failures).await
+    }
+
     /// Updates the state based on WMS data and generates corresponding events.
     ///
     /// # Arguments
@@ -159,6 +273,51 @@ impl DockDoorStateManager {
         self.wms_processor.process_wms_events(wms_events).await
     }
 
+    /// Cross-checks the WMS state of every door against its sensors and generates discrepancy events.
+    ///
+    /// # Returns
+    ///
+    /// The `WmsPlcDiscrepancy` events of the contradictions that outlasted their grace period or were resolved.
+    pub async fn reconcile(&self) -> Vec<DockDoorEvent> {
+        self.reconciler.reconcile(Local::now().naive_local()).await
+    }
+
+    /// Returns the history of a shipment across the doors of its plant.
+    ///
+    /// # Arguments
+    ///
+    /// * `plant_id` - The plant of the shipment.
+    /// * `shipment_id` - The ID of the shipment.
+    ///
+    /// # Returns
+    ///
+    /// The `ShipmentRecord` of the shipment, or `None` if it is not known.
+    pub async fn get_shipment(&self, plant_id: &str, shipment_id: &str) -> Option<ShipmentRecord> {
+        self.shipment_registry.get(plant_id, shipment_id).await
+    }
+
+    /// Returns how many doors of a plant are in each state right now.
+    ///
+    /// # Arguments
+    ///
+    /// * `plant_id` - The plant of the doors.
+    ///
+    /// # Returns
+    ///
+    /// The `PlantOccupancy` of the plant, or `None` if it has no doors.
+    pub async fn get_plant_occupancy(&self, plant_id: &str) -> Option<PlantOccupancy> {
+        self.door_repository.plant_occupancy(plant_id).await
+    }
+
+    /// Returns how many doors of every plant are in each state right now.
+    ///
+    /// # Returns
+    ///
+    /// The `PlantOccupancy` of every plant.
+    pub async fn get_occupancy(&self) -> Vec<PlantOccupancy> {
+        self.door_repository.all_occupancy().await
+    }
+
     /// Dispatches a single event.
     ///
     /// # Arguments
@@ -224,8 +383,86 @@ impl DockDoorStateManager {
         self.db_event_manager.add_event(event).await
     }
 
+    /// Writes a snapshot of all door states to a file.
+    ///
+    /// The journal position is read before the doors are copied, so an event applied while the snapshot is taken
+    /// may be replayed on top of it, but none is missed.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The file to write the snapshot to.
+    ///
+    /// # Returns
+    ///
+    /// A `DockManagerResult` indicating success or failure.
+    pub async fn save_snapshot(&self, path: &Path) -> DockManagerResult<()> {
+        let journal_sequence = self.journal.as_ref().map_or(0, |journal| journal.applied_sequence());
+        self.door_repository.save_snapshot(path, journal_sequence).await
+    }
+
+    /// Returns the bus the dock door events are published on, for other components to subscribe to.
+    pub fn event_bus(&self) -> Arc<EventBus> {
+        Arc::clone(&self.event_bus)
+    }
+
     pub fn get_door_repository(&self) -> Arc<DoorStateRepository> {
         Arc::clone(&self.door_repository)
     }
 
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, NaiveDateTime};
+    use crate::models::{DockUnassignedEvent, TrailerState, TrailerStateChangedEvent};
+    use crate::models::test_support::{self, DOOR_NAME, PLANT_ID};
+
+    fn entry(sequence: u64, recorded_at: NaiveDateTime, event: DockDoorEvent) -> String {
+        serde_json::to_string(&JournalEntry { sequence, recorded_at, event }).unwrap()
+    }
+
+    #[tokio::test]
+    async fn replay_skips_events_the_snapshot_already_reflects() {
+        let dir = std::env::temp_dir().join(format!("dockmonitor-replay-{}", std::process::id()));
+        tokio::fs::create_dir_all(&dir).await.unwrap();
+        let (snapshot_path, journal_path) = (dir.join("doors.json"), dir.join("events.jsonl"));
+
+        // The door was saved after event 1 unassigned its shipment
+        let plant = test_support::plant();
+        let mut saved = test_support::door(&plant);
+        saved.assigned_shipment.previous_shipment = Some("S0".to_string());
+        let snapshot = DoorSnapshot::new(vec![saved], 1);
+        snapshot.write_to(&snapshot_path).await.unwrap();
+
+        // The clock was set back, so both events were recorded after the snapshot was taken
+        let recorded_at = snapshot.taken_at + Duration::minutes(5);
+        let journal = [
+            entry(1, recorded_at, DockDoorEvent::DockUnassigned(DockUnassignedEvent {
+                plant_id: PLANT_ID.to_string(),
+                dock_name: DOOR_NAME.to_string(),
+                shipment_id: "S0".to_string(),
+                timestamp: recorded_at,
+            })),
+            entry(2, recorded_at, DockDoorEvent::TrailerStateChanged(TrailerStateChangedEvent {
+                plant_id: PLANT_ID.to_string(),
+                dock_name: DOOR_NAME.to_string(),
+                old_state: TrailerState::Undocked,
+                new_state: TrailerState::Docked,
+                timestamp: recorded_at,
+            })),
+        ];
+        tokio::fs::write(&journal_path, journal.join("\n")).await.unwrap();
+
+        let repository = Arc::new(DoorStateRepository::new());
+        repository.update_door(PLANT_ID, test_support::door(&plant)).await.unwrap();
+        let snapshot_sequence = DockDoorStateManager::restore_snapshot(&repository, &snapshot_path, 3600).await;
+        DockDoorStateManager::replay_journal(&repository, &journal_path, 0, snapshot_sequence).await;
+        tokio::fs::remove_dir_all(&dir).await.unwrap();
+
+        assert_eq!(snapshot_sequence, Some(1));
+        let door = repository.get_door_state(PLANT_ID, DOOR_NAME).await.unwrap();
+        // Replaying event 1 again would have moved the unassigned shipment out of `previous_shipment`
+        assert_eq!(door.assigned_shipment.previous_shipment.as_deref(), Some("S0"));
+        assert!(door.current_visit.is_some(), "event 2 should have been replayed");
+    }
+}
@@ -0,0 +1,49 @@
+use std::sync::Arc;
+use log::error;
+use crate::models::{DockDoorEvent, TrailerVisit};
+use crate::services::db_outbox::DbOutbox;
+use crate::state_management::door_state_repository::DoorStateRepository;
+
+/// Follows the trailer visit of every door through the dispatched events and stores the completed visits.
+///
+/// The open visit of a door is kept on the door itself, so it is saved in snapshots and rebuilt by journal replay.
+/// Completed visits are put in an outbox and written by its retry, so dispatching never waits for the database.
+pub struct TrailerVisitTracker {
+    /// Repository holding the doors and their open visits.
+    door_repository: Arc<DoorStateRepository>,
+    /// The outbox the completed visits are stored through.
+    visit_outbox: Arc<DbOutbox<TrailerVisit>>,
+}
+
+impl TrailerVisitTracker {
+    /// Creates a new `TrailerVisitTracker`.
+    ///
+    /// # Arguments
+    ///
+    /// * `door_repository` - The repository holding the doors.
+    /// * `visit_outbox` - The outbox to store the completed visits through.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `TrailerVisitTracker`.
+    pub fn new(door_repository: Arc<DoorStateRepository>, visit_outbox: Arc<DbOutbox<TrailerVisit>>) -> Self {
+        Self { door_repository, visit_outbox }
+    }
+
+    /// Records an event in the visit of its door, putting the visit in the outbox if the event completed it.
+    ///
+    /// # Arguments
+    ///
+    /// * `event` - The `DockDoorEvent` being dispatched.
+    pub async fn track(&self, event: &DockDoorEvent) {
+        let completed = self.door_repository
+            .modify_door(event.get_plant_id(), event.get_dock_name(), |door| door.track_visit(event))
+            .await
+            .flatten();
+        if let Some(visit) = completed {
+            if let Err(e) = self.visit_outbox.push(vec![visit]).await {
+                error!("Failed to keep a trailer visit in the outbox file, it is only kept in memory: {:?}", e);
+            }
+        }
+    }
+}
@@ -1,15 +1,18 @@
 use std::str::FromStr;
-use crate::models::{WmsDoorStatus, DockDoorEvent, DoorState, DockDoor, ShipmentAssignedEvent, ShipmentUnassignedEvent, LoadingStatus, LoadingStatusChangedEvent, DoorStateChangedEvent, WmsEvent};
+use crate::models::{WmsDoorStatus, DockDoorEvent, DoorState, DockDoor, ShipmentAssignedEvent, ShipmentUnassignedEvent, LoadingStatus, LoadingStatusChangedEvent, TransitionCause, WmsEvent};
 use crate::errors::{DockManagerError, DockManagerResult};
 use crate::state_management::door_state_repository::DoorStateRepository;
+use crate::state_management::shipment_registry::ShipmentRegistry;
 use std::sync::Arc;
 use chrono::Local;
-use log::info;
+use log::{debug, info};
 
 /// Processes WMS (Warehouse Management System) data updates for the dock monitoring system.
 pub struct WmsDataProcessor {
     /// Repository for managing dock door states.
     door_repository: Arc<DoorStateRepository>,
+    /// Registry following the shipments across the doors they are assigned to.
+    shipment_registry: Arc<ShipmentRegistry>,
 }
 
 impl WmsDataProcessor {
@@ -18,13 +21,15 @@ impl WmsDataProcessor {
     /// # Arguments
     ///
     /// * `door_repository` - A reference to the `DoorStateRepository` for managing door states.
+    /// * `shipment_registry` - A reference to the `ShipmentRegistry` the assignments and WMS events are recorded in.
     ///
     /// # Returns
     ///
     /// A new instance of `WmsDataProcessor`.
-    pub fn new(door_repository: Arc<DoorStateRepository>) -> Self {
+    pub fn new(door_repository: Arc<DoorStateRepository>, shipment_registry: Arc<ShipmentRegistry>) -> Self {
         Self {
             door_repository,
+            shipment_registry,
         }
     }
 
@@ -46,11 +51,19 @@ impl WmsDataProcessor {
             events.extend(door_events);
         }
 
+        let pruned = self.shipment_registry.prune(Local::now().naive_local()).await;
+        if pruned > 0 {
+            debug!("Forgot {} shipments past their retention period", pruned);
+        }
+
         Ok(events)
     }
 
     /// Processes a single WMS update for a specific door.
     ///
+    /// The update is applied to the door in place through `DoorStateRepository::modify_door`, and the shipment
+    /// registry is told about the assignment afterwards, so the door is never written back from a stale copy.
+    ///
     /// # Arguments
     ///
     /// * `wms_status` - The `WmsDoorStatus` containing the WMS update for a door.
@@ -60,14 +73,45 @@ impl WmsDataProcessor {
     /// A Result containing a vector of `DockDoorEvent`s generated from the WMS update,
     /// or a `DockManagerError` if processing fails.
     async fn process_single_wms_update(&self, wms_status: &WmsDoorStatus) -> Result<Vec<DockDoorEvent>, DockManagerError> {
-        let mut events = Vec::new();
+        let new_loading_status = LoadingStatus::from_str(&wms_status.loading_status)
+            .map_err(|_| DockManagerError::ConfigError(format!("Invalid loading status: {}", wms_status.loading_status)))?;
 
-        let mut door = self.door_repository.get_door_state(wms_status.plant.as_str(),&wms_status.dock_name).await
+        let (events, replaced_shipment) = self.door_repository
+            .modify_door(&wms_status.plant, &wms_status.dock_name, |door| self.apply_wms_status(door, wms_status, new_loading_status))
+            .await
             .ok_or_else(|| DockManagerError::DoorNotFound(wms_status.dock_name.clone()))?;
 
+        if let Some(old_shipment) = &replaced_shipment {
+            self.shipment_registry.unassign(&wms_status.plant, old_shipment, &wms_status.dock_name, Local::now().naive_local()).await;
+        }
+        // Report the current assignment on every poll, so the registry also learns of the ones made before a restart
+        if let Some(shipment_id) = &wms_status.assigned_shipment {
+            self.shipment_registry.assign(&wms_status.plant, shipment_id, &wms_status.dock_name, Local::now().naive_local()).await;
+        }
+
+        Ok(events)
+    }
+
+    /// Applies a WMS update to a door.
+    ///
+    /// # Arguments
+    ///
+    /// * `door` - A mutable reference to the `DockDoor` being updated.
+    /// * `wms_status` - The `WmsDoorStatus` containing the WMS update for the door.
+    /// * `new_loading_status` - The loading status parsed from the update.
+    ///
+    /// # Returns
+    ///
+    /// The `DockDoorEvent`s generated from the update, and the shipment the door was assigned before, if the
+    /// update replaced it.
+    fn apply_wms_status(&self, door: &mut DockDoor, wms_status: &WmsDoorStatus, new_loading_status: LoadingStatus) -> (Vec<DockDoorEvent>, Option<String>) {
+        let mut events = Vec::new();
+        let mut replaced_shipment = None;
+
         // Update shipment assignment
         if door.assigned_shipment.current_shipment != wms_status.assigned_shipment {
             let old_shipment = door.assigned_shipment.current_shipment.clone();
+            replaced_shipment = old_shipment.clone();
             door.assigned_shipment.current_shipment = wms_status.assigned_shipment.clone();
             door.assigned_shipment.assignment_dttm = Some(Local::now().naive_local());
             door.consolidated.dock_assignment = Some(Local::now().naive_local());
@@ -95,9 +139,6 @@ impl WmsDataProcessor {
         }
 
         // Update loading status
-        let new_loading_status = LoadingStatus::from_str(&wms_status.loading_status)
-            .map_err(|_| DockManagerError::ConfigError(format!("Invalid loading status: {}", wms_status.loading_status)))?;
-
         if door.loading_status.loading_status != new_loading_status {
             events.push(DockDoorEvent::LoadingStatusChanged(LoadingStatusChangedEvent {
                 plant_id: wms_status.plant.clone(),
@@ -127,22 +168,13 @@ impl WmsDataProcessor {
         }
 
         // Update door state based on WMS data
-        let new_door_state = self.determine_door_state(&door, wms_status);
-        if door.door_state != new_door_state {
-            events.push(DockDoorEvent::DoorStateChanged(DoorStateChangedEvent {
-                plant_id: wms_status.plant.clone(),
-                dock_name: door.dock_name.clone(),
-                old_state: door.door_state,
-                new_state: new_door_state,
-                timestamp: chrono::Local::now().naive_local(),
-            }));
-            door.door_state = new_door_state;
+        let new_door_state = self.determine_door_state(door, wms_status);
+        let cause = TransitionCause::WmsStatus { loading_status: wms_status.loading_status.clone() };
+        if let Some(event) = door.transition_door_state(new_door_state, cause, chrono::Local::now().naive_local()) {
+            events.push(event);
         }
 
-        // Update the door in the repository
-        self.door_repository.update_door(door.plant_id.clone().as_str(), door).await?;
-
-        Ok(events)
+        (events, replaced_shipment)
     }
 
     /// Determines the appropriate door state based on WMS data and current door state.
@@ -173,32 +205,14 @@ impl WmsDataProcessor {
 
         for wms_event in wms_events {
             info!("Converting WMS Event: {:?}", wms_event);
-            let mut door = self.door_repository.get_door_state(&wms_event.plant, &wms_event.dock_name).await
-                .ok_or_else(|| DockManagerError::DoorNotFound(wms_event.dock_name.clone()))?;
-
             // Convert WmsEvent to DockDoorEvent
             let dock_door_event = DockDoorEvent::from_wms_event(wms_event.clone());
             info!("Converted WMS Event: {:?}", dock_door_event);
             // Update door state based on WMS event
-            if wms_event.message_type == "DOCK_ASSIGNMENT" {
-                door.consolidated.dock_assignment = Some(wms_event.log_dttm.unwrap_or_else(|| chrono::Local::now().naive_local()));
-            }
-
-            if wms_event.message_type == "STARTED_SHIPMENT" {
-                door.consolidated.shipment_started_dttm = Some(wms_event.log_dttm.unwrap_or_else(|| chrono::Local::now().naive_local()));
-            }
-
-            if wms_event.message_type == "LGV_START_LOADING" {
-                door.consolidated.lgv_loading_started = Some(wms_event.log_dttm.unwrap_or_else(|| chrono::Local::now().naive_local()));
-            }
-
-            if wms_event.message_type == "FIRST_DROP" {
-                door.consolidated.lgv_loading_started = Some(wms_event.log_dttm.unwrap_or_else(|| chrono::Local::now().naive_local()));
-            }
-
+            self.door_repository.modify_door(&wms_event.plant, &wms_event.dock_name, |door| Self::record_wms_event_time(door, &dock_door_event)).await
+                .ok_or_else(|| DockManagerError::DoorNotFound(wms_event.dock_name.clone()))?;
 
-            // Update the door in the repository
-            self.door_repository.update_door(&wms_event.plant, door).await?;
+            self.shipment_registry.record_wms_event(&wms_event, wms_event.log_dttm.unwrap_or_else(|| Local::now().naive_local())).await;
 
             events.push(dock_door_event);
         }
@@ -206,4 +220,47 @@ impl WmsDataProcessor {
         Ok(events)
     }
 
+    /// Records the time of a WMS event in the door's consolidated data.
+    ///
+    /// # Arguments
+    ///
+    /// * `door` - A mutable reference to the `DockDoor` the event occurred at.
+    /// * `event` - The `DockDoorEvent` converted from the WMS event.
+    fn record_wms_event_time(door: &mut DockDoor, event: &DockDoorEvent) {
+        match event {
+            DockDoorEvent::DockAssigned(e) => door.consolidated.dock_assignment = Some(e.timestamp),
+            DockDoorEvent::ShipmentStarted(e) => door.consolidated.shipment_started_dttm = Some(e.base_event.timestamp),
+            DockDoorEvent::LgvStartLoading(e) => door.consolidated.lgv_loading_started = Some(e.base_event.timestamp),
+            DockDoorEvent::FirstDrop(e) => door.consolidated.lgv_loading_started = Some(e.base_event.timestamp),
+            _ => {},
+        }
+    }
+
+    /// Replays a journaled event, applying the WMS data it carries to the door as the original update did.
+    ///
+    /// # Arguments
+    ///
+    /// * `door` - A mutable reference to the `DockDoor` being rebuilt.
+    /// * `event` - The journaled `DockDoorEvent`.
+    pub fn replay_event(&self, door: &mut DockDoor, event: &DockDoorEvent) {
+        match event {
+            DockDoorEvent::ShipmentAssigned(e) => {
+                door.assigned_shipment.current_shipment = Some(e.shipment_id.clone());
+                door.assigned_shipment.assignment_dttm = Some(e.timestamp);
+                door.consolidated.dock_assignment = Some(e.timestamp);
+            },
+            DockDoorEvent::ShipmentUnassigned(_) => {
+                door.assigned_shipment.current_shipment = None;
+                door.assigned_shipment.assignment_dttm = None;
+                door.consolidated.dock_assignment = None;
+            },
+            DockDoorEvent::LoadingStatusChanged(e) => {
+                door.loading_status.previous_loading_status = door.loading_status.loading_status;
+                door.loading_status.previous_state_dttm = door.loading_status.current_state_dttm;
+                door.loading_status.loading_status = e.new_status;
+                door.loading_status.current_state_dttm = Some(e.timestamp);
+            },
+            _ => Self::record_wms_event_time(door, event),
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,33 @@
+use std::time::Duration;
+use async_trait::async_trait;
+use chrono::{Local, NaiveDateTime};
+
+/// A source of the current time, so that code reading the time or waiting can be driven by something else
+/// than the system clock.
+#[async_trait]
+pub trait Clock: Send + Sync {
+    /// Returns the current local time.
+    fn now(&self) -> NaiveDateTime;
+
+    /// Waits for the given duration without blocking the executor.
+    ///
+    /// # Arguments
+    ///
+    /// * `duration` - How long to wait.
+    async fn sleep(&self, duration: Duration);
+}
+
+/// The `Clock` of the system, reading the local time and sleeping on the tokio timer.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+#[async_trait]
+impl Clock for SystemClock {
+    fn now(&self) -> NaiveDateTime {
+        Local::now().naive_local()
+    }
+
+    async fn sleep(&self, duration: Duration) {
+        tokio::time::sleep(duration).await;
+    }
+}
@@ -1,3 +1,6 @@
+pub mod clock;
+pub mod shutdown;
+
 use chrono::Duration;
 
 
@@ -0,0 +1,238 @@
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::Duration;
+use log::{info, warn};
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tokio::time::{Instant, Interval};
+
+/// A handle background tasks use to learn that the service is shutting down.
+#[derive(Clone)]
+pub struct ShutdownSignal {
+    /// Receives `true` once shutdown has been triggered.
+    receiver: watch::Receiver<bool>,
+}
+
+impl ShutdownSignal {
+    /// Returns `true` if shutdown has been triggered.
+    pub fn is_triggered(&self) -> bool {
+        *self.receiver.borrow()
+    }
+
+    /// Waits until shutdown is triggered.
+    pub async fn wait(&mut self) {
+        // An error means the coordinator is gone, which only happens once the service is exiting
+        let _ = self.receiver.wait_for(|triggered| *triggered).await;
+    }
+
+    /// Waits for the next tick of an interval, unless shutdown is triggered first.
+    ///
+    /// Periodic tasks loop on this instead of `Interval::tick`, so that a cycle which has started
+    /// runs to completion and no new cycle starts once shutdown has been triggered.
+    ///
+    /// # Arguments
+    ///
+    /// * `interval` - The interval of the task.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the interval ticked, `false` if the task should stop.
+    pub async fn tick(&mut self, interval: &mut Interval) -> bool {
+        if self.is_triggered() {
+            return false;
+        }
+        tokio::select! {
+            _ = interval.tick() => !self.is_triggered(),
+            _ = self.wait() => false,
+        }
+    }
+
+    /// Sleeps for the given duration, unless shutdown is triggered first.
+    ///
+    /// # Arguments
+    ///
+    /// * `duration` - How long to sleep.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the full duration has passed, `false` if the task should stop.
+    pub async fn sleep(&mut self, duration: Duration) -> bool {
+        if self.is_triggered() {
+            return false;
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(duration) => !self.is_triggered(),
+            _ = self.wait() => false,
+        }
+    }
+}
+
+/// Signals the background tasks of the service to stop and waits for them to finish.
+///
+/// Shutdown happens in two stages. `shutdown` stops the tasks started with `spawn`, such as the polling tasks and
+/// the checks the rules run in the background. `drain` then stops the tasks started with `spawn_drain`, which
+/// handle the work the others hand over to them and so keep running until that work has been handed over.
+pub struct ShutdownCoordinator {
+    /// Sends `true` when shutdown is triggered.
+    sender: watch::Sender<bool>,
+    /// Sends `true` when the draining tasks are to finish their remaining work and stop.
+    drain_sender: watch::Sender<bool>,
+    /// The running tasks, with the names they are reported by.
+    tasks: Mutex<Vec<(String, JoinHandle<()>)>>,
+    /// The running draining tasks, with the names they are reported by.
+    drain_tasks: Mutex<Vec<(String, JoinHandle<()>)>>,
+}
+
+impl ShutdownCoordinator {
+    /// Creates a new `ShutdownCoordinator` without tasks.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of `ShutdownCoordinator`.
+    pub fn new() -> Self {
+        let (sender, _) = watch::channel(false);
+        let (drain_sender, _) = watch::channel(false);
+        Self {
+            sender,
+            drain_sender,
+            tasks: Mutex::new(Vec::new()),
+            drain_tasks: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Returns a signal that tells a task when to stop.
+    pub fn signal(&self) -> ShutdownSignal {
+        ShutdownSignal { receiver: self.sender.subscribe() }
+    }
+
+    /// Returns a signal that tells a draining task when to finish its remaining work and stop.
+    pub fn drain_signal(&self) -> ShutdownSignal {
+        ShutdownSignal { receiver: self.drain_sender.subscribe() }
+    }
+
+    /// Returns `true` if shutdown has been triggered.
+    pub fn is_triggered(&self) -> bool {
+        *self.sender.borrow()
+    }
+
+    /// Spawns a task that the coordinator waits for on shutdown.
+    ///
+    /// The task is expected to stop by itself soon after its `ShutdownSignal` has been triggered. Tasks may be
+    /// spawned while shutting down; they are waited for as well.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name the task is reported by.
+    /// * `task` - The task to spawn.
+    pub fn spawn<F>(&self, name: &str, task: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        Self::register(&self.tasks, name, tokio::spawn(task));
+    }
+
+    /// Spawns a task that the coordinator waits for once the other tasks have stopped.
+    ///
+    /// The task is expected to handle what it receives until its drain signal is triggered, then finish the
+    /// work it has already received and stop.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name the task is reported by.
+    /// * `task` - The task to spawn.
+    pub fn spawn_drain<F>(&self, name: &str, task: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        Self::register(&self.drain_tasks, name, tokio::spawn(task));
+    }
+
+    /// Adds a task to a list, forgetting the tasks of the list that already finished.
+    fn register(tasks: &Mutex<Vec<(String, JoinHandle<()>)>>, name: &str, handle: JoinHandle<()>) {
+        let mut tasks = tasks.lock().unwrap();
+        tasks.retain(|(_, handle)| !handle.is_finished());
+        tasks.push((name.to_string(), handle));
+    }
+
+    /// Signals every task to stop.
+    pub fn trigger(&self) {
+        info!("Triggering shutdown of {} background tasks", self.tasks.lock().unwrap().len());
+        self.sender.send_replace(true);
+    }
+
+    /// Signals every task to stop and waits for them to finish.
+    ///
+    /// Tasks still running at the deadline are aborted. The draining tasks keep running until `drain`.
+    ///
+    /// # Arguments
+    ///
+    /// * `deadline` - When to stop waiting for the tasks.
+    ///
+    /// # Returns
+    ///
+    /// `true` if every task finished before the deadline, `false` if some had to be aborted.
+    pub async fn shutdown(&self, deadline: Instant) -> bool {
+        self.trigger();
+        let stopped = Self::wait_for(&self.tasks, deadline).await;
+        if stopped {
+            info!("All background tasks stopped");
+        }
+        stopped
+    }
+
+    /// Signals the draining tasks to finish their remaining work and waits for them, after waiting for the
+    /// tasks spawned since `shutdown`.
+    ///
+    /// Tasks still running at the deadline are aborted.
+    ///
+    /// # Arguments
+    ///
+    /// * `deadline` - When to stop waiting for the tasks.
+    ///
+    /// # Returns
+    ///
+    /// `true` if every task finished before the deadline, `false` if some had to be aborted.
+    pub async fn drain(&self, deadline: Instant) -> bool {
+        if !Self::wait_for(&self.tasks, deadline).await {
+            return false;
+        }
+        self.drain_sender.send_replace(true);
+        let drained = Self::wait_for(&self.drain_tasks, deadline).await;
+        if drained {
+            info!("All draining tasks finished their work");
+        }
+        drained
+    }
+
+    /// Waits for the tasks of a list to finish, including the ones added to it meanwhile, aborting the tasks
+    /// still running at the deadline.
+    async fn wait_for(tasks: &Mutex<Vec<(String, JoinHandle<()>)>>, deadline: Instant) -> bool {
+        loop {
+            let mut batch = std::mem::take(&mut *tasks.lock().unwrap());
+            if batch.is_empty() {
+                return true;
+            }
+            let wait_for_batch = async {
+                for (name, handle) in batch.iter_mut() {
+                    if let Err(e) = handle.await {
+                        warn!("Background task {} failed during shutdown: {}", name, e);
+                    }
+                }
+            };
+            if tokio::time::timeout_at(deadline, wait_for_batch).await.is_err() {
+                let late = batch.into_iter().chain(std::mem::take(&mut *tasks.lock().unwrap()));
+                for (name, handle) in late.filter(|(_, handle)| !handle.is_finished()) {
+                    warn!("Background task {} did not stop before the shutdown deadline, aborting it", name);
+                    handle.abort();
+                }
+                return false;
+            }
+        }
+    }
+}
+
+impl Default for ShutdownCoordinator {
+    fn default() -> Self {
+        Self::new()
+    }
+}